@@ -0,0 +1,519 @@
+use crate::browser::bookmarks::{Bookmarks, escape};
+use crate::browser::document::{Block, Document, link};
+use crate::browser::file::human_size;
+use crate::browser::url::{Url, percent_encode};
+use crate::config::keys::{Action, Keymap, key_name};
+use crate::draw::text::{Span, StyledLine, TextAttributes, TextStyle, char_width};
+use crate::net::cache::HttpCache;
+use crate::net::cookies::CookieJar;
+use crate::net::date;
+
+/// What the keys on `about:hyperline` do: the actions whose keys are listed
+/// together, or for what cannot be rebound, the fixed text to list instead.
+const KEYS: &[(&[Action], &str, &str)] = &[
+    (
+        &[Action::Open],
+        "",
+        "open a URL, offering visited pages (Up, Down or Tab to choose)",
+    ),
+    (&[Action::EditUrl], "", "edit the page's URL and open it"),
+    (&[Action::Back], "", "go back"),
+    (&[Action::Forward], "", "go forward"),
+    (&[Action::Reload], "", "reload the page"),
+    (
+        &[Action::ScrollDown, Action::ScrollUp],
+        "",
+        "scroll down and up",
+    ),
+    (
+        &[Action::PageDown, Action::PageUp],
+        "",
+        "scroll a page down and up",
+    ),
+    (
+        &[Action::Top, Action::Bottom],
+        "",
+        "go to the top and bottom",
+    ),
+    (&[], "click", "follow a link or use a form control"),
+    (&[], "mid-click", "open a link in a new tab"),
+    (&[Action::OpenInTab], "", "open a URL in a new tab"),
+    (
+        &[Action::ClosePane, Action::CloseTab],
+        "",
+        "close the pane, or the whole tab",
+    ),
+    (
+        &[Action::SplitRow, Action::SplitColumn],
+        "",
+        "split the pane side by side, or one above the other",
+    ),
+    (
+        &[
+            Action::FocusLeft,
+            Action::FocusDown,
+            Action::FocusUp,
+            Action::FocusRight,
+        ],
+        "",
+        "go to the pane to the left, below, above or to the right",
+    ),
+    (
+        &[
+            Action::ResizeLeft,
+            Action::ResizeDown,
+            Action::ResizeUp,
+            Action::ResizeRight,
+        ],
+        "",
+        "move the divider next to the pane",
+    ),
+    (
+        &[Action::PreviousTab, Action::NextTab],
+        "",
+        "go to the previous and next tab",
+    ),
+    (
+        &[Action::MoveTabLeft, Action::MoveTabRight],
+        "",
+        "move the tab left and right",
+    ),
+    (&[], "1-9", "go to a tab, 9 to the last one"),
+    (&[Action::Bookmark], "", "bookmark the page, with tags"),
+    (&[Action::Bookmarks], "", "show bookmarks"),
+    (
+        &[Action::Hints],
+        "",
+        "label links and controls, then type a label to use one",
+    ),
+    (
+        &[Action::Find],
+        "",
+        "find on the page (Alt-c: match case, Alt-r: regular expression)",
+    ),
+    (
+        &[Action::FindNext, Action::FindPrevious],
+        "",
+        "go to the next and previous match",
+    ),
+    (&[Action::ClearFind], "", "stop showing the matches"),
+    (&[Action::NextTheme], "", "switch to the next theme"),
+    (&[Action::Quit], "", "quit"),
+];
+
+/// The lines of `KEYS`, with the keys bound in `keys`. Keys for one action
+/// are joined with `/`, and actions with `, `.
+fn key_lines(keys: &Keymap) -> Vec<StyledLine> {
+    let bound = |action: &Action| match keys.keys(*action)[..] {
+        [] => "unbound".to_string(),
+        ref bound => bound.iter().map(key_name).collect::<Vec<_>>().join("/"),
+    };
+    let rows = KEYS
+        .iter()
+        .map(|(actions, fixed, text)| match actions.is_empty() {
+            true => (fixed.to_string(), *text),
+            false => (
+                actions.iter().map(bound).collect::<Vec<_>>().join(", "),
+                *text,
+            ),
+        })
+        .collect::<Vec<_>>();
+
+    let width = |keys: &str| keys.chars().map(char_width).sum::<usize>();
+    let column = rows.iter().map(|(keys, _)| width(keys)).max().unwrap_or(0);
+    rows.into_iter()
+        .map(|(keys, text)| {
+            let padding = " ".repeat(column - width(&keys));
+            format!("  {}{}  {}", keys, padding, text).into()
+        })
+        .collect()
+}
+
+/// Pages built into hyperline, served from `about:` URLs, with `keys` listed
+/// on `about:hyperline`.
+pub fn page(url: &Url, keys: &Keymap) -> Option<Document> {
+    let heading = TextStyle {
+        attributes: TextAttributes::BOLD,
+        ..Default::default()
+    };
+
+    match url.path.as_str() {
+        "blank" => Some(Document::new(url.clone(), "", vec![])),
+        "hyperline" => Some(Document::new(
+            url.clone(),
+            "Hyperline",
+            vec![
+                Span::new("Hyperline", heading.clone()).into(),
+                StyledLine::default(),
+                "A web browser for the terminal.".into(),
+                StyledLine::default(),
+                Span::new("Keys", heading).into(),
+                StyledLine::default(),
+            ]
+            .into_iter()
+            .chain(key_lines(keys))
+            .chain([
+                StyledLine::default(),
+                "Keys, the theme and its colors, borders and the homepage can be set in".into(),
+                "config.toml in ~/.config/hyperline, which is read again whenever it is".into(),
+                "saved. The themes are dark, light, solarized and high-contrast.".into(),
+                StyledLine::default(),
+                vec![
+                    Span::plain("See also "),
+                    link("about:bookmarks", "about:bookmarks"),
+                    Span::plain(", "),
+                    link("about:cookies", "about:cookies"),
+                    Span::plain(", "),
+                    link("about:cache", "about:cache"),
+                    Span::plain(" and "),
+                    link("about:blank", "about:blank"),
+                ]
+                .into(),
+            ])
+            .collect(),
+        )),
+        _ => None,
+    }
+}
+
+fn without_query(url: &Url) -> Url {
+    Url {
+        query: None,
+        ..url.clone()
+    }
+}
+
+fn heading(text: &str) -> Block {
+    Block::text(Span::new(
+        text,
+        TextStyle {
+            attributes: TextAttributes::BOLD,
+            ..Default::default()
+        },
+    ))
+}
+
+/// `about:cookies`, which lists every stored cookie with links to delete
+/// them. The links lead back here with a query saying what to delete, which
+/// is only done when `from_itself`. The page's own URL has no query, so it
+/// is safe to reload or return to.
+pub fn cookies(url: &Url, jar: &mut CookieJar, from_itself: bool) -> Document {
+    let query = url.query_pairs();
+    let value = |key: &str| {
+        query
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    };
+
+    match (value("delete"), value("clear")) {
+        _ if !from_itself => {}
+        (Some(name), _) => jar.remove(
+            value("domain").unwrap_or_default(),
+            value("path").unwrap_or_default(),
+            name,
+        ),
+        (None, Some("")) => jar.clear(None),
+        (None, Some(domain)) => jar.clear(Some(domain)),
+        (None, None) => {}
+    }
+
+    let now = date::now();
+    let mut cookies = jar
+        .cookies
+        .iter()
+        .filter(|cookie| !cookie.is_expired(now))
+        .collect::<Vec<_>>();
+    cookies.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+
+    let dim = TextStyle {
+        attributes: TextAttributes::DIM,
+        ..Default::default()
+    };
+    let encode = |text: &str| percent_encode(text, "");
+
+    let mut blocks = vec![heading("Cookies"), Block::text("")];
+    if cookies.is_empty() {
+        blocks.push(Block::text("No cookies are stored."));
+    } else {
+        blocks.push(Block::text(vec![
+            Span::plain(format!("{} cookies. ", cookies.len())),
+            link("Delete all", "about:cookies?clear="),
+        ]));
+    }
+
+    let mut domain = None;
+    for cookie in cookies {
+        if domain != Some(&cookie.domain) {
+            domain = Some(&cookie.domain);
+            blocks.push(Block::text(""));
+            blocks.push(Block::text(vec![
+                Span::new(
+                    cookie.domain.clone(),
+                    TextStyle {
+                        attributes: TextAttributes::BOLD,
+                        ..Default::default()
+                    },
+                ),
+                Span::plain(" "),
+                link(
+                    "delete",
+                    &format!("about:cookies?clear={}", encode(&cookie.domain)),
+                ),
+            ]));
+        }
+
+        let mut value = cookie.value.chars().take(40).collect::<String>();
+        if value.len() < cookie.value.len() {
+            value.push('…');
+        }
+
+        let mut flags = vec![match cookie.expires {
+            Some(expires) => format!("expires {}", date::format(expires)),
+            None => "until exit".to_string(),
+        }];
+        if cookie.secure {
+            flags.push("Secure".to_string());
+        }
+        if cookie.http_only {
+            flags.push("HttpOnly".to_string());
+        }
+        flags.push(format!("SameSite={:?}", cookie.same_site));
+
+        let target = format!(
+            "about:cookies?delete={}&domain={}&path={}",
+            encode(&cookie.name),
+            encode(&cookie.domain),
+            encode(&cookie.path)
+        );
+
+        blocks.push(Block::Text {
+            indent: StyledLine::from("  "),
+            marker: StyledLine::default(),
+            line: StyledLine::new(vec![
+                Span::plain(format!("{}={} ", cookie.name, value)),
+                Span::new(
+                    format!("{} {} ", cookie.path, flags.join(", ")),
+                    dim.clone(),
+                ),
+                link("delete", &target),
+            ]),
+        });
+    }
+
+    Document::from_blocks(without_query(url), "Cookies", blocks)
+}
+
+/// `about:cache`, which shows how much the HTTP cache holds, with a link to
+/// empty it. Like `about:cookies`, it only empties it when `from_itself`.
+pub fn cache(url: &Url, cache: &HttpCache, from_itself: bool) -> Document {
+    let mut blocks = vec![heading("Cache"), Block::text("")];
+
+    if from_itself
+        && url.query_pairs().iter().any(|(key, _)| key == "clear")
+        && let Err(error) = cache.clear()
+    {
+        blocks.push(Block::text(error));
+        blocks.push(Block::text(""));
+    }
+
+    match &cache.dir {
+        Some(dir) => {
+            let (count, size) = cache.usage();
+            blocks.push(Block::text(format!("Directory: {}", dir.display())));
+            blocks.push(Block::text(format!(
+                "Size: {} pages, {} of {}",
+                count,
+                human_size(size),
+                human_size(cache.max_size)
+            )));
+            if cache.offline {
+                blocks.push(Block::text(
+                    "Offline: pages are only loaded from the cache, however old they are.",
+                ));
+            }
+            blocks.push(Block::text(""));
+            blocks.push(Block::text(link("Clear the cache", "about:cache?clear")));
+        }
+        None => blocks.push(Block::text("The cache is turned off.")),
+    }
+
+    Document::from_blocks(without_query(url), "Cache", blocks)
+}
+
+/// `about:bookmarks`, which lists bookmarks with a form to search them and
+/// links to delete them. It is written as HTML so the search box is a real
+/// form, submitted back here as `?q=`. Deleting only happens when `from_itself`,
+/// as for `about:cookies`.
+pub fn bookmarks(url: &Url, bookmarks: &mut Bookmarks, from_itself: bool) -> Document {
+    let query = url.query_pairs();
+    let value = |key: &str| {
+        query
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    };
+    let search = value("q").unwrap_or_default();
+    let encode = |text: &str| escape(&percent_encode(text, ""));
+
+    let mut html = String::from("<title>Bookmarks</title>\n<h1>Bookmarks</h1>\n");
+    if let Some(target) = value("delete")
+        && from_itself
+        && let Err(error) = bookmarks.remove(target)
+    {
+        html.push_str(&format!("<p>{}</p>\n", escape(&error)));
+    }
+
+    html.push_str(&format!(
+        "<form action=\"about:bookmarks\"><input name=\"q\" size=\"40\" value=\"{}\"> \
+         <input type=\"submit\" value=\"Search\"></form>\n",
+        escape(search)
+    ));
+
+    let tags = bookmarks.tags();
+    if !tags.is_empty() {
+        html.push_str("<p>Tags:");
+        for (tag, count) in tags {
+            html.push_str(&format!(
+                " <a href=\"about:bookmarks?q={}\">#{}</a> <small>({})</small>",
+                encode(&format!("#{}", tag)),
+                escape(&tag),
+                count
+            ));
+        }
+        html.push_str("</p>\n");
+    }
+
+    let found = bookmarks.search(search);
+    let counted = match found.len() {
+        1 => "1 bookmark".to_string(),
+        count => format!("{} bookmarks", count),
+    };
+    html.push_str(&match (found.len(), search.is_empty()) {
+        (0, true) => "<p>No bookmarks yet. Press m on a page to bookmark it.</p>\n".to_string(),
+        (0, false) => format!("<p>No bookmarks match <b>{}</b>.</p>\n", escape(search)),
+        (_, true) => format!("<p>{}.</p>\n", counted),
+        (1, false) => format!(
+            "<p>1 bookmark matches <b>{}</b>. <a href=\"about:bookmarks\">Show all</a></p>\n",
+            escape(search)
+        ),
+        (_, false) => format!(
+            "<p>{} match <b>{}</b>. <a href=\"about:bookmarks\">Show all</a></p>\n",
+            counted,
+            escape(search)
+        ),
+    });
+
+    html.push_str("<ul>\n");
+    for bookmark in found {
+        let title = match bookmark.title.is_empty() {
+            true => &bookmark.url,
+            false => &bookmark.title,
+        };
+        let tags = bookmark
+            .tags
+            .iter()
+            .map(|tag| format!("#{}", escape(tag)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let delete = match search.is_empty() {
+            true => format!("about:bookmarks?delete={}", encode(&bookmark.url)),
+            false => format!(
+                "about:bookmarks?delete={}&amp;q={}",
+                encode(&bookmark.url),
+                encode(search)
+            ),
+        };
+
+        html.push_str(&format!(
+            "<li><a href=\"{}\">{}</a> <small>{} {} added {}</small> <a href=\"{}\">delete</a></li>\n",
+            escape(&bookmark.url),
+            escape(title),
+            escape(&bookmark.url),
+            tags,
+            date::format(bookmark.added),
+            delete
+        ));
+    }
+    html.push_str("</ul>\n");
+
+    // The page's own URL keeps the search, but not what was deleted.
+    let shown = match search.is_empty() {
+        true => without_query(url),
+        false => Url {
+            query: Some(format!("q={}", percent_encode(search, ""))),
+            ..url.clone()
+        },
+    };
+    Document::from_html(shown, &html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::cookies::parse_set_cookie;
+    use termion::event::Key;
+
+    fn jar() -> CookieJar {
+        let from = Url::parse("http://example.com/").unwrap();
+        let mut jar = CookieJar::new();
+        for header in ["a=1", "b=2"] {
+            let cookie = parse_set_cookie(header, &from, 0).unwrap();
+            jar.insert(cookie, 0);
+        }
+        jar
+    }
+
+    #[test]
+    fn cookies_are_only_deleted_from_the_page_itself() {
+        let clear = Url::parse("about:cookies?clear=").unwrap();
+        let delete = Url::parse("about:cookies?delete=a&domain=example.com&path=/").unwrap();
+
+        let mut cookies = jar();
+        let page = super::cookies(&clear, &mut cookies, false);
+        assert_eq!(cookies.cookies.len(), 2);
+        assert_eq!(page.url.to_string(), "about:cookies");
+
+        super::cookies(&delete, &mut cookies, true);
+        assert_eq!(cookies.cookies.len(), 1);
+        assert_eq!(cookies.cookies[0].name, "b");
+        let page = super::cookies(&clear, &mut cookies, true);
+        assert!(cookies.cookies.is_empty());
+        assert_eq!(page.url.to_string(), "about:cookies");
+    }
+
+    fn text(document: &Document) -> String {
+        document
+            .layout(200)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn lists_the_keys_that_are_bound() {
+        let url = Url::parse("about:hyperline").unwrap();
+        let shown = text(&page(&url, &Keymap::default()).unwrap());
+        assert!(shown.contains("H/Alt-Left"));
+        assert!(shown.contains("Alt-h, Alt-j, Alt-k, Alt-l"));
+        assert!(shown.contains("  q   "));
+
+        let mut keys = Keymap::default();
+        keys.bind(Action::Quit, &[Key::Ctrl('q')]);
+        keys.bind(Action::Reload, &[]);
+        let shown = text(&page(&url, &keys).unwrap());
+        assert!(shown.contains("Ctrl-q"));
+        assert!(shown.contains("unbound"));
+        assert!(!shown.contains("  q   "));
+
+        // Every line lines its text up after the widest keys.
+        let starts = shown
+            .lines()
+            .filter(|line| line.ends_with("quit") || line.ends_with("go back"))
+            .map(|line| line.rfind("  ").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(starts.len(), 2);
+        assert_eq!(starts[0], starts[1]);
+    }
+}
@@ -0,0 +1,428 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::html::dom::{Node, NodeKind};
+use crate::html::parse;
+use crate::net::date;
+
+/// A saved page.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bookmark {
+    pub url: String,
+    pub title: String,
+    /// Lowercase words the bookmark can be found by, without a leading `#`.
+    pub tags: Vec<String>,
+    /// Seconds since the Unix epoch.
+    pub added: u64,
+}
+
+impl Bookmark {
+    /// Whether every word of `query` is found in the title, URL or tags.
+    /// Words starting with `#` only match tags, and must match one exactly.
+    fn matches(&self, query: &str) -> bool {
+        let haystack =
+            format!("{} {} {}", self.title, self.url, self.tags.join(" ")).to_lowercase();
+
+        query.split_whitespace().all(|word| {
+            let word = word.to_lowercase();
+            match word.strip_prefix('#') {
+                Some(tag) => self.tags.iter().any(|t| *t == tag),
+                None => haystack.contains(&word),
+            }
+        })
+    }
+}
+
+/// Splits what was typed for a bookmark's tags, separated by commas or
+/// spaces, into lowercase tags without duplicates.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split(|c: char| c == ',' || c.is_whitespace()) {
+        let tag = tag.trim_start_matches('#').to_lowercase();
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Bookmarked pages in the order they were added, optionally saved to a file.
+#[derive(Debug, Default)]
+pub struct Bookmarks {
+    pub bookmarks: Vec<Bookmark>,
+    /// Where bookmarks are saved whenever they change.
+    pub file: Option<PathBuf>,
+}
+
+impl Bookmarks {
+    pub fn new() -> Self {
+        Bookmarks::default()
+    }
+
+    /// Loads the bookmarks saved in `file`, which is kept up to date from then
+    /// on. A missing file means no bookmarks.
+    pub fn open(file: PathBuf) -> Result<Self, String> {
+        let mut bookmarks = Bookmarks {
+            bookmarks: Vec::new(),
+            file: Some(file.clone()),
+        };
+
+        let text = match fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(bookmarks),
+            Err(error) => return Err(format!("Could not read {}: {}", file.display(), error)),
+        };
+
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let [added, url, tags, title] = line.splitn(4, '\t').collect::<Vec<_>>()[..] else {
+                continue;
+            };
+            bookmarks.bookmarks.push(Bookmark {
+                url: url.to_string(),
+                title: title.to_string(),
+                tags: parse_tags(tags),
+                added: added.parse().unwrap_or(0),
+            });
+        }
+
+        Ok(bookmarks)
+    }
+
+    /// Writes the bookmarks to their file.
+    pub fn save(&self) -> Result<(), String> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+
+        // Tabs and line breaks would split a bookmark apart when it is loaded.
+        let breaks = ['\t', '\r', '\n'];
+        let mut text = String::from("# hyperline bookmarks: added, URL, tags, title\n");
+        for bookmark in &self.bookmarks {
+            let tags = bookmark
+                .tags
+                .iter()
+                .map(|tag| tag.replace(breaks, ""))
+                .filter(|tag| !tag.is_empty())
+                .collect::<Vec<_>>();
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                bookmark.added,
+                bookmark.url.replace(breaks, ""),
+                tags.join(","),
+                bookmark.title.replace(breaks, " ")
+            ));
+        }
+
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+        }
+        fs::write(file, text).map_err(|error| format!("Could not save bookmarks: {}", error))
+    }
+
+    pub fn get(&self, url: &str) -> Option<&Bookmark> {
+        self.bookmarks.iter().find(|bookmark| bookmark.url == url)
+    }
+
+    /// Adds a bookmark, or updates the title and tags of the one for the same
+    /// URL, which keeps its place.
+    pub fn add(&mut self, bookmark: Bookmark) -> Result<(), String> {
+        match self.bookmarks.iter_mut().find(|b| b.url == bookmark.url) {
+            Some(existing) => {
+                existing.title = bookmark.title;
+                existing.tags = bookmark.tags;
+            }
+            None => self.bookmarks.push(bookmark),
+        }
+        self.save()
+    }
+
+    pub fn remove(&mut self, url: &str) -> Result<(), String> {
+        self.bookmarks.retain(|bookmark| bookmark.url != url);
+        self.save()
+    }
+
+    /// The bookmarks matching `query`, most recently added first. An empty
+    /// query matches every bookmark.
+    pub fn search(&self, query: &str) -> Vec<&Bookmark> {
+        self.bookmarks
+            .iter()
+            .rev()
+            .filter(|bookmark| bookmark.matches(query))
+            .collect()
+    }
+
+    /// Every tag in use, sorted, with how many bookmarks have it.
+    pub fn tags(&self) -> Vec<(String, usize)> {
+        let mut tags: Vec<(String, usize)> = Vec::new();
+        for tag in self.bookmarks.iter().flat_map(|bookmark| &bookmark.tags) {
+            match tags.iter_mut().find(|(t, _)| t == tag) {
+                Some((_, count)) => *count += 1,
+                None => tags.push((tag.clone(), 1)),
+            }
+        }
+        tags.sort();
+        tags
+    }
+
+    /// Adds the bookmarks of a Netscape bookmark file, as exported by most
+    /// browsers. The names of the folders a bookmark is in become tags, and
+    /// bookmarks already here are left as they are. Returns how many were added.
+    pub fn import_html(&mut self, source: &str) -> Result<usize, String> {
+        let mut found = Vec::new();
+        collect(
+            &parse::parse(source),
+            &mut Vec::new(),
+            &mut None,
+            &mut found,
+        );
+
+        let mut added = 0;
+        for bookmark in found {
+            if self.get(&bookmark.url).is_none() {
+                self.bookmarks.push(bookmark);
+                added += 1;
+            }
+        }
+        self.save()?;
+        Ok(added)
+    }
+
+    /// The bookmarks as a Netscape bookmark file, which other browsers can
+    /// import. Tags are kept in the `TAGS` attribute.
+    pub fn export_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+             <META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n\
+             <TITLE>Bookmarks</TITLE>\n\
+             <H1>Bookmarks</H1>\n\
+             <DL><p>\n",
+        );
+        for bookmark in &self.bookmarks {
+            html.push_str(&format!(
+                "    <DT><A HREF=\"{}\" ADD_DATE=\"{}\"",
+                escape(&bookmark.url),
+                bookmark.added
+            ));
+            if !bookmark.tags.is_empty() {
+                html.push_str(&format!(" TAGS=\"{}\"", escape(&bookmark.tags.join(","))));
+            }
+            html.push_str(&format!(">{}</A>\n", escape(&bookmark.title)));
+        }
+        html.push_str("</DL><p>\n");
+        html
+    }
+}
+
+/// Finds the links of a bookmark file in document order. `folders` holds the
+/// names of the `<DL>` lists being walked, and `heading` the last folder name
+/// seen, which names the next list.
+fn collect(
+    node: &Node,
+    folders: &mut Vec<String>,
+    heading: &mut Option<String>,
+    found: &mut Vec<Bookmark>,
+) {
+    match &node.kind {
+        NodeKind::Element { name, .. } if name == "h3" => {
+            *heading = Some(node.text_content().trim().to_string());
+            return;
+        }
+        NodeKind::Element { name, .. } if name == "a" => {
+            if let Some(url) = node.attr("href").filter(|url| !url.is_empty()) {
+                let mut tags = parse_tags(node.attr("tags").unwrap_or_default());
+                for folder in parse_tags(&folders.join(",")) {
+                    if !tags.contains(&folder) {
+                        tags.push(folder);
+                    }
+                }
+
+                found.push(Bookmark {
+                    url: url.to_string(),
+                    title: node.text_content().trim().to_string(),
+                    tags,
+                    added: node
+                        .attr("add_date")
+                        .and_then(|added| added.parse().ok())
+                        .unwrap_or_else(date::now),
+                });
+            }
+            return;
+        }
+        NodeKind::Element { name, .. } if name == "dl" => {
+            // Tags cannot have spaces, so "Read later" becomes "read-later".
+            let folder = heading
+                .take()
+                .map(|name| name.split_whitespace().collect::<Vec<_>>().join("-"));
+            let named = folder.is_some();
+            folders.extend(folder);
+            for child in &node.children {
+                collect(child, folders, heading, found);
+            }
+            if named {
+                folders.pop();
+            }
+            return;
+        }
+        _ => {}
+    }
+
+    for child in &node.children {
+        collect(child, folders, heading, found);
+    }
+}
+
+/// Escapes text for an HTML attribute or element.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    fn bookmark(url: &str, title: &str, tags: &[&str], added: u64) -> Bookmark {
+        Bookmark {
+            url: url.to_string(),
+            title: title.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            added,
+        }
+    }
+
+    fn imported(source: &str) -> Vec<Bookmark> {
+        let mut bookmarks = Bookmarks::new();
+        bookmarks.import_html(source).unwrap();
+        bookmarks.bookmarks
+    }
+
+    #[test]
+    fn saves_without_breaking_lines() {
+        let dir = env::temp_dir().join(format!("hyperline-bookmarks-{}", process::id()));
+        let file = dir.join("bookmarks.tsv");
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut bookmarks = Bookmarks::open(file.clone()).unwrap();
+        bookmarks
+            .add(bookmark(
+                "http://example.com/a\tb\nc",
+                "Two\nlines\tand\r",
+                &["ok", "new\nline", "\t"],
+                10,
+            ))
+            .unwrap();
+        bookmarks
+            .add(bookmark("http://example.com/", "Example", &[], 20))
+            .unwrap();
+
+        let loaded = Bookmarks::open(file).unwrap();
+        assert_eq!(
+            loaded.bookmarks,
+            [
+                bookmark(
+                    "http://example.com/abc",
+                    "Two lines and ",
+                    &["ok", "newline"],
+                    10
+                ),
+                bookmark("http://example.com/", "Example", &[], 20)
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn exports_netscape_files() {
+        let bookmarks = Bookmarks {
+            bookmarks: vec![
+                bookmark(
+                    "http://example.com/?a=1&b=\"2\"",
+                    "Tom & Jerry <3",
+                    &["cartoons", "tv"],
+                    1_700_000_000,
+                ),
+                bookmark("http://example.org/", "Plain", &[], 5),
+            ],
+            file: None,
+        };
+
+        let html = bookmarks.export_html();
+        assert!(html.starts_with("<!DOCTYPE NETSCAPE-Bookmark-file-1>\n"));
+        assert!(html.contains(
+            "<DT><A HREF=\"http://example.com/?a=1&amp;b=&quot;2&quot;\" \
+             ADD_DATE=\"1700000000\" TAGS=\"cartoons,tv\">Tom &amp; Jerry &lt;3</A>\n"
+        ));
+        assert!(html.contains("<DT><A HREF=\"http://example.org/\" ADD_DATE=\"5\">Plain</A>\n"));
+        assert!(html.ends_with("</DL><p>\n"));
+
+        // Importing what was exported gives the same bookmarks back.
+        assert_eq!(imported(&html), bookmarks.bookmarks);
+    }
+
+    #[test]
+    fn imports_folders_as_tags() {
+        let source = "<!DOCTYPE NETSCAPE-Bookmark-file-1>
+            <TITLE>Bookmarks</TITLE>
+            <H1>Bookmarks</H1>
+            <DL><p>
+                <DT><A HREF=\"http://top.example/\" ADD_DATE=\"100\">Top</A>
+                <DT><H3 ADD_DATE=\"1\">Read later</H3>
+                <DL><p>
+                    <DT><A HREF=\"http://later.example/\" ADD_DATE=\"200\" TAGS=\"Long, read-later\">Later</A>
+                    <DT><H3>Rust</H3>
+                    <DL><p>
+                        <DT><A HREF=\"http://rust.example/\" ADD_DATE=\"300\">Rust &amp; more</A>
+                    </DL><p>
+                </DL><p>
+                <DT><A HREF=\"http://after.example/\" ADD_DATE=\"400\">After</A>
+                <DT><A HREF=\"\">Empty</A>
+            </DL><p>";
+
+        assert_eq!(
+            imported(source),
+            [
+                bookmark("http://top.example/", "Top", &[], 100),
+                bookmark(
+                    "http://later.example/",
+                    "Later",
+                    &["long", "read-later"],
+                    200
+                ),
+                bookmark(
+                    "http://rust.example/",
+                    "Rust & more",
+                    &["read-later", "rust"],
+                    300
+                ),
+                bookmark("http://after.example/", "After", &[], 400),
+            ]
+        );
+    }
+
+    #[test]
+    fn imports_only_new_bookmarks() {
+        let mut bookmarks = Bookmarks {
+            bookmarks: vec![bookmark("http://example.com/", "Mine", &["kept"], 1)],
+            file: None,
+        };
+        let source = "<DL><p>\
+            <DT><A HREF=\"http://example.com/\" ADD_DATE=\"2\">Theirs</A>\
+            <DT><A HREF=\"http://example.org/\">Undated</A>\
+            </DL><p>";
+
+        let before = date::now();
+        assert_eq!(bookmarks.import_html(source), Ok(1));
+        assert_eq!(
+            bookmarks.bookmarks[0],
+            bookmark("http://example.com/", "Mine", &["kept"], 1)
+        );
+        // Without an ADD_DATE, bookmarks count as added now.
+        assert!(bookmarks.bookmarks[1].added >= before);
+        assert_eq!(bookmarks.import_html(source), Ok(0));
+    }
+}
@@ -0,0 +1,294 @@
+use crate::browser::form::{Control, Form};
+use crate::browser::url::Url;
+use crate::common;
+use crate::draw::text::{Span, StyledLine, TextAttributes, TextStyle, printable};
+use crate::draw::theme::Role;
+use crate::html;
+
+/// A piece of a page that is laid out on its own lines.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Block {
+    /// Text wrapped at spaces to fit the viewport, like CSS `white-space: normal`.
+    Text {
+        /// Repeated at the start of every line, such as the bar of a quote.
+        indent: StyledLine,
+        /// Shown on the first line only, such as a list bullet. Later lines
+        /// are indented by its width instead.
+        marker: StyledLine,
+        line: StyledLine,
+    },
+    /// Text kept as written, broken only where it runs past the viewport,
+    /// like CSS `white-space: pre-wrap`.
+    Preformatted(StyledLine),
+    /// A horizontal line across the viewport.
+    Rule,
+}
+
+impl Block {
+    pub fn text(line: impl Into<StyledLine>) -> Self {
+        Block::Text {
+            indent: StyledLine::default(),
+            marker: StyledLine::default(),
+            line: line.into(),
+        }
+    }
+
+    /// The block with its control characters replaced.
+    fn to_printable(&self) -> Block {
+        match self {
+            Block::Text {
+                indent,
+                marker,
+                line,
+            } => Block::Text {
+                indent: indent.to_printable(),
+                marker: marker.to_printable(),
+                line: line.to_printable(),
+            },
+            Block::Preformatted(line) => Block::Preformatted(line.to_printable()),
+            Block::Rule => Block::Rule,
+        }
+    }
+
+    fn layout(&self, width: usize) -> Vec<StyledLine> {
+        match self {
+            Block::Text {
+                indent,
+                marker,
+                line,
+            } => {
+                let hanging = " ".repeat(marker.width());
+                let available = width.saturating_sub(indent.width() + marker.width());
+
+                line.wrap(available)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, wrapped)| {
+                        let mut spans = indent.spans.clone();
+                        if i == 0 {
+                            spans.extend(marker.spans.iter().cloned());
+                        } else if !hanging.is_empty() {
+                            spans.push(Span::plain(hanging.clone()));
+                        }
+                        spans.extend(wrapped.spans);
+                        StyledLine::new(spans)
+                    })
+                    .collect()
+            }
+            Block::Preformatted(line) => {
+                let chars = line.chars().collect::<Vec<_>>();
+                if chars.is_empty() {
+                    return vec![StyledLine::default()];
+                }
+
+                chars
+                    .chunks(width.max(1))
+                    .map(|chunk| StyledLine::from_chars(chunk.iter().copied()))
+                    .collect()
+            }
+            Block::Rule => vec![
+                Span::new(
+                    if common::locale_is_utf8() { "─" } else { "-" }.repeat(width),
+                    TextStyle {
+                        attributes: TextAttributes::DIM,
+                        ..Default::default()
+                    },
+                )
+                .into(),
+            ],
+        }
+    }
+}
+
+/// A loaded page, ready to be laid out in a viewport.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Document {
+    pub url: Url,
+    pub title: String,
+    pub blocks: Vec<Block>,
+    pub forms: Vec<Form>,
+    /// The form controls on the page, drawn where `blocks` has text whose
+    /// style's `widget` is their index.
+    pub controls: Vec<Control>,
+}
+
+impl Document {
+    /// A page of plain lines, each wrapped to the viewport.
+    pub fn new(url: Url, title: impl Into<String>, lines: Vec<StyledLine>) -> Self {
+        Document::from_blocks(url, title, lines.into_iter().map(Block::text).collect())
+    }
+
+    /// A page of `blocks`. Control characters in them and in the title are
+    /// replaced, since pages come from anywhere.
+    pub fn from_blocks(url: Url, title: impl Into<String>, blocks: Vec<Block>) -> Self {
+        Document {
+            url,
+            title: title.into().chars().map(printable).collect(),
+            blocks: blocks.iter().map(Block::to_printable).collect(),
+            forms: Vec::new(),
+            controls: Vec::new(),
+        }
+    }
+
+    /// A page parsed from HTML `source`, with its links resolved against `url`.
+    pub fn from_html(url: Url, source: &str) -> Self {
+        let tree = html::parse::parse(source);
+        let rendered = html::render::render(&tree, &url);
+
+        Document {
+            forms: rendered.forms,
+            controls: rendered.controls,
+            ..Document::from_blocks(url, html::render::title(&tree), rendered.blocks)
+        }
+    }
+
+    /// A page showing `text` as written, such as a plain text file.
+    pub fn plain_text(url: Url, title: impl Into<String>, text: &str) -> Self {
+        let blocks = text
+            .lines()
+            .map(|line| Block::Preformatted(expand_tabs(line).into()))
+            .collect();
+
+        Document::from_blocks(url, title, blocks)
+    }
+
+    /// The page laid out for a viewport `width` cells wide.
+    pub fn layout(&self, width: usize) -> Vec<StyledLine> {
+        if self.controls.is_empty() {
+            return self
+                .blocks
+                .iter()
+                .flat_map(|block| block.layout(width))
+                .collect();
+        }
+
+        // Controls are drawn as they are now, not as they were when the page loaded.
+        let show = |line: &StyledLine| StyledLine {
+            spans: line
+                .spans
+                .iter()
+                .map(
+                    |span| match span.style.widget.and_then(|i| self.controls.get(i)) {
+                        Some(control) => Span::new(
+                            control.display().chars().map(printable).collect::<String>(),
+                            span.style.clone(),
+                        ),
+                        None => span.clone(),
+                    },
+                )
+                .collect(),
+        };
+
+        self.blocks
+            .iter()
+            .map(|block| match block {
+                Block::Text {
+                    indent,
+                    marker,
+                    line,
+                } => Block::Text {
+                    indent: indent.clone(),
+                    marker: marker.clone(),
+                    line: show(line),
+                },
+                Block::Preformatted(line) => Block::Preformatted(show(line)),
+                Block::Rule => Block::Rule,
+            })
+            .flat_map(|block| block.layout(width))
+            .collect()
+    }
+
+    /// A page explaining why `url` could not be shown.
+    pub fn error(url: Url, message: &str) -> Self {
+        let heading = TextStyle {
+            role: Some(Role::Error),
+            attributes: TextAttributes::BOLD,
+            ..Default::default()
+        };
+
+        let mut lines = vec![
+            Span::new("Unable to load this page", heading).into(),
+            StyledLine::default(),
+        ];
+        lines.extend(message.lines().map(StyledLine::from));
+        lines.push(StyledLine::default());
+        lines.push(format!("URL: {}", url).into());
+
+        Document::new(url, "Error", lines)
+    }
+}
+
+/// Replaces tabs with spaces up to the next multiple of eight columns.
+fn expand_tabs(line: &str) -> String {
+    let mut expanded = String::with_capacity(line.len());
+    let mut column = 0;
+
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = 8 - column % 8;
+            expanded.push_str(&" ".repeat(spaces));
+            column += spaces;
+        } else {
+            expanded.push(c);
+            column += 1;
+        }
+    }
+
+    expanded
+}
+
+/// A span linking to `target`, styled like links on rendered pages.
+pub(crate) fn link(text: &str, target: &str) -> Span {
+    Span::new(
+        text,
+        TextStyle {
+            role: Some(Role::Link),
+            attributes: TextAttributes::UNDERLINE,
+            link: Some(target.to_string()),
+            ..Default::default()
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(document: &Document) -> String {
+        document
+            .layout(80)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn url() -> Url {
+        Url::parse("http://example.com/").unwrap()
+    }
+
+    #[test]
+    fn plain_text_expands_tabs_and_replaces_control_characters() {
+        let document =
+            Document::plain_text(url(), "a\x1b[2Jb", "x\ty\n\x1b]52;c;aGk=\x07\n\u{9b}31m");
+        assert_eq!(
+            text(&document),
+            "x       y\n\u{fffd}]52;c;aGk=\u{fffd}\n\u{fffd}31m"
+        );
+        assert_eq!(document.title, "a\u{fffd}[2Jb");
+    }
+
+    #[test]
+    fn html_cannot_write_escapes() {
+        let source = "<title>&#27;]0;spoof&#7;</title><p>raw \x1b[31m and &#27;[2J</p>\
+                      <pre>\x1bP</pre>";
+        let document = Document::from_html(url(), source);
+        assert_eq!(document.title, "\u{fffd}]0;spoof\u{fffd}");
+        let text = text(&document);
+        assert!(
+            !text.chars().any(|c| c != '\n' && c.is_control()),
+            "{:?}",
+            text
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+
+use crate::browser::about;
+use crate::browser::bookmarks::Bookmarks;
+use crate::browser::document::Document;
+use crate::browser::file;
+use crate::browser::history::History;
+use crate::browser::url::Url;
+use crate::config::keys::Keymap;
+use crate::net::http::{Client, HttpError, Request, Response};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadError {
+    UnsupportedScheme(String),
+    NotFound(String),
+    Io(String),
+    Http(HttpError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::UnsupportedScheme(scheme) => {
+                write!(f, "URLs starting with '{}:' are not supported.", scheme)
+            }
+            LoadError::NotFound(url) => write!(f, "Nothing was found at {}.", url),
+            LoadError::Io(error) => write!(f, "{}", error),
+            LoadError::Http(error @ HttpError::Certificate { .. }) => write!(
+                f,
+                "{}\n\nThe connection was stopped because the server could not prove \
+                 who it is. If you trust it, add its certificate authority with \
+                 --ca-file, or start hyperline with --insecure.",
+                error
+            ),
+            LoadError::Http(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// Media types shown as plain text, besides `text/*`.
+const TEXT_TYPES: &[&str] = &[
+    "application/json",
+    "application/javascript",
+    "application/xml",
+    "application/x-sh",
+];
+
+/// Turns an HTTP response into a page, whatever its status.
+fn from_response(response: Response) -> Document {
+    let mime = response.mime_type();
+    let name = response
+        .url
+        .path
+        .rsplit('/')
+        .find(|segment| !segment.is_empty())
+        .unwrap_or("")
+        .to_string();
+
+    let looks_like_html = mime.is_empty() && response.body.trim_ascii_start().starts_with(b"<");
+
+    let mut document = if mime == "text/html" || mime == "application/xhtml+xml" || looks_like_html
+    {
+        Document::from_html(response.url.clone(), &response.text())
+    } else if mime.starts_with("text/")
+        || mime.ends_with("+json")
+        || TEXT_TYPES.contains(&mime.as_str())
+    {
+        Document::plain_text(response.url.clone(), name.clone(), &response.text())
+    } else {
+        let message = format!(
+            "Hyperline cannot show {} content ({}).",
+            if mime.is_empty() { "binary" } else { &mime },
+            file::human_size(response.body.len() as u64)
+        );
+        Document::new(response.url.clone(), name.clone(), vec![message.into()])
+    };
+
+    if !response.is_success() && (document.title.is_empty() || document.title == name) {
+        document.title = format!("{} {}", response.status, response.reason);
+    }
+
+    document
+}
+
+/// Loads documents for every kind of URL hyperline understands. One fetcher
+/// is shared by all sessions, so state such as connections can be reused.
+#[derive(Debug, Default)]
+pub struct Fetcher {
+    pub client: Client,
+    /// Served as `about:bookmarks`.
+    pub bookmarks: Bookmarks,
+    /// Pages visited in every session, offered when typing a URL.
+    pub history: History,
+    /// The keys listed on `about:hyperline`.
+    pub keys: Keymap,
+}
+
+impl Fetcher {
+    pub fn new() -> Self {
+        Fetcher::default()
+    }
+
+    /// Sends an HTTP request, such as a form submission, and shows the response.
+    pub fn send(&mut self, request: Request) -> Result<Document, LoadError> {
+        let response = self.client.send(request).map_err(LoadError::Http)?;
+        Ok(from_response(response))
+    }
+
+    pub fn fetch(&mut self, url: &Url) -> Result<Document, LoadError> {
+        self.fetch_from(url, None)
+    }
+
+    /// Loads `url` for a link or redirect on `initiator`, which decides what
+    /// cookies are sent.
+    pub fn fetch_from(
+        &mut self,
+        url: &Url,
+        initiator: Option<&Url>,
+    ) -> Result<Document, LoadError> {
+        // Built-in pages only delete what their queries ask for when a link
+        // on the page itself was followed, so no other page can link there to do it.
+        let from_itself = initiator
+            .is_some_and(|initiator| initiator.scheme == "about" && initiator.path == url.path);
+
+        match url.scheme.as_str() {
+            "about" if url.path == "cookies" => {
+                Ok(about::cookies(url, &mut self.client.cookies, from_itself))
+            }
+            "about" if url.path == "bookmarks" => {
+                Ok(about::bookmarks(url, &mut self.bookmarks, from_itself))
+            }
+            "about" if url.path == "cache" => {
+                Ok(about::cache(url, &self.client.cache, from_itself))
+            }
+            "about" => {
+                about::page(url, &self.keys).ok_or_else(|| LoadError::NotFound(url.to_string()))
+            }
+            "file" => file::load(url),
+            "http" | "https" => {
+                let mut request = Request::get(url.without_fragment());
+                request.initiator = initiator.cloned();
+                self.send(request)
+            }
+            scheme => Err(LoadError::UnsupportedScheme(scheme.to_string())),
+        }
+    }
+
+    /// Loads `url` again, checking any cached copy with the server first.
+    pub fn reload(&mut self, url: &Url) -> Result<Document, LoadError> {
+        match url.scheme.as_str() {
+            "http" | "https" => {
+                let mut request = Request::get(url.without_fragment());
+                request.headers.insert("Cache-Control", "max-age=0");
+                self.send(request)
+            }
+            _ => self.fetch(url),
+        }
+    }
+}
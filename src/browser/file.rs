@@ -0,0 +1,150 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use crate::browser::document::{self, Block, Document};
+use crate::browser::fetch::LoadError;
+use crate::browser::url::Url;
+use crate::draw::text::{Span, StyledLine, TextAttributes, TextStyle};
+
+/// Extensions of files rendered as HTML rather than shown as text.
+const HTML_EXTENSIONS: &[&str] = &["html", "htm", "xhtml"];
+
+/// Sizes in binary units, like `1.5 KiB`.
+pub(crate) fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn io_error(url: &Url, error: std::io::Error) -> LoadError {
+    match error.kind() {
+        ErrorKind::NotFound => LoadError::NotFound(url.to_string()),
+        _ => LoadError::Io(error.to_string()),
+    }
+}
+
+/// A page listing the entries of the directory at `path`, each linking to its `file:` URL.
+fn listing(url: Url, path: &Path) -> Result<Document, LoadError> {
+    let mut entries = fs::read_dir(path)
+        .map_err(|error| io_error(&url, error))?
+        .filter_map(Result::ok)
+        .map(|entry| {
+            let metadata = entry.metadata().ok();
+            let is_dir = metadata.as_ref().is_some_and(|m| m.is_dir());
+            let size = metadata.filter(|m| m.is_file()).map(|m| m.len());
+            (
+                entry.file_name().to_string_lossy().into_owned(),
+                is_dir,
+                size,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    // Directories first, then files, each sorted by name.
+    entries.sort_by_key(|(name, is_dir, _)| (!is_dir, name.to_lowercase()));
+
+    let title = format!("Index of {}", path.display());
+    let heading = TextStyle {
+        attributes: TextAttributes::BOLD,
+        ..Default::default()
+    };
+
+    let mut blocks = vec![
+        Block::text(Span::new(title.clone(), heading)),
+        Block::text(""),
+    ];
+
+    if let Some(parent) = path.parent() {
+        let target = Url::from_path(parent).to_string();
+        blocks.push(Block::Preformatted(document::link("../", &target).into()));
+    }
+
+    let width = entries
+        .iter()
+        .map(|(name, is_dir, _)| name.chars().count() + *is_dir as usize)
+        .max()
+        .unwrap_or(0);
+
+    for (name, is_dir, size) in entries {
+        let label = if is_dir { format!("{}/", name) } else { name };
+        let mut target = Url::from_path(&path.join(&label));
+        if is_dir && !target.path.ends_with('/') {
+            target.path.push('/');
+        }
+
+        let mut spans = vec![document::link(&label, &target.to_string())];
+        if let Some(size) = size {
+            let padding = width - label.chars().count() + 2;
+            spans.push(Span::plain(format!(
+                "{}{:>10}",
+                " ".repeat(padding),
+                human_size(size)
+            )));
+        }
+        blocks.push(Block::Preformatted(StyledLine::new(spans)));
+    }
+
+    Ok(Document::from_blocks(url, title, blocks))
+}
+
+/// Loads a `file:` URL: HTML files are rendered, directories listed and
+/// anything else shown as plain text.
+pub fn load(url: &Url) -> Result<Document, LoadError> {
+    let Some(path) = url.to_path() else {
+        return Err(LoadError::UnsupportedScheme(url.scheme.clone()));
+    };
+
+    let metadata = fs::metadata(&path).map_err(|error| io_error(url, error))?;
+    if metadata.is_dir() {
+        return listing(url.clone(), &path);
+    }
+
+    let bytes = fs::read(&path).map_err(|error| io_error(url, error))?;
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let is_html = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            HTML_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        });
+
+    if is_html {
+        let mut document = Document::from_html(url.clone(), &String::from_utf8_lossy(&bytes));
+        if document.title.is_empty() {
+            document.title = name;
+        }
+        return Ok(document);
+    }
+
+    // Like most tools, treat files with NUL bytes near the start as binary.
+    if bytes.iter().take(8000).any(|&byte| byte == 0) {
+        let message = format!(
+            "{} is a binary file of {}.",
+            name,
+            human_size(bytes.len() as u64)
+        );
+        return Ok(Document::new(url.clone(), name, vec![message.into()]));
+    }
+
+    Ok(Document::plain_text(
+        url.clone(),
+        name,
+        &String::from_utf8_lossy(&bytes),
+    ))
+}
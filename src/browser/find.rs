@@ -0,0 +1,304 @@
+use regex::{Regex, RegexBuilder};
+
+use crate::draw::text::{StyledLine, TextStyle};
+use crate::draw::theme::Role;
+
+/// How a search query is matched.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FindOptions {
+    pub case_sensitive: bool,
+    /// Treat the query as a regular expression instead of plain text.
+    pub regex: bool,
+}
+
+/// Where a query matched, as a line of the laid-out page and a range of
+/// characters in it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A search on a page and the match it is at.
+#[derive(Clone, Debug)]
+pub struct Search {
+    pub query: String,
+    pub options: FindOptions,
+    pattern: Regex,
+    pub matches: Vec<Match>,
+    /// Index of the current match in `matches`.
+    pub current: Option<usize>,
+}
+
+impl Search {
+    /// Fails when `query` is not a valid regular expression in regex mode.
+    pub fn new(query: &str, options: FindOptions) -> Result<Self, String> {
+        let source = match options.regex {
+            true => query.to_string(),
+            false => regex::escape(query),
+        };
+        let pattern = RegexBuilder::new(&source)
+            .case_insensitive(!options.case_sensitive)
+            .build()
+            .map_err(|error| match error {
+                regex::Error::Syntax(_) => "Invalid regular expression.".to_string(),
+                error => error.to_string(),
+            })?;
+
+        Ok(Search {
+            query: query.to_string(),
+            options,
+            pattern,
+            matches: Vec::new(),
+            current: None,
+        })
+    }
+
+    /// Finds every match in `lines`. Matches do not continue across lines.
+    pub fn run(&mut self, lines: &[StyledLine]) {
+        self.matches.clear();
+        self.current = None;
+        if self.query.is_empty() {
+            return;
+        }
+
+        for (number, line) in lines.iter().enumerate() {
+            let text = line.to_string();
+            // Byte offsets are turned into character columns.
+            let column = |offset: usize| text[..offset].chars().count();
+
+            for found in self.pattern.find_iter(&text) {
+                if found.is_empty() {
+                    continue;
+                }
+                self.matches.push(Match {
+                    line: number,
+                    start: column(found.start()),
+                    end: column(found.end()),
+                });
+            }
+        }
+    }
+
+    pub fn current_match(&self) -> Option<&Match> {
+        self.matches.get(self.current?)
+    }
+
+    /// Moves to the first match on or after `line`, wrapping to the first
+    /// match of the page.
+    pub fn select_from(&mut self, line: usize) -> Option<&Match> {
+        let index = self
+            .matches
+            .iter()
+            .position(|found| found.line >= line)
+            .or((!self.matches.is_empty()).then_some(0));
+        self.current = index;
+        self.current_match()
+    }
+
+    /// Moves to the next match, or the previous one, wrapping at either end.
+    pub fn step(&mut self, forward: bool) -> Option<&Match> {
+        let count = self.matches.len();
+        if count == 0 {
+            return None;
+        }
+
+        self.current = Some(match (self.current, forward) {
+            (None, true) => 0,
+            (None, false) => count - 1,
+            (Some(current), true) => (current + 1) % count,
+            (Some(current), false) => (current + count - 1) % count,
+        });
+        self.current_match()
+    }
+
+    /// `line`, the `number`th of the page, with its matches highlighted.
+    pub fn highlight(&self, number: usize, line: &StyledLine) -> StyledLine {
+        let current = self.current_match();
+        let matches = self
+            .matches
+            .iter()
+            .filter(|found| found.line == number)
+            .collect::<Vec<_>>();
+        if matches.is_empty() {
+            return line.clone();
+        }
+
+        let styles = line
+            .chars()
+            .enumerate()
+            .map(|(column, (c, style))| {
+                let found = matches
+                    .iter()
+                    .find(|found| (found.start..found.end).contains(&column));
+                let style = match found {
+                    Some(found) => TextStyle {
+                        fg: None,
+                        role: None,
+                        background_role: Some(match Some(*found) == current {
+                            true => Role::Focus,
+                            false => Role::Selection,
+                        }),
+                        ..style.clone()
+                    },
+                    None => style.clone(),
+                };
+                (c, style)
+            })
+            .collect::<Vec<_>>();
+
+        StyledLine::from_chars(styles.iter().map(|(c, style)| (*c, style)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::Color;
+    use crate::draw::text::Span;
+
+    fn lines(texts: &[&str]) -> Vec<StyledLine> {
+        texts.iter().map(|text| StyledLine::from(*text)).collect()
+    }
+
+    fn found(query: &str, options: FindOptions, texts: &[&str]) -> Vec<(usize, usize, usize)> {
+        let mut search = Search::new(query, options).unwrap();
+        search.run(&lines(texts));
+        search
+            .matches
+            .iter()
+            .map(|found| (found.line, found.start, found.end))
+            .collect()
+    }
+
+    #[test]
+    fn literal_regex_and_case() {
+        let texts = ["a.b axb", "A.B"];
+        let literal = FindOptions::default();
+        let regex = FindOptions {
+            regex: true,
+            ..FindOptions::default()
+        };
+        let case_sensitive = FindOptions {
+            case_sensitive: true,
+            ..FindOptions::default()
+        };
+
+        assert_eq!(found("a.b", literal, &texts), [(0, 0, 3), (1, 0, 3)]);
+        assert_eq!(
+            found("a.b", regex, &texts),
+            [(0, 0, 3), (0, 4, 7), (1, 0, 3)]
+        );
+        assert_eq!(found("a.b", case_sensitive, &texts), [(0, 0, 3)]);
+        assert_eq!(found("A.B", case_sensitive, &texts), [(1, 0, 3)]);
+        assert_eq!(
+            found(r"\bA", regex, &texts),
+            [(0, 0, 1), (0, 4, 5), (1, 0, 1)]
+        );
+
+        // Special characters are only special in regex mode.
+        assert_eq!(found("(", literal, &["f(x)"]), [(0, 1, 2)]);
+        assert_eq!(
+            Search::new("(", regex).err(),
+            Some("Invalid regular expression.".to_string())
+        );
+    }
+
+    #[test]
+    fn skips_empty_matches() {
+        let regex = FindOptions {
+            regex: true,
+            ..FindOptions::default()
+        };
+        assert_eq!(found("x*", regex, &["axxb", ""]), [(0, 1, 3)]);
+        assert_eq!(found("", FindOptions::default(), &["abc"]), []);
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        let texts = ["héllo wörld ☕ wörld"];
+        assert_eq!(
+            found("wörld", FindOptions::default(), &texts),
+            [(0, 6, 11), (0, 14, 19)]
+        );
+        assert_eq!(
+            found("WÖRLD ☕", FindOptions::default(), &texts),
+            [(0, 6, 13)]
+        );
+
+        // Matches run across spans of different styles.
+        let bold = TextStyle {
+            attributes: crate::draw::text::TextAttributes::BOLD,
+            ..TextStyle::default()
+        };
+        let line = StyledLine::new(vec![Span::plain("fö"), Span::new("öbar", bold)]);
+        let mut search = Search::new("ööb", FindOptions::default()).unwrap();
+        search.run(&[line]);
+        assert_eq!(
+            search.matches,
+            [Match {
+                line: 0,
+                start: 1,
+                end: 4
+            }]
+        );
+    }
+
+    #[test]
+    fn steps_and_wraps() {
+        let mut search = Search::new("x", FindOptions::default()).unwrap();
+        search.run(&lines(&["x", "", "x", "", "", "x"]));
+        let line = |found: Option<&Match>| found.map(|found| found.line);
+
+        assert_eq!(line(search.step(false)), Some(5));
+        assert_eq!(line(search.step(true)), Some(0));
+        assert_eq!(line(search.step(true)), Some(2));
+        assert_eq!(line(search.step(true)), Some(5));
+        assert_eq!(line(search.step(true)), Some(0));
+        assert_eq!(line(search.step(false)), Some(5));
+
+        assert_eq!(line(search.select_from(1)), Some(2));
+        assert_eq!(line(search.select_from(2)), Some(2));
+        assert_eq!(line(search.select_from(3)), Some(5));
+        // Past the last match, the search starts over from the top.
+        assert_eq!(line(search.select_from(6)), Some(0));
+        assert_eq!(search.current, Some(0));
+
+        // Searching again starts without a current match.
+        search.run(&lines(&["none"]));
+        assert_eq!(search.current_match(), None);
+        assert_eq!(search.step(true), None);
+        assert_eq!(search.select_from(0), None);
+    }
+
+    #[test]
+    fn highlights_matches() {
+        let colored = TextStyle {
+            fg: Some(Color { r: 1, g: 2, b: 3 }),
+            role: Some(Role::Link),
+            ..TextStyle::default()
+        };
+        let line = StyledLine::new(vec![Span::new("one two one", colored.clone())]);
+        let mut search = Search::new("one", FindOptions::default()).unwrap();
+        search.run(&[line.clone(), StyledLine::from("none here")]);
+        search.step(true);
+
+        let marked = |role| TextStyle {
+            background_role: Some(role),
+            ..TextStyle::default()
+        };
+        assert_eq!(
+            search.highlight(0, &line),
+            StyledLine::new(vec![
+                Span::new("one", marked(Role::Focus)),
+                Span::new(" two ", colored),
+                Span::new("one", marked(Role::Selection)),
+            ])
+        );
+
+        // Lines without matches are left as they are.
+        let other = StyledLine::from("two");
+        assert_eq!(search.highlight(2, &other), other);
+    }
+}
@@ -0,0 +1,623 @@
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::browser::url::{Url, percent_encode};
+use crate::common;
+use crate::net::http::{Method, Request};
+
+/// How a form's data is encoded when it is sent, from its `enctype`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    UrlEncoded,
+    Multipart,
+    TextPlain,
+}
+
+impl Encoding {
+    /// The encoding named by an `enctype` attribute. Unknown names mean the default.
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "multipart/form-data" => Encoding::Multipart,
+            "text/plain" => Encoding::TextPlain,
+            _ => Encoding::UrlEncoded,
+        }
+    }
+}
+
+/// The method named by a `method` attribute. Only POST is anything but GET.
+pub fn parse_method(value: &str) -> Method {
+    match value.trim().eq_ignore_ascii_case("post") {
+        true => Method::Post,
+        false => Method::Get,
+    }
+}
+
+/// A `<form>` element: where and how its controls are submitted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Form {
+    pub action: Url,
+    pub method: Method,
+    pub encoding: Encoding,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ControlKind {
+    /// Any single-line text field, such as `text`, `search` or `email`.
+    Text,
+    Password,
+    Hidden,
+    Checkbox,
+    Radio,
+    Submit,
+    /// A submit button drawn as an image, which sends where it was clicked.
+    Image,
+    Reset,
+    /// A button that does nothing without scripts.
+    Button,
+    /// Uploads the file at the path in `value`.
+    File,
+    Select {
+        multiple: bool,
+    },
+    TextArea,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelectOption {
+    pub value: String,
+    pub label: String,
+    pub selected: bool,
+    pub default_selected: bool,
+}
+
+/// An `<input>`, `<button>`, `<select>` or `<textarea>` element and its
+/// current state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Control {
+    pub kind: ControlKind,
+    /// Index of the form the control belongs to in its document.
+    pub form: Option<usize>,
+    pub name: String,
+    pub value: String,
+    pub checked: bool,
+    pub disabled: bool,
+    /// Whether the control is drawn on the page. Hidden inputs are not, but
+    /// are still submitted.
+    pub visible: bool,
+    /// The text of buttons, which may differ from the value they send.
+    pub label: String,
+    /// The choices of a `<select>`.
+    pub options: Vec<SelectOption>,
+    /// Width of text fields, in characters.
+    pub size: usize,
+
+    /// What the control had when the page loaded, restored by reset buttons.
+    pub default_value: String,
+    pub default_checked: bool,
+
+    /// Where submit buttons override their form's `action`, `method` and
+    /// `enctype`, from `formaction`, `formmethod` and `formenctype`.
+    pub action: Option<Url>,
+    pub method: Option<Method>,
+    pub encoding: Option<Encoding>,
+}
+
+impl Control {
+    pub fn new(kind: ControlKind, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let value = value.into();
+        Control {
+            kind,
+            form: None,
+            name: name.into(),
+            default_value: value.clone(),
+            value,
+            checked: false,
+            disabled: false,
+            visible: true,
+            label: String::new(),
+            options: Vec::new(),
+            size: 20,
+            default_checked: false,
+            action: None,
+            method: None,
+            encoding: None,
+        }
+    }
+
+    /// Whether the control's value is typed in.
+    pub fn is_editable(&self) -> bool {
+        matches!(
+            self.kind,
+            ControlKind::Text | ControlKind::Password | ControlKind::File | ControlKind::TextArea
+        )
+    }
+
+    pub fn is_submit(&self) -> bool {
+        matches!(self.kind, ControlKind::Submit | ControlKind::Image)
+    }
+
+    /// How the control looks on the page.
+    pub fn display(&self) -> String {
+        let field = |text: String| {
+            // Long values show their end, where typing happens.
+            let length = text.chars().count();
+            let visible = text
+                .chars()
+                .skip(length.saturating_sub(self.size))
+                .collect::<String>();
+            format!("[{:_<width$}]", visible, width = self.size)
+        };
+
+        match &self.kind {
+            ControlKind::Text | ControlKind::File => field(self.value.clone()),
+            ControlKind::Password => field("*".repeat(self.value.chars().count())),
+            ControlKind::TextArea => {
+                let mut lines = self.value.lines();
+                let first = lines.next().unwrap_or("").to_string();
+                match lines.next() {
+                    Some(_) => field(format!("{}…", first)),
+                    None => field(first),
+                }
+            }
+            ControlKind::Hidden => String::new(),
+            ControlKind::Checkbox => if self.checked { "[x]" } else { "[ ]" }.to_string(),
+            ControlKind::Radio => if self.checked { "(*)" } else { "( )" }.to_string(),
+            ControlKind::Submit | ControlKind::Image | ControlKind::Reset | ControlKind::Button => {
+                format!("[{}]", self.label)
+            }
+            ControlKind::Select { .. } => {
+                let selected = self
+                    .options
+                    .iter()
+                    .filter(|option| option.selected)
+                    .map(|option| option.label.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let arrow = if common::locale_is_utf8() { "▾" } else { "v" };
+                format!("[{} {}]", selected, arrow)
+            }
+        }
+    }
+
+    /// Puts the control back the way the page loaded it.
+    pub fn reset(&mut self) {
+        self.value = self.default_value.clone();
+        self.checked = self.default_checked;
+        for option in &mut self.options {
+            option.selected = option.default_selected;
+        }
+    }
+}
+
+/// A value in a form's data set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+    Text(String),
+    File { name: String, content: Vec<u8> },
+}
+
+/// Checks the radio button at `index` and unchecks the others in its group.
+pub fn check_radio(controls: &mut [Control], index: usize) {
+    let (name, form) = (controls[index].name.clone(), controls[index].form);
+
+    for (i, control) in controls.iter_mut().enumerate() {
+        if control.kind == ControlKind::Radio && control.name == name && control.form == form {
+            control.checked = i == index;
+        }
+    }
+}
+
+/// Resets every control in `form`.
+pub fn reset(controls: &mut [Control], form: usize) {
+    for control in controls.iter_mut().filter(|c| c.form == Some(form)) {
+        control.reset();
+    }
+}
+
+/// A media type for an uploaded file, from its extension.
+fn file_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("txt") => "text/plain",
+        Some("html" | "htm") => "text/html",
+        Some("css") => "text/css",
+        Some("csv") => "text/csv",
+        Some("json") => "application/json",
+        Some("pdf") => "application/pdf",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("zip") => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+/// The name and value pairs `form` sends when `submitter` is used to submit it.
+/// Files are only read with `read_files`, as other encodings send just their names.
+pub fn data(
+    controls: &[Control],
+    form: usize,
+    submitter: Option<usize>,
+    read_files: bool,
+) -> Result<Vec<(String, Value)>, String> {
+    let mut data = Vec::new();
+
+    for (i, control) in controls.iter().enumerate() {
+        if control.form != Some(form) || control.disabled {
+            continue;
+        }
+
+        let text = |value: &str| Value::Text(value.to_string());
+        match &control.kind {
+            ControlKind::Image if submitter == Some(i) => {
+                // Nothing was clicked on, so the point is the corner.
+                let prefix = match control.name.is_empty() {
+                    true => String::new(),
+                    false => format!("{}.", control.name),
+                };
+                data.push((format!("{}x", prefix), text("0")));
+                data.push((format!("{}y", prefix), text("0")));
+            }
+            _ if control.name.is_empty() => {}
+            ControlKind::Submit if submitter == Some(i) => {
+                data.push((control.name.clone(), text(&control.value)));
+            }
+            ControlKind::Checkbox | ControlKind::Radio if control.checked => {
+                let value = if control.value.is_empty() {
+                    "on"
+                } else {
+                    &control.value
+                };
+                data.push((control.name.clone(), text(value)));
+            }
+            ControlKind::Text | ControlKind::Password | ControlKind::Hidden => {
+                data.push((control.name.clone(), text(&control.value)));
+            }
+            // Line breaks are sent as CRLF, whatever the platform.
+            ControlKind::TextArea => {
+                let value = control.value.replace("\r\n", "\n").replace('\n', "\r\n");
+                data.push((control.name.clone(), Value::Text(value)));
+            }
+            ControlKind::Select { .. } => {
+                for option in control.options.iter().filter(|option| option.selected) {
+                    data.push((control.name.clone(), text(&option.value)));
+                }
+            }
+            ControlKind::File => {
+                let value = match control.value.is_empty() {
+                    true => Value::File {
+                        name: String::new(),
+                        content: Vec::new(),
+                    },
+                    false => {
+                        let path = Path::new(&control.value);
+                        let content = match read_files {
+                            true => fs::read(path).map_err(|error| {
+                                format!("Could not read {}: {}", path.display(), error)
+                            })?,
+                            false => Vec::new(),
+                        };
+                        let name = path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default();
+                        Value::File { name, content }
+                    }
+                };
+                data.push((control.name.clone(), value));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(data)
+}
+
+/// Encodes `data` as `application/x-www-form-urlencoded`. Files are sent by name.
+pub fn urlencode(data: &[(String, Value)]) -> String {
+    let encode = |text: &str| percent_encode(text, "*").replace("%20", "+");
+
+    data.iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Value::Text(text) => text,
+                Value::File { name, .. } => name,
+            };
+            format!("{}={}", encode(name), encode(value))
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Encodes `data` as `multipart/form-data` parts separated by `boundary`.
+pub fn multipart(data: &[(String, Value)], boundary: &str) -> Vec<u8> {
+    let escape = |text: &str| {
+        text.replace('"', "%22")
+            .replace('\r', "%0D")
+            .replace('\n', "%0A")
+    };
+
+    let mut body = Vec::new();
+    for (name, value) in data {
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        match value {
+            Value::Text(text) => {
+                body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"\r\n\r\n",
+                        escape(name)
+                    )
+                    .as_bytes(),
+                );
+                body.extend_from_slice(text.as_bytes());
+            }
+            Value::File {
+                name: file,
+                content,
+            } => {
+                body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                        escape(name),
+                        escape(file),
+                        file_type(Path::new(file))
+                    )
+                    .as_bytes(),
+                );
+                body.extend_from_slice(content);
+            }
+        }
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+/// Encodes `data` as `text/plain`, one `name=value` line per entry.
+fn plain(data: &[(String, Value)]) -> Vec<u8> {
+    data.iter()
+        .map(|(name, value)| match value {
+            Value::Text(text) => format!("{}={}\r\n", name, text),
+            Value::File { name: file, .. } => format!("{}={}\r\n", name, file),
+        })
+        .collect::<String>()
+        .into_bytes()
+}
+
+/// A boundary for multipart bodies, unlikely to appear inside them.
+fn boundary() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos());
+    format!("----hyperline{:024x}", nanos)
+}
+
+/// The request that submits the form `control` belongs to. Submit buttons
+/// submit their form themselves; other controls use the form's first
+/// submit button, as pressing Enter in a text field does.
+pub fn submit(forms: &[Form], controls: &[Control], control: usize) -> Result<Request, String> {
+    let form_index = controls
+        .get(control)
+        .and_then(|control| control.form)
+        .ok_or("This control is not part of a form.")?;
+    let form = forms
+        .get(form_index)
+        .ok_or("The form of this control is missing.")?;
+
+    let submitter = match controls[control].is_submit() {
+        true => Some(control),
+        false => controls
+            .iter()
+            .position(|c| c.form == Some(form_index) && c.is_submit() && !c.disabled),
+    };
+    let button = submitter.map(|index| &controls[index]);
+
+    let action = button
+        .and_then(|button| button.action.clone())
+        .unwrap_or_else(|| form.action.clone());
+    let method = button
+        .and_then(|button| button.method)
+        .unwrap_or(form.method);
+    let encoding = button
+        .and_then(|button| button.encoding)
+        .unwrap_or(form.encoding);
+
+    let read_files = method == Method::Post && encoding == Encoding::Multipart;
+    let data = data(controls, form_index, submitter, read_files)?;
+
+    Ok(match (method, encoding) {
+        (Method::Post, Encoding::UrlEncoded) => Request::post(
+            action,
+            "application/x-www-form-urlencoded",
+            urlencode(&data).into_bytes(),
+        ),
+        (Method::Post, Encoding::Multipart) => {
+            let boundary = boundary();
+            Request::post(
+                action,
+                &format!("multipart/form-data; boundary={}", boundary),
+                multipart(&data, &boundary),
+            )
+        }
+        (Method::Post, Encoding::TextPlain) => Request::post(action, "text/plain", plain(&data)),
+        _ => Request::get(Url {
+            query: Some(urlencode(&data)),
+            ..action
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    fn text(name: &str, value: &str) -> (String, Value) {
+        (name.to_string(), Value::Text(value.to_string()))
+    }
+
+    fn file(name: &str, file: &str, content: &[u8]) -> (String, Value) {
+        (
+            name.to_string(),
+            Value::File {
+                name: file.to_string(),
+                content: content.to_vec(),
+            },
+        )
+    }
+
+    fn in_form(mut control: Control) -> Control {
+        control.form = Some(0);
+        control
+    }
+
+    fn form(method: Method, encoding: Encoding) -> Form {
+        Form {
+            action: Url::parse("http://example.com/submit").unwrap(),
+            method,
+            encoding,
+        }
+    }
+
+    #[test]
+    fn urlencodes() {
+        assert_eq!(
+            urlencode(&[text("q", "a b"), text("sum", "1+1=2&more")]),
+            "q=a+b&sum=1%2B1%3D2%26more"
+        );
+        assert_eq!(
+            urlencode(&[text("név", "café ☕"), text("star", "*-._~")]),
+            "n%C3%A9v=caf%C3%A9+%E2%98%95&star=*-._~"
+        );
+        assert_eq!(urlencode(&[text("", ""), text("a", "")]), "=&a=");
+        assert_eq!(
+            urlencode(&[file("upload", "my file.txt", b"not sent")]),
+            "upload=my+file.txt"
+        );
+        assert_eq!(urlencode(&[]), "");
+    }
+
+    #[test]
+    fn encodes_multipart() {
+        let body = multipart(
+            &[
+                text("say", "hi\r\nthere"),
+                file("upload", "notes.txt", b"contents"),
+            ],
+            "XyZ",
+        );
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "--XyZ\r\n\
+             Content-Disposition: form-data; name=\"say\"\r\n\r\n\
+             hi\r\nthere\r\n\
+             --XyZ\r\n\
+             Content-Disposition: form-data; name=\"upload\"; filename=\"notes.txt\"\r\n\
+             Content-Type: text/plain\r\n\r\n\
+             contents\r\n\
+             --XyZ--\r\n"
+        );
+
+        assert_eq!(multipart(&[], "XyZ"), b"--XyZ--\r\n");
+        assert!(boundary().starts_with("----hyperline"));
+    }
+
+    #[test]
+    fn quotes_multipart_names() {
+        let body = multipart(&[file("a\"b", "say \"hi\"\r\n.PNG", b"")], "B");
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.contains(
+            "Content-Disposition: form-data; name=\"a%22b\"; filename=\"say %22hi%22%0D%0A.PNG\"\r\n\
+             Content-Type: image/png\r\n"
+        ));
+
+        let body = String::from_utf8(multipart(&[file("f", "", b"")], "B")).unwrap();
+        assert!(body.contains("filename=\"\"\r\nContent-Type: application/octet-stream\r\n"));
+    }
+
+    #[test]
+    fn reads_files_only_for_multipart() {
+        let dir = env::temp_dir().join(format!("hyperline-form-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("upload.txt");
+        fs::write(&path, b"uploaded").unwrap();
+        let missing = dir.join("missing.txt");
+
+        let controls = |path: &Path| {
+            vec![
+                in_form(Control::new(
+                    ControlKind::File,
+                    "upload",
+                    path.to_string_lossy(),
+                )),
+                in_form(Control::new(ControlKind::Submit, "", "Send")),
+            ]
+        };
+
+        let sent = data(&controls(&path), 0, Some(1), true).unwrap();
+        assert_eq!(sent, vec![file("upload", "upload.txt", b"uploaded")]);
+        let sent = data(&controls(&missing), 0, Some(1), false).unwrap();
+        assert_eq!(sent, vec![file("upload", "missing.txt", b"")]);
+        assert!(data(&controls(&missing), 0, Some(1), true).is_err());
+
+        let get = submit(
+            &[form(Method::Get, Encoding::Multipart)],
+            &controls(&missing),
+            1,
+        )
+        .unwrap();
+        assert_eq!(get.url.query.as_deref(), Some("upload=missing.txt"));
+        let post = submit(
+            &[form(Method::Post, Encoding::UrlEncoded)],
+            &controls(&missing),
+            1,
+        )
+        .unwrap();
+        assert_eq!(post.body, b"upload=missing.txt");
+        assert!(
+            submit(
+                &[form(Method::Post, Encoding::Multipart)],
+                &controls(&missing),
+                1
+            )
+            .is_err()
+        );
+        let post = submit(
+            &[form(Method::Post, Encoding::Multipart)],
+            &controls(&path),
+            1,
+        )
+        .unwrap();
+        assert!(
+            String::from_utf8(post.body).unwrap().contains(
+                "filename=\"upload.txt\"\r\nContent-Type: text/plain\r\n\r\nuploaded\r\n"
+            )
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_forms_are_an_error() {
+        let controls = [
+            in_form(Control::new(ControlKind::Text, "q", "x")),
+            Control::new(ControlKind::Submit, "", "Go"),
+        ];
+        assert_eq!(
+            submit(&[], &controls, 0),
+            Err("The form of this control is missing.".to_string())
+        );
+        assert!(submit(&[], &controls, 1).is_err());
+        assert!(submit(&[], &controls, 5).is_err());
+
+        let request = submit(&[form(Method::Get, Encoding::UrlEncoded)], &controls, 0).unwrap();
+        assert_eq!(request.url.query.as_deref(), Some("q=x"));
+    }
+}
@@ -0,0 +1,181 @@
+use crate::common::{Rect, Vec2};
+use crate::draw::boxes::BoxChar;
+
+/// Letters hint labels are made of, easiest to reach first.
+pub const ALPHABET: &str = "sadfjklewcmpgh";
+
+/// What choosing a hint does.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Follows the link.
+    Link(String),
+    /// Uses the form control with this index, as clicking it does.
+    Widget(usize),
+}
+
+/// A label shown over a link or control so it can be chosen by typing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hint {
+    pub label: String,
+    /// Where the target starts on screen.
+    pub position: Vec2,
+    pub target: Target,
+}
+
+/// `count` labels of equal length, so none is the start of another.
+pub fn labels(count: usize, alphabet: &str) -> Vec<String> {
+    let letters = alphabet.chars().collect::<Vec<_>>();
+    if letters.is_empty() {
+        return Vec::new();
+    }
+
+    let mut length = 1;
+    while letters.len().pow(length) < count {
+        length += 1;
+    }
+
+    (0..count)
+        .map(|mut n| {
+            let mut label = vec![letters[0]; length as usize];
+            for slot in label.iter_mut().rev() {
+                *slot = letters[n % letters.len()];
+                n /= letters.len();
+            }
+            label.into_iter().collect()
+        })
+        .collect()
+}
+
+/// A hint for every link and control drawn in `buffer` inside `area`,
+/// labelled in reading order. A target split over several lines gets a hint on each.
+pub fn collect(buffer: &[Vec<BoxChar>], area: &Rect) -> Vec<Hint> {
+    let mut targets = Vec::new();
+
+    let top = area.position.y.max(0) as usize;
+    let left = area.position.x.max(0) as usize;
+    for (y, row) in buffer.iter().enumerate().skip(top).take(area.size.y) {
+        let mut previous = None;
+        for (x, cell) in row.iter().enumerate().skip(left).take(area.size.x) {
+            let target = match (cell.widget, &cell.link) {
+                (Some(widget), _) => Some(Target::Widget(widget)),
+                (None, Some(link)) => Some(Target::Link(link.clone())),
+                (None, None) => None,
+            };
+
+            if let Some(found) = &target
+                && target != previous
+            {
+                targets.push((Vec2::new(x, y), found.clone()));
+            }
+            previous = target;
+        }
+    }
+
+    labels(targets.len(), ALPHABET)
+        .into_iter()
+        .zip(targets)
+        .map(|(label, (position, target))| Hint {
+            label,
+            position,
+            target,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_have_equal_length() {
+        let letters = ALPHABET.chars().map(String::from).collect::<Vec<_>>();
+        assert_eq!(labels(ALPHABET.len(), ALPHABET), letters);
+        assert_eq!(labels(3, "ab"), ["aa", "ab", "ba"]);
+        assert_eq!(labels(0, ALPHABET), Vec::<String>::new());
+        assert_eq!(labels(5, ""), Vec::<String>::new());
+
+        for (count, length) in [(ALPHABET.len() + 1, 2), (196, 2), (197, 3)] {
+            let labels = labels(count, ALPHABET);
+            assert_eq!(labels.len(), count);
+            assert!(labels.iter().all(|label| label.chars().count() == length));
+            for (i, label) in labels.iter().enumerate() {
+                for (j, other) in labels.iter().enumerate() {
+                    assert!(i == j || !other.starts_with(label.as_str()));
+                }
+            }
+        }
+    }
+
+    fn buffer(rows: &[&[(Option<&str>, Option<usize>)]]) -> Vec<Vec<BoxChar>> {
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|(link, widget)| BoxChar {
+                        link: link.map(str::to_string),
+                        widget: *widget,
+                        ..BoxChar::default()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn targets(hints: &[Hint]) -> Vec<(usize, usize, Target)> {
+        hints
+            .iter()
+            .map(|hint| (hint.position.x, hint.position.y, hint.target.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn merges_neighbouring_cells() {
+        let (a, b, none) = ((Some("a"), None), (Some("b"), None), (None, None));
+        // A control inside a link is hinted as the control.
+        let control = (Some("a"), Some(2));
+        let cells = buffer(&[
+            &[none, a, a, a, b, b, none, control, control, a],
+            &[a, a, none, a, none, none, none, none, none, none],
+        ]);
+        let area = Rect {
+            position: Vec2::new(0, 0),
+            size: Vec2::new(10, 2),
+        };
+        let link = |url: &str| Target::Link(url.to_string());
+
+        let hints = collect(&cells, &area);
+        assert_eq!(
+            targets(&hints),
+            [
+                (1, 0, link("a")),
+                (4, 0, link("b")),
+                (7, 0, Target::Widget(2)),
+                (9, 0, link("a")),
+                // A link going on to the next line gets a hint there too.
+                (0, 1, link("a")),
+                (3, 1, link("a")),
+            ]
+        );
+        let labels = hints
+            .iter()
+            .map(|hint| hint.label.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["s", "a", "d", "f", "j", "k"]);
+    }
+
+    #[test]
+    fn only_looks_inside_the_area() {
+        let a = (Some("a"), None);
+        let cells = buffer(&[&[a, a, a], &[a, a, a], &[a, a, a]]);
+        let area = Rect {
+            position: Vec2::new(1, 1),
+            size: Vec2::new(1, 5),
+        };
+        assert_eq!(
+            targets(&collect(&cells, &area)),
+            [
+                (1, 1, Target::Link("a".to_string())),
+                (1, 2, Target::Link("a".to_string()))
+            ]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use std::fs;
+use std::path::PathBuf;
+
+/// How many pages are remembered. The ones least likely to be wanted again
+/// are forgotten first.
+const MAX_VISITS: usize = 5000;
+
+/// How much a visit counts for by how many days ago it was, newest first.
+/// Older visits count for the last weight.
+const RECENCY_WEIGHTS: &[(u64, f64)] = &[(4, 100.0), (14, 70.0), (31, 50.0), (90, 30.0)];
+const OLD_WEIGHT: f64 = 10.0;
+
+/// How many seconds visits may wait to be saved while browsing.
+const SAVE_INTERVAL: u64 = 30;
+
+/// A page that has been visited, however many times.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Visit {
+    pub url: String,
+    pub title: String,
+    pub count: u32,
+    /// Seconds since the Unix epoch.
+    pub last: u64,
+}
+
+impl Visit {
+    /// How likely the page is to be wanted again, from how often and how
+    /// recently it was visited.
+    pub fn frecency(&self, now: u64) -> f64 {
+        let days = now.saturating_sub(self.last) / (24 * 60 * 60);
+        let weight = RECENCY_WEIGHTS
+            .iter()
+            .find(|(within, _)| days < *within)
+            .map_or(OLD_WEIGHT, |(_, weight)| *weight);
+        self.count as f64 * weight
+    }
+
+    /// The URL without what is usually left out when typing it, such as
+    /// `https://www.`.
+    fn short_url(&self) -> &str {
+        let url = self.url.to_lowercase();
+        let start = ["https://", "http://", "file://"]
+            .iter()
+            .find(|scheme| url.starts_with(*scheme))
+            .map_or(0, |scheme| scheme.len());
+        let start = match url[start..].starts_with("www.") {
+            true => start + 4,
+            false => start,
+        };
+        &self.url[start..]
+    }
+}
+
+/// Every page visited in any tab, saved across runs so they can be offered
+/// again when typing a URL.
+#[derive(Debug, Default)]
+pub struct History {
+    pub visits: Vec<Visit>,
+    /// Where history is saved, at most every `SAVE_INTERVAL` seconds and
+    /// on exit.
+    pub file: Option<PathBuf>,
+    /// Whether there are visits the file does not have yet.
+    unsaved: bool,
+    /// When the history was last saved.
+    saved_at: u64,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History::default()
+    }
+
+    /// Loads the history saved in `file`, which is kept up to date from then
+    /// on. A missing file means nothing has been visited yet.
+    pub fn open(file: PathBuf) -> Result<Self, String> {
+        let mut history = History {
+            file: Some(file.clone()),
+            ..History::default()
+        };
+
+        let text = match fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(history),
+            Err(error) => return Err(format!("Could not read {}: {}", file.display(), error)),
+        };
+
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let [last, count, url, title] = line.splitn(4, '\t').collect::<Vec<_>>()[..] else {
+                continue;
+            };
+            history.visits.push(Visit {
+                url: url.to_string(),
+                title: title.to_string(),
+                count: count.parse().unwrap_or(1),
+                last: last.parse().unwrap_or(0),
+            });
+        }
+
+        Ok(history)
+    }
+
+    /// Writes the history to its file.
+    pub fn save(&self) -> Result<(), String> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+
+        let mut text = String::from("# hyperline history: last visited, visits, URL, title\n");
+        for visit in &self.visits {
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                visit.last,
+                visit.count,
+                visit.url,
+                visit.title.replace(['\t', '\n'], " ")
+            ));
+        }
+
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+        }
+        fs::write(file, text).map_err(|error| format!("Could not save history: {}", error))
+    }
+
+    pub fn get(&self, url: &str) -> Option<&Visit> {
+        self.visits.iter().find(|visit| visit.url == url)
+    }
+
+    /// Counts a visit to `url` at `now`, keeping the page's latest title.
+    pub fn record(&mut self, url: &str, title: &str, now: u64) {
+        match self.visits.iter_mut().find(|visit| visit.url == url) {
+            Some(visit) => {
+                visit.count += 1;
+                visit.last = now;
+                if !title.is_empty() {
+                    visit.title = title.to_string();
+                }
+            }
+            None => self.visits.push(Visit {
+                url: url.to_string(),
+                title: title.to_string(),
+                count: 1,
+                last: now,
+            }),
+        }
+
+        if self.visits.len() > MAX_VISITS {
+            self.visits
+                .sort_by(|a, b| b.frecency(now).total_cmp(&a.frecency(now)));
+            self.visits.truncate(MAX_VISITS);
+        }
+
+        self.unsaved = true;
+        self.save_if_due(now);
+    }
+
+    /// Saves visits not saved yet, unless the history was saved less than
+    /// `SAVE_INTERVAL` seconds before `now`.
+    pub fn save_if_due(&mut self, now: u64) {
+        if self.unsaved && now.saturating_sub(self.saved_at) >= SAVE_INTERVAL {
+            self.saved_at = now;
+            // Saving is best effort; history still works for this session.
+            let _ = self.flush();
+        }
+    }
+
+    /// Saves visits not saved yet, as on exit.
+    pub fn flush(&mut self) -> Result<(), String> {
+        if self.unsaved {
+            self.save()?;
+            self.unsaved = false;
+        }
+        Ok(())
+    }
+
+    /// Up to `limit` pages for what has been typed into the URL prompt, the
+    /// most frecent first. Every word has to be found in the URL or title,
+    /// and pages whose URL starts with what was typed come before the rest.
+    pub fn complete(&self, input: &str, now: u64, limit: usize) -> Vec<&Visit> {
+        let input = input.trim().to_lowercase();
+        if input.is_empty() {
+            return Vec::new();
+        }
+
+        let mut found = self
+            .visits
+            .iter()
+            .filter(|visit| {
+                let haystack = format!("{} {}", visit.url, visit.title).to_lowercase();
+                input.split_whitespace().all(|word| haystack.contains(word))
+            })
+            .map(|visit| {
+                let prefix = visit.url.to_lowercase().starts_with(&input)
+                    || visit.short_url().to_lowercase().starts_with(&input);
+                (prefix, visit.frecency(now), visit)
+            })
+            .collect::<Vec<_>>();
+
+        found.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.total_cmp(&a.1)));
+        found
+            .into_iter()
+            .take(limit)
+            .map(|(_, _, visit)| visit)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::path::Path;
+    use std::process;
+
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn saved_urls(file: &Path) -> Vec<String> {
+        History::open(file.to_path_buf())
+            .unwrap()
+            .visits
+            .into_iter()
+            .map(|visit| visit.url)
+            .collect()
+    }
+
+    #[test]
+    fn saves_visits_together() {
+        let dir = env::temp_dir().join(format!("hyperline-history-{}", process::id()));
+        let file = dir.join("history.txt");
+        let _ = fs::remove_dir_all(&dir);
+        let mut history = History::open(file.clone()).unwrap();
+
+        history.record("http://a/", "A", 1000);
+        assert_eq!(saved_urls(&file), ["http://a/"]);
+
+        history.record("http://b/", "B", 1010);
+        history.record("http://a/", "", 1020);
+        history.save_if_due(1020);
+        assert_eq!(saved_urls(&file), ["http://a/"]);
+
+        history.save_if_due(1000 + SAVE_INTERVAL);
+        assert_eq!(saved_urls(&file), ["http://a/", "http://b/"]);
+
+        history.record("http://c/", "C\tthe\nthird", 1040);
+        history.flush().unwrap();
+        let loaded = History::open(file.clone()).unwrap();
+        assert_eq!(loaded.visits.len(), 3);
+        assert_eq!(loaded.get("http://c/").unwrap().title, "C the third");
+        assert_eq!(loaded.get("http://a/").unwrap().count, 2);
+        assert_eq!(loaded.get("http://a/").unwrap().title, "A");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn completes_prefixes_first_then_by_frecency() {
+        let now = 1000 * DAY;
+        let mut history = History::new();
+        history.record("https://www.rust-lang.org/", "Rust", now - 60 * DAY);
+        history.record("https://docs.rs/regex", "regex - Rust", now);
+        history.record("https://docs.rs/regex", "regex - Rust", now);
+        history.record("https://example.com/rust", "Not it", now - 200 * DAY);
+
+        let urls = |input: &str| {
+            history
+                .complete(input, now, 10)
+                .into_iter()
+                .map(|visit| visit.url.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            urls("rust"),
+            [
+                "https://www.rust-lang.org/",
+                "https://docs.rs/regex",
+                "https://example.com/rust"
+            ]
+        );
+        assert_eq!(urls("REGEX rust"), ["https://docs.rs/regex"]);
+        assert_eq!(urls("docs"), ["https://docs.rs/regex"]);
+        assert!(urls("  ").is_empty());
+        assert_eq!(history.complete("rust", now, 1).len(), 1);
+    }
+
+    #[test]
+    fn frecency_weighs_recent_visits_more() {
+        let visit = |count, days_ago| Visit {
+            url: String::new(),
+            title: String::new(),
+            count,
+            last: 1000 * DAY - days_ago * DAY,
+        };
+        let now = 1000 * DAY;
+        assert_eq!(visit(1, 0).frecency(now), 100.0);
+        assert_eq!(visit(2, 20).frecency(now), 100.0);
+        assert_eq!(visit(3, 400).frecency(now), 30.0);
+    }
+}
@@ -0,0 +1,83 @@
+use termion::event::Key;
+
+/// A single line of editable text, such as the URL prompt.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LineInput {
+    pub text: String,
+    /// Position of the cursor, in characters.
+    pub cursor: usize,
+    /// Enter starts a new line instead of submitting, and Ctrl-d submits.
+    pub multiline: bool,
+}
+
+/// What a key did to a `LineInput`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputEvent {
+    Edited,
+    Submitted,
+    Cancelled,
+    Ignored,
+}
+
+impl LineInput {
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let cursor = text.chars().count();
+        LineInput {
+            text,
+            cursor,
+            multiline: false,
+        }
+    }
+
+    /// An input for several lines of text, such as a `<textarea>`.
+    pub fn multiline(text: impl Into<String>) -> Self {
+        LineInput {
+            multiline: true,
+            ..LineInput::new(text)
+        }
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    pub fn handle_key(&mut self, key: Key) -> InputEvent {
+        let length = self.text.chars().count();
+
+        match key {
+            Key::Char('\n') if !self.multiline => return InputEvent::Submitted,
+            Key::Ctrl('d') if self.multiline => return InputEvent::Submitted,
+            Key::Esc | Key::Ctrl('c') => return InputEvent::Cancelled,
+            Key::Char(c) => {
+                let index = self.byte_index(self.cursor);
+                self.text.insert(index, c);
+                self.cursor += 1;
+            }
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let index = self.byte_index(self.cursor);
+                self.text.remove(index);
+            }
+            Key::Delete if self.cursor < length => {
+                let index = self.byte_index(self.cursor);
+                self.text.remove(index);
+            }
+            Key::Ctrl('u') => {
+                let index = self.byte_index(self.cursor);
+                self.text.replace_range(..index, "");
+                self.cursor = 0;
+            }
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(length),
+            Key::Home | Key::Ctrl('a') => self.cursor = 0,
+            Key::End | Key::Ctrl('e') => self.cursor = length,
+            _ => return InputEvent::Ignored,
+        }
+
+        InputEvent::Edited
+    }
+}
@@ -0,0 +1,12 @@
+pub mod about;
+pub mod bookmarks;
+pub mod document;
+pub mod fetch;
+pub mod file;
+pub mod find;
+pub mod form;
+pub mod hints;
+pub mod history;
+pub mod input;
+pub mod session;
+pub mod url;
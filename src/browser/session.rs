@@ -0,0 +1,427 @@
+use std::collections::HashSet;
+
+use crate::browser::document::Document;
+use crate::browser::fetch::{Fetcher, LoadError};
+use crate::browser::find::{FindOptions, Search};
+use crate::browser::form::{self, Control, ControlKind};
+use crate::browser::history::History;
+use crate::browser::url::Url;
+use crate::common::{Rect, Vec2};
+use crate::draw::text::{StyledLine, TextStyle};
+use crate::draw::theme::Role;
+use crate::net::date;
+use crate::net::http::Method;
+use crate::window::{Container, DivOptions};
+
+/// A visited page and how far down it was scrolled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub url: Url,
+    pub scroll: usize,
+}
+
+/// One browsing context: the page being shown and the pages behind and ahead of it.
+#[derive(Clone, Debug, Default)]
+pub struct Session {
+    back: Vec<HistoryEntry>,
+    forward: Vec<HistoryEntry>,
+    current: Option<HistoryEntry>,
+
+    document: Option<Document>,
+    /// The document laid out for the viewport, rebuilt when either changes.
+    lines: Vec<StyledLine>,
+    viewport: Vec2,
+
+    /// What is being searched for on the page, found again after every layout.
+    search: Option<Search>,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session::default()
+    }
+
+    pub fn document(&self) -> Option<&Document> {
+        self.document.as_ref()
+    }
+
+    pub fn url(&self) -> Option<&Url> {
+        self.current.as_ref().map(|entry| &entry.url)
+    }
+
+    pub fn scroll(&self) -> usize {
+        self.current.as_ref().map_or(0, |entry| entry.scroll)
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+
+    /// Loads `url` and shows it, or an error page if it could not be loaded.
+    fn load(&mut self, fetcher: &mut Fetcher, url: Url, scroll: usize, initiator: Option<&Url>) {
+        let result = fetcher.fetch_from(&url, initiator);
+        let loaded = result.is_ok();
+        self.show(url, result, scroll);
+        if loaded {
+            self.record_visit(fetcher);
+        }
+    }
+
+    /// Adds the page being shown to the history kept across sessions.
+    /// Built-in pages are left out, as they are never typed to get to.
+    fn record_visit(&self, fetcher: &mut Fetcher) {
+        if let (Some(url), Some(document)) = (self.url(), &self.document)
+            && url.scheme != "about"
+        {
+            let url = url.without_fragment().to_string();
+            fetcher.history.record(&url, &document.title, date::now());
+        }
+    }
+
+    fn show(&mut self, url: Url, result: Result<Document, LoadError>, scroll: usize) {
+        let document =
+            result.unwrap_or_else(|error| Document::error(url.clone(), &error.to_string()));
+
+        // Redirects change where the page actually came from.
+        let url = match document.url.without_fragment() == url.without_fragment() {
+            true => url,
+            false => document.url.clone(),
+        };
+
+        self.current = Some(HistoryEntry { url, scroll });
+        self.document = Some(document);
+        self.search = None;
+        self.relayout();
+    }
+
+    /// Goes to `url`, making the current page the one `back` returns to.
+    pub fn navigate(&mut self, fetcher: &mut Fetcher, url: Url) {
+        self.navigate_from(fetcher, url, None);
+    }
+
+    fn navigate_from(&mut self, fetcher: &mut Fetcher, url: Url, initiator: Option<Url>) {
+        self.leave();
+        self.load(fetcher, url, 0, initiator.as_ref());
+    }
+
+    /// Moves the current page into the back history, before going somewhere new.
+    fn leave(&mut self) {
+        if let Some(entry) = self.current.take() {
+            self.back.push(entry);
+        }
+        self.forward.clear();
+    }
+
+    /// Goes to what the user typed into the URL prompt.
+    pub fn open(&mut self, fetcher: &mut Fetcher, input: &str) -> Result<(), String> {
+        let url = Url::from_input(input)?;
+        self.navigate(fetcher, url);
+        Ok(())
+    }
+
+    /// Follows a link on the current page, resolving it against the page's URL.
+    pub fn follow(&mut self, fetcher: &mut Fetcher, target: &str) -> Result<(), String> {
+        let initiator = self.url().cloned();
+        let url = match &initiator {
+            Some(base) => base.join(target)?,
+            None => Url::parse(target)?,
+        };
+        self.navigate_from(fetcher, url, initiator);
+        Ok(())
+    }
+
+    /// Returns to the previous page where it was left. Returns whether there was one.
+    pub fn go_back(&mut self, fetcher: &mut Fetcher) -> bool {
+        let Some(entry) = self.back.pop() else {
+            return false;
+        };
+
+        if let Some(current) = self.current.take() {
+            self.forward.push(current);
+        }
+        self.load(fetcher, entry.url, entry.scroll, None);
+        true
+    }
+
+    /// Undoes `go_back`. Returns whether there was a page to go forward to.
+    pub fn go_forward(&mut self, fetcher: &mut Fetcher) -> bool {
+        let Some(entry) = self.forward.pop() else {
+            return false;
+        };
+
+        if let Some(current) = self.current.take() {
+            self.back.push(current);
+        }
+        self.load(fetcher, entry.url, entry.scroll, None);
+        true
+    }
+
+    /// Loads the current page again, keeping its place in history and its scroll position.
+    pub fn reload(&mut self, fetcher: &mut Fetcher) {
+        if let Some(entry) = self.current.take() {
+            let result = fetcher.reload(&entry.url);
+            self.show(entry.url, result, entry.scroll);
+        }
+    }
+
+    /// The form control at `index` on the current page.
+    pub fn control(&self, index: usize) -> Option<&Control> {
+        self.document.as_ref()?.controls.get(index)
+    }
+
+    fn controls_mut(&mut self) -> &mut [Control] {
+        match &mut self.document {
+            Some(document) => &mut document.controls,
+            None => &mut [],
+        }
+    }
+
+    /// Sets what a text field holds.
+    pub fn set_value(&mut self, index: usize, value: String) {
+        if let Some(control) = self.controls_mut().get_mut(index) {
+            control.value = value;
+        }
+        self.relayout();
+    }
+
+    /// Ticks or unticks a checkbox, or picks a radio button over the others in its group.
+    pub fn toggle(&mut self, index: usize) {
+        let controls = self.controls_mut();
+        match controls.get(index).map(|control| &control.kind) {
+            Some(ControlKind::Checkbox) => controls[index].checked = !controls[index].checked,
+            Some(ControlKind::Radio) => form::check_radio(controls, index),
+            _ => {}
+        }
+        self.relayout();
+    }
+
+    /// Picks an option of a `<select>`. In lists that allow several, the
+    /// option is toggled instead.
+    pub fn select(&mut self, index: usize, option: usize) {
+        if let Some(control) = self.controls_mut().get_mut(index)
+            && let ControlKind::Select { multiple } = control.kind
+            && option < control.options.len()
+        {
+            for (i, choice) in control.options.iter_mut().enumerate() {
+                if multiple && i == option {
+                    choice.selected = !choice.selected;
+                } else if !multiple {
+                    choice.selected = i == option;
+                }
+            }
+        }
+        self.relayout();
+    }
+
+    /// Resets the form the control at `index` belongs to.
+    pub fn reset_form(&mut self, index: usize) {
+        let controls = self.controls_mut();
+        if let Some(form) = controls.get(index).and_then(|control| control.form) {
+            form::reset(controls, form);
+        }
+        self.relayout();
+    }
+
+    /// Submits the form the control at `index` belongs to and shows the response.
+    pub fn submit(&mut self, fetcher: &mut Fetcher, index: usize) -> Result<(), String> {
+        let document = self.document.as_ref().ok_or("No page is open.")?;
+        let mut request = form::submit(&document.forms, &document.controls, index)?;
+        request.initiator = self.url().cloned();
+
+        let url = request.url.clone();
+        let is_get = request.method == Method::Get;
+        self.leave();
+        let result = match request.method {
+            // GET forms work with any URL, such as files and about pages.
+            Method::Get => fetcher.fetch_from(&url, request.initiator.as_ref()),
+            _ => fetcher.send(request),
+        };
+        // Only GET submissions can be visited again from their URL.
+        let visited = result.is_ok() && is_get;
+        self.show(url, result, 0);
+        if visited {
+            self.record_visit(fetcher);
+        }
+        Ok(())
+    }
+
+    fn relayout(&mut self) {
+        self.lines = self
+            .document
+            .as_ref()
+            .map(|document| document.layout(self.viewport.x))
+            .unwrap_or_default();
+
+        let max = self.max_scroll();
+        if let Some(entry) = &mut self.current {
+            entry.scroll = entry.scroll.min(max);
+        }
+
+        // Matches move when the page is laid out again.
+        let scroll = self.scroll();
+        if let Some(search) = &mut self.search {
+            let line = search.current_match().map_or(scroll, |found| found.line);
+            search.run(&self.lines);
+            search.select_from(line);
+        }
+    }
+
+    /// Sets the size of the area the page is shown in.
+    pub fn set_viewport(&mut self, size: Vec2) {
+        if size != self.viewport {
+            self.viewport = size;
+            self.relayout();
+        }
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(self.viewport.y)
+    }
+
+    pub fn scroll_to(&mut self, line: usize) {
+        let max = self.max_scroll();
+        if let Some(entry) = &mut self.current {
+            entry.scroll = line.min(max);
+        }
+    }
+
+    pub fn scroll_by(&mut self, lines: isize) {
+        self.scroll_to(self.scroll().saturating_add_signed(lines));
+    }
+
+    /// Scrolls by whole viewports, keeping a line of overlap.
+    pub fn scroll_pages(&mut self, pages: isize) {
+        let page = self.viewport.y.saturating_sub(1).max(1) as isize;
+        self.scroll_by(pages * page);
+    }
+
+    /// Scrolls just enough to show `line`, leaving some of the page above it.
+    fn reveal(&mut self, line: usize) {
+        let scroll = self.scroll();
+        if line < scroll || line >= scroll + self.viewport.y {
+            self.scroll_to(line.saturating_sub(self.viewport.y / 3));
+        }
+    }
+
+    /// Searches the page for `query`, going to the first match on or after
+    /// line `from`. An empty query ends the search.
+    pub fn find(&mut self, query: &str, options: FindOptions, from: usize) -> Result<(), String> {
+        if query.is_empty() {
+            self.search = None;
+            return Ok(());
+        }
+
+        let mut search = Search::new(query, options)?;
+        search.run(&self.lines);
+        let line = search.select_from(from).map(|found| found.line);
+        self.search = Some(search);
+
+        if let Some(line) = line {
+            self.reveal(line);
+        }
+        Ok(())
+    }
+
+    /// Goes to the next match of the search, or the previous one. Returns
+    /// false when there is no search or nothing matched.
+    pub fn find_next(&mut self, forward: bool) -> bool {
+        let line = self
+            .search
+            .as_mut()
+            .and_then(|search| search.step(forward))
+            .map(|found| found.line);
+
+        if let Some(line) = line {
+            self.reveal(line);
+        }
+        line.is_some()
+    }
+
+    pub fn clear_find(&mut self) {
+        self.search = None;
+    }
+
+    pub fn search(&self) -> Option<&Search> {
+        self.search.as_ref()
+    }
+
+    /// The lines of the page that are currently in view.
+    pub fn visible_lines(&self) -> &[StyledLine] {
+        let start = self.scroll().min(self.lines.len());
+        let end = (start + self.viewport.y).min(self.lines.len());
+        &self.lines[start..end]
+    }
+
+    /// A one-line summary of where the session is, for a status bar.
+    pub fn status(&self) -> String {
+        let Some(url) = self.url() else {
+            return String::new();
+        };
+
+        let title = self
+            .document
+            .as_ref()
+            .map(|document| document.title.as_str())
+            .filter(|title| !title.is_empty());
+
+        match title {
+            Some(title) => format!("{} — {}", title, url),
+            None => url.to_string(),
+        }
+    }
+
+    /// Draws the visible part of the page into `rect` of `container`, with
+    /// what was found on it in the colors for matches and links to pages in
+    /// `history` marked as visited.
+    pub fn draw(&mut self, container: &mut Container, rect: &Rect, history: &History) {
+        self.set_viewport(rect.size.clone());
+
+        let urls = history
+            .visits
+            .iter()
+            .map(|visit| visit.url.as_str())
+            .collect::<HashSet<_>>();
+        let visited = |line: &StyledLine| {
+            let styles = line
+                .chars()
+                .map(|(c, style)| match &style.link {
+                    Some(link)
+                        if style.role == Some(Role::Link)
+                            && urls.contains(link.split('#').next().unwrap_or(link)) =>
+                    {
+                        let style = TextStyle {
+                            role: Some(Role::VisitedLink),
+                            ..style.clone()
+                        };
+                        (c, style)
+                    }
+                    _ => (c, style.clone()),
+                })
+                .collect::<Vec<_>>();
+            StyledLine::from_chars(styles.iter().map(|(c, style)| (*c, style)))
+        };
+
+        container.draw_box(DivOptions {
+            id: Some("page".to_string()),
+
+            position: rect.position.clone(),
+            size: rect.size.clone(),
+
+            content: Some(match &self.search {
+                Some(search) => self
+                    .visible_lines()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, line)| search.highlight(self.scroll() + i, &visited(line)))
+                    .collect(),
+                None => self.visible_lines().iter().map(visited).collect(),
+            }),
+
+            ..Default::default()
+        });
+    }
+}
@@ -0,0 +1,522 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Schemes whose URLs have no `//` and host, like `about:blank`.
+const NO_AUTHORITY: &[&str] = &["about", "data", "file", "mailto"];
+
+/// An absolute URL, split into the parts hyperline needs to load it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Url {
+    pub scheme: String,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub path: String,
+    pub query: Option<String>,
+    pub fragment: Option<String>,
+}
+
+impl Url {
+    pub fn parse(input: &str) -> Result<Url, String> {
+        let input = input.trim();
+
+        let colon = input
+            .find(':')
+            .filter(|&i| i > 0)
+            .ok_or_else(|| format!("'{}' is not an absolute URL.", input))?;
+        let scheme = &input[..colon];
+        if !scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        {
+            return Err(format!("'{}' is not an absolute URL.", input));
+        }
+
+        let (rest, fragment) = split_off(&input[colon + 1..], '#');
+        let (rest, query) = split_off(rest, '?');
+
+        let (host, port, path) = match rest.strip_prefix("//") {
+            Some(rest) => {
+                let end = rest.find('/').unwrap_or(rest.len());
+                let (authority, path) = rest.split_at(end);
+                // User info is not supported, so it is dropped.
+                let authority = authority.rsplit('@').next().unwrap_or(authority);
+
+                // In an IPv6 host such as `[::1]:8080`, only a colon after
+                // the `]` starts the port.
+                let port_colon = match authority.rfind(']') {
+                    Some(end) => authority[end..].find(':').map(|i| end + i),
+                    None => authority.rfind(':'),
+                };
+                let (host, port) = match port_colon {
+                    Some(colon) => {
+                        let (host, port) = (&authority[..colon], &authority[colon + 1..]);
+                        // An empty port means the scheme's default.
+                        let port = match port.is_empty() {
+                            true => None,
+                            false => Some(
+                                port.parse::<u16>()
+                                    .map_err(|_| format!("Invalid port in '{}'.", input))?,
+                            ),
+                        };
+                        (host, port)
+                    }
+                    None => (authority, None),
+                };
+
+                let path = if path.is_empty() { "/" } else { path };
+                (Some(host.to_ascii_lowercase()), port, path.to_string())
+            }
+            None => (None, None, rest.to_string()),
+        };
+
+        Ok(Url {
+            scheme: scheme.to_ascii_lowercase(),
+            host,
+            port,
+            path,
+            query: query.map(str::to_string),
+            fragment: fragment.map(str::to_string),
+        })
+    }
+
+    /// Resolves `reference`, such as the target of a link, against this URL.
+    pub fn join(&self, reference: &str) -> Result<Url, String> {
+        let reference = reference.trim();
+
+        if let Ok(url) = Url::parse(reference)
+            && !reference.starts_with(|c: char| !c.is_ascii_alphabetic())
+        {
+            return Ok(url);
+        }
+
+        if reference.starts_with("//") {
+            return Url::parse(&format!("{}:{}", self.scheme, reference));
+        }
+
+        let (rest, fragment) = split_off(reference, '#');
+        let (path, query) = split_off(rest, '?');
+
+        let mut url = self.clone();
+        url.fragment = fragment.map(str::to_string);
+
+        if path.is_empty() {
+            if query.is_some() {
+                url.query = query.map(str::to_string);
+            }
+            return Ok(url);
+        }
+
+        url.query = query.map(str::to_string);
+        url.path = if path.starts_with('/') {
+            remove_dot_segments(path)
+        } else {
+            let directory = &self.path[..self.path.rfind('/').map_or(0, |i| i + 1)];
+            remove_dot_segments(&format!("{}{}", directory, path))
+        };
+
+        Ok(url)
+    }
+
+    /// The port to connect to, falling back to the scheme's default.
+    pub fn port_or_default(&self) -> Option<u16> {
+        self.port.or(match self.scheme.as_str() {
+            "http" => Some(80),
+            "https" => Some(443),
+            _ => None,
+        })
+    }
+
+    /// The path and query, as sent in an HTTP request line. Characters that
+    /// may not appear there, such as spaces, are percent-encoded.
+    pub fn request_target(&self) -> String {
+        match &self.query {
+            Some(query) => format!("{}?{}", escape_invalid(&self.path), escape_invalid(query)),
+            None => escape_invalid(&self.path),
+        }
+    }
+
+    /// A `file:` URL for an absolute `path`.
+    pub fn from_path(path: &Path) -> Url {
+        Url {
+            scheme: "file".to_string(),
+            host: Some(String::new()),
+            port: None,
+            path: percent_encode(&path.to_string_lossy(), "/"),
+            query: None,
+            fragment: None,
+        }
+    }
+
+    /// What the user typed as an address: a URL, or a path to a local file,
+    /// which may be relative to the working directory or start with `~`.
+    pub fn from_input(input: &str) -> Result<Url, String> {
+        let input = input.trim();
+
+        let looks_like_path = input.starts_with(['/', '.', '~']) || Path::new(input).exists();
+        if !looks_like_path {
+            // Anything without `//` and a scheme that needs none, such as
+            // `example.com` or `localhost:8080`, is taken as a web address.
+            return match Url::parse(input) {
+                Ok(url) if input.contains("://") || NO_AUTHORITY.contains(&url.scheme.as_str()) => {
+                    Ok(url)
+                }
+                _ => Url::parse(&format!("http://{}", input)),
+            };
+        }
+
+        let path = match input.strip_prefix('~') {
+            Some(rest) => {
+                let home = std::env::var("HOME").map_err(|_| "HOME is not set.".to_string())?;
+                PathBuf::from(format!("{}{}", home, rest))
+            }
+            None => PathBuf::from(input),
+        };
+        let path = std::path::absolute(&path).map_err(|error| error.to_string())?;
+
+        Ok(Url::from_path(&path))
+    }
+
+    /// The local path a `file:` URL points to.
+    pub fn to_path(&self) -> Option<PathBuf> {
+        if self.scheme != "file" {
+            return None;
+        }
+
+        Some(PathBuf::from(percent_decode(&self.path)))
+    }
+
+    /// The `key=value` pairs of the query, decoded as a form would encode them.
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        let decode = |text: &str| percent_decode(&text.replace('+', " "));
+
+        self.query
+            .as_deref()
+            .unwrap_or("")
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) => (decode(key), decode(value)),
+                None => (decode(pair), String::new()),
+            })
+            .collect()
+    }
+
+    /// This URL without its fragment, for comparing documents.
+    pub fn without_fragment(&self) -> Url {
+        Url {
+            fragment: None,
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.scheme)?;
+        if let Some(host) = &self.host {
+            write!(f, "//{}", host)?;
+            if let Some(port) = self.port {
+                write!(f, ":{}", port)?;
+            }
+        }
+        write!(f, "{}", self.path)?;
+        if let Some(query) = &self.query {
+            write!(f, "?{}", query)?;
+        }
+        if let Some(fragment) = &self.fragment {
+            write!(f, "#{}", fragment)?;
+        }
+        Ok(())
+    }
+}
+
+/// Escapes every byte of `text` that is not an unreserved URL character or in `keep`.
+pub fn percent_encode(text: &str, keep: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric()
+            || b"-._~".contains(&byte)
+            || keep.as_bytes().contains(&byte)
+        {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Escapes spaces, control characters, non-ASCII text and the characters
+/// URLs may never contain, leaving everything else, including escapes, as it is.
+fn escape_invalid(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_graphic() && !b"\"<>\\^`{|}".contains(&byte) {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    escaped
+}
+
+/// Undoes `percent_encode`. Escapes that do not decode to UTF-8 are replaced.
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn split_off(input: &str, separator: char) -> (&str, Option<&str>) {
+    match input.split_once(separator) {
+        Some((before, after)) => (before, Some(after)),
+        None => (input, None),
+    }
+}
+
+/// Resolves the `.` and `..` segments of `path`. Paths without a leading
+/// `/`, such as those of `about:` URLs, stay relative.
+fn remove_dot_segments(path: &str) -> String {
+    let absolute = path.starts_with('/');
+    let mut segments: Vec<&str> = Vec::new();
+
+    for segment in path.split('/').skip(absolute as usize) {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    // A trailing `.` or `..` still names a directory.
+    if path.ends_with("/.") || path.ends_with("/..") {
+        segments.push("");
+    }
+
+    match absolute {
+        true => format!("/{}", segments.join("/")),
+        false => segments.join("/"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Url {
+        Url::parse(input).unwrap()
+    }
+
+    #[test]
+    fn parses_the_parts_of_a_url() {
+        let url = parse("HTTPS://User@Example.COM:8443/a/b?c=d#e");
+        assert_eq!(url.scheme, "https");
+        assert_eq!(url.host.as_deref(), Some("example.com"));
+        assert_eq!(url.port, Some(8443));
+        assert_eq!(url.path, "/a/b");
+        assert_eq!(url.query.as_deref(), Some("c=d"));
+        assert_eq!(url.fragment.as_deref(), Some("e"));
+        assert_eq!(url.to_string(), "https://example.com:8443/a/b?c=d#e");
+
+        let url = parse("http://example.com");
+        assert_eq!((url.path.as_str(), url.port), ("/", None));
+        assert_eq!(url.port_or_default(), Some(80));
+        assert_eq!(parse("http://example.com:/").port, None);
+
+        let url = parse("about:blank");
+        assert_eq!((url.host, url.path.as_str()), (None, "blank"));
+    }
+
+    #[test]
+    fn parses_ipv6_hosts() {
+        let url = parse("http://[::1]/");
+        assert_eq!(url.host.as_deref(), Some("[::1]"));
+        assert_eq!(url.port, None);
+
+        let url = parse("http://[2001:db8::7]:8080/index.html");
+        assert_eq!(url.host.as_deref(), Some("[2001:db8::7]"));
+        assert_eq!(url.port, Some(8080));
+        assert_eq!(url.to_string(), "http://[2001:db8::7]:8080/index.html");
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_url() {
+        assert!(Url::parse("example.com").is_err());
+        assert!(Url::parse(":nothing").is_err());
+        assert!(Url::parse("a b:c").is_err());
+        assert!(Url::parse("http://example.com:http/").is_err());
+        assert!(Url::parse("http://example.com:70000/").is_err());
+        assert!(Url::parse("http://[::1]:x/").is_err());
+    }
+
+    #[test]
+    fn joins_references_as_rfc_3986_does() {
+        let base = parse("http://a/b/c/d;p?q");
+        let cases = [
+            ("g:h", "g:h"),
+            ("g", "http://a/b/c/g"),
+            ("./g", "http://a/b/c/g"),
+            ("g/", "http://a/b/c/g/"),
+            ("/g", "http://a/g"),
+            ("//g", "http://g/"),
+            ("?y", "http://a/b/c/d;p?y"),
+            ("g?y", "http://a/b/c/g?y"),
+            ("#s", "http://a/b/c/d;p?q#s"),
+            ("g#s", "http://a/b/c/g#s"),
+            ("g?y#s", "http://a/b/c/g?y#s"),
+            (";x", "http://a/b/c/;x"),
+            ("g;x", "http://a/b/c/g;x"),
+            ("", "http://a/b/c/d;p?q"),
+            (".", "http://a/b/c/"),
+            ("./", "http://a/b/c/"),
+            ("..", "http://a/b/"),
+            ("../", "http://a/b/"),
+            ("../g", "http://a/b/g"),
+            ("../..", "http://a/"),
+            ("../../", "http://a/"),
+            ("../../g", "http://a/g"),
+            ("../../../g", "http://a/g"),
+            ("/./g", "http://a/g"),
+            ("/../g", "http://a/g"),
+            ("g.", "http://a/b/c/g."),
+            (".g", "http://a/b/c/.g"),
+            ("g..", "http://a/b/c/g.."),
+            ("..g", "http://a/b/c/..g"),
+            ("./../g", "http://a/b/g"),
+            ("./g/.", "http://a/b/c/g/"),
+            ("g/./h", "http://a/b/c/g/h"),
+            ("g/../h", "http://a/b/c/h"),
+        ];
+
+        for (reference, expected) in cases {
+            assert_eq!(
+                base.join(reference).unwrap().to_string(),
+                expected,
+                "{}",
+                reference
+            );
+        }
+    }
+
+    #[test]
+    fn joins_against_ipv6_and_scheme_relative_bases() {
+        let base = parse("https://[::1]:8443/docs/index.html");
+        assert_eq!(
+            base.join("../img/a.png").unwrap().to_string(),
+            "https://[::1]:8443/img/a.png"
+        );
+        assert_eq!(
+            base.join("//example.com/x").unwrap().to_string(),
+            "https://example.com/x"
+        );
+    }
+
+    #[test]
+    fn removes_dot_segments() {
+        assert_eq!(remove_dot_segments("/a/b/c/./../../g"), "/a/g");
+        assert_eq!(remove_dot_segments("/mid/content=5/../6"), "/mid/6");
+        assert_eq!(remove_dot_segments("/../../x"), "/x");
+        assert_eq!(remove_dot_segments("/a/."), "/a/");
+        assert_eq!(remove_dot_segments("/a/b/.."), "/a/");
+        assert_eq!(remove_dot_segments("/"), "/");
+        assert_eq!(remove_dot_segments("foo"), "foo");
+        assert_eq!(remove_dot_segments("a/./b/../c"), "a/c");
+        assert_eq!(remove_dot_segments("../x"), "x");
+    }
+
+    #[test]
+    fn joins_against_bases_without_a_slash() {
+        let base = parse("about:cookies");
+        assert_eq!(base.join("foo").unwrap().to_string(), "about:foo");
+        assert_eq!(base.join("foo?q#f").unwrap().to_string(), "about:foo?q#f");
+        assert_eq!(
+            parse("mailto:a@example.com")
+                .join("b@example.com")
+                .unwrap()
+                .to_string(),
+            "mailto:b@example.com"
+        );
+        assert_eq!(
+            parse("about:a/b").join("../c").unwrap().to_string(),
+            "about:c"
+        );
+    }
+
+    #[test]
+    fn encodes_request_targets() {
+        let target = |url: &str| parse(url).request_target();
+
+        assert_eq!(target("http://a/b/c?d=e"), "/b/c?d=e");
+        assert_eq!(target("http://a/a b/ü?q=x y"), "/a%20b/%C3%BC?q=x%20y");
+        assert_eq!(
+            target("http://a/\"quoted\"<>{x}|^`"),
+            "/%22quoted%22%3C%3E%7Bx%7D%7C%5E%60"
+        );
+        // Escapes already there, and characters with a meaning, are kept.
+        assert_eq!(
+            target("http://a/%20;x=1,2/@!$&'()*+:?a=%2F&b=/?"),
+            "/%20;x=1,2/@!$&'()*+:?a=%2F&b=/?"
+        );
+        assert_eq!(target("http://a/tab\there"), "/tab%09here");
+    }
+
+    #[test]
+    fn reads_what_the_user_typed() {
+        let url = |input: &str| Url::from_input(input).unwrap().to_string();
+
+        assert_eq!(url("example.com"), "http://example.com/");
+        assert_eq!(url("localhost:8080/a"), "http://localhost:8080/a");
+        assert_eq!(url("[::1]:8080"), "http://[::1]:8080/");
+        assert_eq!(url("https://example.com/x"), "https://example.com/x");
+        assert_eq!(url("about:blank"), "about:blank");
+        assert_eq!(url("/tmp/a b.html"), "file:///tmp/a%20b.html");
+
+        if let Ok(home) = std::env::var("HOME") {
+            let expected = Url::from_path(&PathBuf::from(format!("{}/notes.txt", home)));
+            assert_eq!(url("~/notes.txt"), expected.to_string());
+        }
+    }
+
+    #[test]
+    fn file_urls_round_trip_paths() {
+        let path = Path::new("/tmp/a b/ü.txt");
+        let url = Url::from_path(path);
+        assert_eq!(url.to_string(), "file:///tmp/a%20b/%C3%BC.txt");
+        assert_eq!(url.to_path().unwrap(), path);
+    }
+
+    #[test]
+    fn decodes_query_pairs() {
+        let url = parse("http://a/?q=a+b%21&empty=&flag");
+        assert_eq!(
+            url.query_pairs(),
+            vec![
+                ("q".to_string(), "a b!".to_string()),
+                ("empty".to_string(), String::new()),
+                ("flag".to_string(), String::new()),
+            ]
+        );
+    }
+}
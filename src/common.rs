@@ -1,12 +1,22 @@
+use std::sync::OnceLock;
+
 use crate::draw::boxes::BoxChar;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Vec2<T = usize> {
     pub x: T,
     pub y: T,
 }
 
-#[derive(Clone)]
+/// An area of the screen, in cells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rect {
+    pub position: Vec2<i16>,
+    pub size: Vec2,
+}
+
+/// A 24-bit color. Parsing from CSS and conversions are in `draw::color`.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -44,6 +54,11 @@ impl Color {
         g: 0,
         b: 255,
     };
+    pub const ORANGE: Color = Color {
+        r: 255,
+        g: 165,
+        b: 0,
+    };
 
     pub fn fg(&self) -> String {
         format!("\x1b[38;2;{};{};{}m", self.r, self.g, self.b)
@@ -54,7 +69,25 @@ impl Color {
     }
 }
 
-pub fn compile_buffer(buffer: &Vec<Vec<BoxChar>>) -> String {
+/// Whether the terminal's locale uses UTF-8, following the usual
+/// `LC_ALL` > `LC_CTYPE` > `LANG` precedence. Checked once per process.
+pub fn locale_is_utf8() -> bool {
+    static UTF8: OnceLock<bool> = OnceLock::new();
+
+    *UTF8.get_or_init(|| {
+        ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .map(|value| {
+                let value = value.to_ascii_lowercase();
+                value.contains("utf-8") || value.contains("utf8")
+            })
+            .unwrap_or(false)
+    })
+}
+
+pub fn compile_buffer(buffer: &[Vec<BoxChar>]) -> String {
     let mut result = String::new();
     for row in buffer {
         for box_char in row {
@@ -0,0 +1,254 @@
+use termion::event::Key;
+
+/// Something a key does while browsing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Hints,
+    Find,
+    FindNext,
+    FindPrevious,
+    ClearFind,
+    Open,
+    EditUrl,
+    OpenInTab,
+    Bookmark,
+    Bookmarks,
+    ClosePane,
+    CloseTab,
+    SplitRow,
+    SplitColumn,
+    FocusLeft,
+    FocusDown,
+    FocusUp,
+    FocusRight,
+    ResizeLeft,
+    ResizeDown,
+    ResizeUp,
+    ResizeRight,
+    PreviousTab,
+    NextTab,
+    MoveTabLeft,
+    MoveTabRight,
+    NextTheme,
+    Back,
+    Forward,
+    Reload,
+    ScrollDown,
+    ScrollUp,
+    PageDown,
+    PageUp,
+    Top,
+    Bottom,
+}
+
+/// Every action with its name in the config file and its default keys.
+const ACTIONS: &[(Action, &str, &[&str])] = &[
+    (Action::Quit, "quit", &["q"]),
+    (Action::Hints, "hints", &["f"]),
+    (Action::Find, "find", &["/"]),
+    (Action::FindNext, "find_next", &["n"]),
+    (Action::FindPrevious, "find_previous", &["N"]),
+    (Action::ClearFind, "clear_find", &["Esc"]),
+    (Action::Open, "open", &["o"]),
+    (Action::EditUrl, "edit_url", &["O"]),
+    (Action::OpenInTab, "open_in_tab", &["t"]),
+    (Action::Bookmark, "bookmark", &["m"]),
+    (Action::Bookmarks, "bookmarks", &["B"]),
+    (Action::ClosePane, "close_pane", &["x"]),
+    (Action::CloseTab, "close_tab", &["X"]),
+    (Action::SplitRow, "split_row", &["|"]),
+    (Action::SplitColumn, "split_column", &["-"]),
+    (Action::FocusLeft, "focus_left", &["Alt-h"]),
+    (Action::FocusDown, "focus_down", &["Alt-j"]),
+    (Action::FocusUp, "focus_up", &["Alt-k"]),
+    (Action::FocusRight, "focus_right", &["Alt-l"]),
+    (Action::ResizeLeft, "resize_left", &["Alt-H"]),
+    (Action::ResizeDown, "resize_down", &["Alt-J"]),
+    (Action::ResizeUp, "resize_up", &["Alt-K"]),
+    (Action::ResizeRight, "resize_right", &["Alt-L"]),
+    (Action::PreviousTab, "previous_tab", &["["]),
+    (Action::NextTab, "next_tab", &["]"]),
+    (Action::MoveTabLeft, "move_tab_left", &["{"]),
+    (Action::MoveTabRight, "move_tab_right", &["}"]),
+    (Action::NextTheme, "next_theme", &["T"]),
+    (Action::Back, "back", &["H", "Alt-Left"]),
+    (Action::Forward, "forward", &["L", "Alt-Right"]),
+    (Action::Reload, "reload", &["r"]),
+    (Action::ScrollDown, "scroll_down", &["j", "Down"]),
+    (Action::ScrollUp, "scroll_up", &["k", "Up"]),
+    (Action::PageDown, "page_down", &["Space", "PageDown"]),
+    (Action::PageUp, "page_up", &["b", "PageUp"]),
+    (Action::Top, "top", &["g", "Home"]),
+    (Action::Bottom, "bottom", &["G", "End"]),
+];
+
+/// Keys with names, rather than the character they type.
+const NAMED_KEYS: &[(&str, Key)] = &[
+    ("Space", Key::Char(' ')),
+    ("Enter", Key::Char('\n')),
+    ("Tab", Key::Char('\t')),
+    ("BackTab", Key::BackTab),
+    ("Backspace", Key::Backspace),
+    ("Delete", Key::Delete),
+    ("Insert", Key::Insert),
+    ("Esc", Key::Esc),
+    ("Up", Key::Up),
+    ("Down", Key::Down),
+    ("Left", Key::Left),
+    ("Right", Key::Right),
+    ("Home", Key::Home),
+    ("End", Key::End),
+    ("PageUp", Key::PageUp),
+    ("PageDown", Key::PageDown),
+    ("Alt-Up", Key::AltUp),
+    ("Alt-Down", Key::AltDown),
+    ("Alt-Left", Key::AltLeft),
+    ("Alt-Right", Key::AltRight),
+];
+
+impl Action {
+    /// The action called `name` in the config file.
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTIONS
+            .iter()
+            .find(|(_, n, _)| *n == name)
+            .map(|(action, _, _)| *action)
+    }
+}
+
+/// Reads a key as written in the config file, such as `q`, `Space`,
+/// `Ctrl-d`, `Alt-h` or `F5`.
+pub fn parse_key(text: &str) -> Result<Key, String> {
+    let single = |text: &str| {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+
+    if let Some((_, key)) = NAMED_KEYS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(text))
+    {
+        return Ok(*key);
+    }
+    if let Some(c) = single(text) {
+        return Ok(Key::Char(c));
+    }
+    if let Some(c) = text.strip_prefix("Ctrl-").and_then(single) {
+        return Ok(Key::Ctrl(c.to_ascii_lowercase()));
+    }
+    if let Some(c) = text.strip_prefix("Alt-").and_then(single) {
+        return Ok(Key::Alt(c));
+    }
+    if let Some(number) = text.strip_prefix('F').and_then(|n| n.parse().ok())
+        && (1..=12).contains(&number)
+    {
+        return Ok(Key::F(number));
+    }
+
+    Err(format!(
+        "'{}' is not a key. Keys are written like q, Space, Ctrl-d, Alt-h or F5.",
+        text
+    ))
+}
+
+/// Writes `key` the way the config file does, so `parse_key` reads it back.
+pub fn key_name(key: &Key) -> String {
+    if let Some((name, _)) = NAMED_KEYS.iter().find(|(_, named)| named == key) {
+        return name.to_string();
+    }
+
+    match key {
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("Ctrl-{}", c),
+        Key::Alt(c) => format!("Alt-{}", c),
+        Key::F(number) => format!("F{}", number),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Which key does what while browsing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = ACTIONS
+            .iter()
+            .flat_map(|(action, _, keys)| {
+                keys.iter()
+                    .filter_map(|key| parse_key(key).ok())
+                    .map(|key| (key, *action))
+            })
+            .collect();
+
+        Keymap { bindings }
+    }
+}
+
+impl Keymap {
+    /// What `key` does, if anything.
+    pub fn action(&self, key: &Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| bound == key)
+            .map(|(_, action)| *action)
+    }
+
+    /// The keys bound to `action`, in the order they were bound.
+    pub fn keys(&self, action: Action) -> Vec<Key> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /// Makes `keys` the keys for `action`, in place of the ones it had.
+    /// Any other action bound to one of them loses that key.
+    pub fn bind(&mut self, action: Action, keys: &[Key]) {
+        self.bindings
+            .retain(|(key, bound)| *bound != action && !keys.contains(key));
+        self.bindings.extend(keys.iter().map(|key| (*key, action)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_names_are_read_back() {
+        for key in [
+            Key::Char('q'),
+            Key::Char(' '),
+            Key::Esc,
+            Key::AltLeft,
+            Key::Ctrl('d'),
+            Key::Alt('H'),
+            Key::F(5),
+        ] {
+            assert_eq!(parse_key(&key_name(&key)), Ok(key));
+        }
+        assert_eq!(key_name(&Key::Char(' ')), "Space");
+        assert_eq!(key_name(&Key::Alt('h')), "Alt-h");
+    }
+
+    #[test]
+    fn keys_follow_rebinding() {
+        let mut keymap = Keymap::default();
+        assert_eq!(keymap.keys(Action::Back), [Key::Char('H'), Key::AltLeft]);
+
+        keymap.bind(Action::Quit, &[Key::Char('H')]);
+        assert_eq!(keymap.keys(Action::Back), [Key::AltLeft]);
+        assert_eq!(keymap.keys(Action::Quit), [Key::Char('H')]);
+
+        keymap.bind(Action::Reload, &[]);
+        assert!(keymap.keys(Action::Reload).is_empty());
+    }
+}
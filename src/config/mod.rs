@@ -0,0 +1,294 @@
+pub mod keys;
+pub mod toml;
+
+use std::fs;
+use std::path::Path;
+
+use crate::browser::url::Url;
+use crate::common::Color;
+use crate::config::keys::{Action, Keymap};
+use crate::config::toml::{Entry, Value};
+use crate::draw::border::BorderStyle;
+use crate::draw::theme::{Role, THEMES, Theme};
+
+/// What can be turned off. These are read when hyperline starts, so changes
+/// to them apply from the next start.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Features {
+    /// Remembering visited pages, and offering them when typing a URL.
+    pub history: bool,
+    /// Keeping cookies between runs.
+    pub cookies: bool,
+    /// Keeping downloaded pages on disk, to load them faster next time.
+    pub cache: bool,
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Features {
+            history: true,
+            cookies: true,
+            cache: true,
+        }
+    }
+}
+
+/// Settings read from `config.toml` in the config directory. Everything
+/// left out of the file keeps its default.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    /// Opened when no URL is given on the command line.
+    pub homepage: String,
+    /// Most the disk cache holds, in bytes.
+    pub cache_size: Option<u64>,
+
+    /// How boxes over the page, such as form editors, are drawn.
+    pub popup_border: BorderStyle,
+    pub tab_border: BorderStyle,
+    pub divider_border: BorderStyle,
+
+    /// The built-in theme chosen, with any colors the file changes.
+    pub theme: Theme,
+    pub keys: Keymap,
+    pub features: Features,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            homepage: "about:hyperline".to_string(),
+            cache_size: None,
+            popup_border: BorderStyle::Rounded,
+            tab_border: BorderStyle::Rounded,
+            divider_border: BorderStyle::Sharp,
+            theme: Theme::default(),
+            keys: Keymap::default(),
+            features: Features::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config in `file`. A missing file means the defaults. Errors
+    /// list every problem found in the file, one per line.
+    pub fn load(file: &Path) -> Result<Config, String> {
+        let text = match fs::read_to_string(file) {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Config::default());
+            }
+            Err(error) => return Err(format!("Could not read {}: {}", file.display(), error)),
+        };
+
+        Config::parse(&text).map_err(|errors| {
+            let mut message = format!("{} has errors:", file.display());
+            for error in errors {
+                message.push_str("\n  ");
+                message.push_str(&error);
+            }
+            message
+        })
+    }
+
+    /// Reads a config from the text of a config file.
+    pub fn parse(text: &str) -> Result<Config, Vec<String>> {
+        let (entries, mut errors) = toml::parse(text);
+
+        let mut config = Config::default();
+        // Colors change the chosen theme, wherever the theme is chosen.
+        let mut colors: Vec<(Role, Color)> = Vec::new();
+        // Keys bound by the file so far, to catch one being bound twice.
+        let mut bound: Vec<(termion::event::Key, &str)> = Vec::new();
+
+        for (index, entry) in entries.iter().enumerate() {
+            let name = match entry.table.is_empty() {
+                true => entry.key.clone(),
+                false => format!("{}.{}", entry.table, entry.key),
+            };
+            let mut error = |message: String| {
+                errors.push(toml::Error {
+                    line: entry.line,
+                    message,
+                })
+            };
+
+            match (entry.table.as_str(), entry.key.as_str()) {
+                ("", "homepage") => match string(entry, &name) {
+                    Ok(url) => match Url::from_input(url) {
+                        Ok(_) => config.homepage = url.to_string(),
+                        Err(message) => error(format!("homepage: {}", message)),
+                    },
+                    Err(message) => error(message),
+                },
+                ("", "theme") => match string(entry, &name) {
+                    Ok(theme) => match Theme::built_in(theme) {
+                        Some(theme) => config.theme = theme,
+                        None => error(format!(
+                            "theme: '{}' is not a theme. Try {}.",
+                            theme,
+                            THEMES.join(", ")
+                        )),
+                    },
+                    Err(message) => error(message),
+                },
+                ("", "cache_size") => match entry.value {
+                    Value::Integer(megabytes) if megabytes > 0 => {
+                        config.cache_size = Some(megabytes as u64 * 1024 * 1024)
+                    }
+                    _ => error("cache_size should be a number of megabytes.".to_string()),
+                },
+                ("borders", key @ ("popup" | "tabs" | "dividers")) => {
+                    let style = string(entry, &name).and_then(|style| {
+                        BorderStyle::from_name(style).ok_or(format!(
+                            "{}: '{}' is not a border style. Try rounded, sharp, thick, \
+                             double, dotted, block, ascii, heavy-dashed or double-dashed.",
+                            name, style
+                        ))
+                    });
+                    match (style, key) {
+                        (Ok(style), "popup") => config.popup_border = style,
+                        (Ok(style), "tabs") => config.tab_border = style,
+                        (Ok(style), _) => config.divider_border = style,
+                        (Err(message), _) => error(message),
+                    }
+                }
+                ("colors", key) => {
+                    let color = string(entry, &name).and_then(|text| {
+                        Color::parse(text).ok_or(format!(
+                            "{}: '{}' is not a color. Colors are written like #ff8800, \
+                             rgb(255 136 0), hsl(32 100% 50%) or darkorange.",
+                            name, text
+                        ))
+                    });
+                    match (Role::from_name(key), color) {
+                        (None, _) => error(format!("{} is not a color that can be set.", name)),
+                        (Some(role), Ok(color)) => colors.push((role, color)),
+                        (Some(_), Err(message)) => error(message),
+                    }
+                }
+                ("features", key) => {
+                    let slot = match key {
+                        "history" => &mut config.features.history,
+                        "cookies" => &mut config.features.cookies,
+                        "cache" => &mut config.features.cache,
+                        _ => {
+                            error(format!("{} is not a feature that can be turned off.", name));
+                            continue;
+                        }
+                    };
+                    match entry.value {
+                        Value::Boolean(on) => *slot = on,
+                        _ => error(format!("{} should be true or false.", name)),
+                    }
+                }
+                ("keys", key) => {
+                    let Some(action) = Action::from_name(key) else {
+                        error(format!("{} is not something a key can do.", name));
+                        continue;
+                    };
+                    let texts = match &entry.value {
+                        Value::String(text) => vec![text.as_str()],
+                        Value::Array(items) => items
+                            .iter()
+                            .filter_map(|item| match item {
+                                Value::String(text) => Some(text.as_str()),
+                                _ => None,
+                            })
+                            .collect(),
+                        _ => Vec::new(),
+                    };
+                    if texts.is_empty() {
+                        error(format!("{} should be a key, or a list of keys.", name));
+                        continue;
+                    }
+
+                    let mut keys = Vec::new();
+                    for text in texts {
+                        match keys::parse_key(text) {
+                            Ok(key) => match bound.iter().find(|(bound, _)| *bound == key) {
+                                Some((_, other)) => error(format!(
+                                    "{}: '{}' is already bound to {}.",
+                                    name, text, other
+                                )),
+                                None => {
+                                    bound.push((key, &entry.key));
+                                    keys.push(key);
+                                }
+                            },
+                            Err(message) => error(format!("{}: {}", name, message)),
+                        }
+                    }
+                    config.keys.bind(action, &keys);
+                }
+                ("" | "borders", _) => error(format!("{} is not a setting.", name)),
+                // An unknown section is only reported at its first setting.
+                (table, _) => {
+                    if !entries[..index].iter().any(|e| e.table == table) {
+                        error(format!("[{}] is not a section of the config.", table))
+                    }
+                }
+            }
+        }
+
+        for (role, color) in colors {
+            *config.theme.color_mut(role) = color;
+        }
+
+        if !errors.is_empty() {
+            errors.sort_by_key(|error| error.line);
+            return Err(errors.iter().map(ToString::to_string).collect());
+        }
+        Ok(config)
+    }
+}
+
+/// The value of `entry`, called `name` in errors, which should be a string.
+fn string<'a>(entry: &'a Entry, name: &str) -> Result<&'a str, String> {
+    match &entry.value {
+        Value::String(text) => Ok(text),
+        value => Err(format!(
+            "{} should be a string, in quotes, not {}.",
+            name,
+            value.kind()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_settings() {
+        let config = Config::parse(
+            "homepage = \"example.com\"\ncache_size = 8\n\n[features]\nhistory = false\n\
+             [borders]\ntabs = \"double\"",
+        )
+        .unwrap();
+        assert_eq!(config.homepage, "example.com");
+        assert_eq!(config.cache_size, Some(8 * 1024 * 1024));
+        assert!(!config.features.history && config.features.cookies);
+        assert_eq!(config.tab_border, BorderStyle::Double);
+        assert_eq!(Config::parse("# nothing set\n").unwrap(), Config::default());
+    }
+
+    #[test]
+    fn reports_every_error_by_line() {
+        let errors = Config::parse(
+            "cache_size = 0\ntheme = \"plaid\"\nnope = 1\n[features]\nhistory = \"no\"\n\
+             [extra]\na = 1\nb = [1",
+        )
+        .unwrap_err();
+        assert_eq!(
+            errors,
+            [
+                "line 1: cache_size should be a number of megabytes.",
+                "line 2: theme: 'plaid' is not a theme. Try dark, light, solarized, high-contrast.",
+                "line 3: nope is not a setting.",
+                "line 5: features.history should be true or false.",
+                "line 7: [extra] is not a section of the config.",
+                "line 8: b: the list is missing its closing ]. Lists have to fit on one line.",
+            ]
+        );
+    }
+}
@@ -0,0 +1,391 @@
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    /// What kind of value this is, for error messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::String(_) => "a string",
+            Value::Integer(_) => "a number",
+            Value::Boolean(_) => "true or false",
+            Value::Array(_) => "a list",
+        }
+    }
+}
+
+/// A `key = value` line, with the table it is in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    /// The name of the table, empty before the first `[table]` line.
+    pub table: String,
+    pub key: String,
+    pub value: Value,
+    /// Counted from 1.
+    pub line: usize,
+}
+
+/// A problem with a line of the file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Reads every entry of `text`, which is the part of TOML the config file
+/// needs: `[tables]`, and `key = value` lines whose values are strings,
+/// integers, booleans or lists of those on one line. Lines that cannot be
+/// read are skipped and reported, so every mistake in the file is found at once.
+pub fn parse(text: &str) -> (Vec<Entry>, Vec<Error>) {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut errors = Vec::new();
+    let mut table = String::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        let mut error = |message: String| {
+            errors.push(Error {
+                line: number,
+                message,
+            })
+        };
+
+        let mut rest = line.trim_start();
+        if rest.is_empty() || rest.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = rest.strip_prefix('[') {
+            match header.split_once(']') {
+                Some((name, after)) if is_blank(after) => match bare_key(name.trim()) {
+                    Some(name) => table = name.to_string(),
+                    None => error(format!("'{}' is not a valid table name.", name.trim())),
+                },
+                _ => error("A table name should be written as [name].".to_string()),
+            }
+            continue;
+        }
+
+        let Some((key, value)) = rest.split_once('=') else {
+            error(format!("Expected key = value, found '{}'.", rest.trim()));
+            continue;
+        };
+        let Some(key) = bare_key(key.trim()) else {
+            error(format!("'{}' is not a valid key.", key.trim()));
+            continue;
+        };
+
+        rest = value;
+        match parse_value(&mut rest) {
+            Ok(_) if !is_blank(rest) => error(format!(
+                "Unexpected '{}' after the value of {}.",
+                rest.trim(),
+                key
+            )),
+            Ok(_) if entries.iter().any(|e| e.table == table && e.key == key) => {
+                error(format!("{} is set more than once.", key))
+            }
+            Ok(value) => entries.push(Entry {
+                table: table.clone(),
+                key: key.to_string(),
+                value,
+                line: number,
+            }),
+            Err(message) => error(format!("{}: {}", key, message)),
+        }
+    }
+
+    (entries, errors)
+}
+
+/// Whether only whitespace or a comment is left on the line.
+fn is_blank(rest: &str) -> bool {
+    let rest = rest.trim_start();
+    rest.is_empty() || rest.starts_with('#')
+}
+
+/// `key` when it is a bare TOML key: letters, digits, `_` and `-`.
+fn bare_key(key: &str) -> Option<&str> {
+    let valid = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    valid.then_some(key)
+}
+
+/// Reads a value from the start of `rest`, leaving what follows it.
+fn parse_value(rest: &mut &str) -> Result<Value, String> {
+    *rest = rest.trim_start();
+
+    if let Some(after) = rest.strip_prefix('"') {
+        return parse_string(after, rest);
+    }
+    if let Some(after) = rest.strip_prefix('\'') {
+        let (text, after) = after
+            .split_once('\'')
+            .ok_or("the string is missing its closing quote.")?;
+        *rest = after;
+        return Ok(Value::String(text.to_string()));
+    }
+    if let Some(after) = rest.strip_prefix('[') {
+        *rest = after;
+        let mut items = Vec::new();
+        loop {
+            *rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix(']') {
+                *rest = after;
+                return Ok(Value::Array(items));
+            }
+            if rest.is_empty() || rest.starts_with('#') {
+                return Err(
+                    "the list is missing its closing ]. Lists have to fit on one line.".into(),
+                );
+            }
+            items.push(parse_value(rest)?);
+            *rest = rest.trim_start();
+            match rest.strip_prefix(',') {
+                Some(after) => *rest = after,
+                // A list cut off by the end of the line is reported above.
+                None if rest.starts_with(']') || is_blank(rest) => {}
+                None => return Err("the items of a list are separated by commas.".into()),
+            }
+        }
+    }
+
+    let end = rest
+        .find(|c: char| c.is_whitespace() || c == ',' || c == ']' || c == '#')
+        .unwrap_or(rest.len());
+    let (word, after) = rest.split_at(end);
+    *rest = after;
+
+    match word {
+        "true" => Ok(Value::Boolean(true)),
+        "false" => Ok(Value::Boolean(false)),
+        "" => Err("the value is missing.".into()),
+        word => word
+            .replace('_', "")
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| format!("'{}' is not a value. Strings need quotes.", word)),
+    }
+}
+
+/// Reads a basic string, whose opening quote is already read, with its escapes.
+fn parse_string<'a>(after: &'a str, rest: &mut &'a str) -> Result<Value, String> {
+    let mut text = String::new();
+    let mut chars = after.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => {
+                *rest = &after[i + 1..];
+                return Ok(Value::String(text));
+            }
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('n') => text.push('\n'),
+                Some('t') => text.push('\t'),
+                Some('"') => text.push('"'),
+                Some('\\') => text.push('\\'),
+                Some('u') => {
+                    let hex = chars.by_ref().take(4).map(|(_, c)| c).collect::<String>();
+                    let c = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or(format!("'\\u{}' is not a character.", hex))?;
+                    text.push(c);
+                }
+                Some(c) => return Err(format!("'\\{}' is not an escape.", c)),
+                None => break,
+            },
+            c => text.push(c),
+        }
+    }
+
+    Err("the string is missing its closing quote.".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The only value in `text`, which has to parse without errors.
+    fn value(text: &str) -> Value {
+        let (entries, errors) = parse(&format!("key = {}", text));
+        assert_eq!(errors, vec![], "{}", text);
+        entries.into_iter().next().unwrap().value
+    }
+
+    /// The message of the only error in `text`.
+    fn error(text: &str) -> String {
+        let (_, errors) = parse(text);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        errors[0].to_string()
+    }
+
+    fn string(text: &str) -> Value {
+        Value::String(text.to_string())
+    }
+
+    #[test]
+    fn strings_and_escapes() {
+        assert_eq!(value("\"plain\""), string("plain"));
+        assert_eq!(value("\"a\\tb\\nc\""), string("a\tb\nc"));
+        assert_eq!(
+            value("\"say \\\"hi\\\" \\\\o/\""),
+            string("say \"hi\" \\o/")
+        );
+        assert_eq!(value("\"caf\\u00e9 \\u2192\""), string("café →"));
+        assert_eq!(
+            value("'C:\\path\\no \"escapes\"'"),
+            string("C:\\path\\no \"escapes\"")
+        );
+        assert_eq!(
+            value("\"# not a comment\" # a comment"),
+            string("# not a comment")
+        );
+        assert_eq!(value("\"\""), string(""));
+
+        assert_eq!(
+            error("key = \"\\q\""),
+            "line 1: key: '\\q' is not an escape."
+        );
+        assert_eq!(
+            error("key = \"\\uzzzz\""),
+            "line 1: key: '\\uzzzz' is not a character."
+        );
+        assert_eq!(
+            error("key = \"open"),
+            "line 1: key: the string is missing its closing quote."
+        );
+        assert_eq!(
+            error("key = 'open"),
+            "line 1: key: the string is missing its closing quote."
+        );
+        assert_eq!(
+            error("key = \"ends in a backslash\\"),
+            "line 1: key: the string is missing its closing quote."
+        );
+    }
+
+    #[test]
+    fn numbers_and_booleans() {
+        assert_eq!(value("42"), Value::Integer(42));
+        assert_eq!(value("-7"), Value::Integer(-7));
+        assert_eq!(value("+7"), Value::Integer(7));
+        assert_eq!(value("1_000_000"), Value::Integer(1_000_000));
+        assert_eq!(value("true"), Value::Boolean(true));
+        assert_eq!(value("false # off"), Value::Boolean(false));
+
+        assert_eq!(
+            error("key = yes"),
+            "line 1: key: 'yes' is not a value. Strings need quotes."
+        );
+        assert_eq!(error("key ="), "line 1: key: the value is missing.");
+        assert_eq!(
+            error("key = 1 2"),
+            "line 1: Unexpected '2' after the value of key."
+        );
+    }
+
+    #[test]
+    fn arrays() {
+        assert_eq!(value("[]"), Value::Array(vec![]));
+        assert_eq!(
+            value("[ \"a\", 'b' ,1,true ]"),
+            Value::Array(vec![
+                string("a"),
+                string("b"),
+                Value::Integer(1),
+                Value::Boolean(true)
+            ])
+        );
+        assert_eq!(
+            value("[\"a\", \"b\",]"),
+            Value::Array(vec![string("a"), string("b")])
+        );
+        assert_eq!(
+            value("[[1], [], \"],\"]"),
+            Value::Array(vec![
+                Value::Array(vec![Value::Integer(1)]),
+                Value::Array(vec![]),
+                string("],")
+            ])
+        );
+
+        assert_eq!(
+            error("key = [1, 2"),
+            "line 1: key: the list is missing its closing ]. Lists have to fit on one line."
+        );
+        assert_eq!(
+            error("key = [1 2]"),
+            "line 1: key: the items of a list are separated by commas."
+        );
+    }
+
+    #[test]
+    fn tables_and_keys() {
+        let (entries, errors) =
+            parse("top = 1\n[colors]\nlink = \"red\"\n  [ keys ] # c\nquit-now = 'q'");
+        assert_eq!(errors, vec![]);
+        let keys = entries
+            .iter()
+            .map(|entry| (entry.table.as_str(), entry.key.as_str(), entry.line))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            keys,
+            [
+                ("", "top", 1),
+                ("colors", "link", 3),
+                ("keys", "quit-now", 5)
+            ]
+        );
+
+        assert_eq!(
+            error("[my table]"),
+            "line 1: 'my table' is not a valid table name."
+        );
+        assert_eq!(
+            error("[keys] extra"),
+            "line 1: A table name should be written as [name]."
+        );
+        assert_eq!(error("a.b = 1"), "line 1: 'a.b' is not a valid key.");
+        assert_eq!(
+            error("just words"),
+            "line 1: Expected key = value, found 'just words'."
+        );
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let (entries, errors) = parse("a = 1\n[t]\na = 2\n[u]\na = 3\n[t]\na = 4");
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            errors,
+            vec![Error {
+                line: 7,
+                message: "a is set more than once.".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn every_error_is_reported_on_its_line() {
+        let text = "# comment\n\nok = 1\nbad = \nalso ok = 2\n\n[fine]\nx = [1,\ny = \"\\x\"\n";
+        let (entries, errors) = parse(text);
+        assert_eq!(entries.len(), 1);
+        let lines = errors.iter().map(|error| error.line).collect::<Vec<_>>();
+        assert_eq!(lines, [4, 5, 8, 9]);
+    }
+}
@@ -1,89 +1,178 @@
+use std::borrow::Cow;
+
 use bitflags::bitflags;
+use unicode_width::UnicodeWidthChar;
+
+use crate::common;
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct BorderChars {
-    pub top: &'static str,
-    pub left: &'static str,
-    pub right: &'static str,
-    pub bottom: &'static str,
-    pub top_left: &'static str,
-    pub top_right: &'static str,
-    pub bottom_left: &'static str,
-    pub bottom_right: &'static str,
+    pub top: Cow<'static, str>,
+    pub left: Cow<'static, str>,
+    pub right: Cow<'static, str>,
+    pub bottom: Cow<'static, str>,
+    pub top_left: Cow<'static, str>,
+    pub top_right: Cow<'static, str>,
+    pub bottom_left: Cow<'static, str>,
+    pub bottom_right: Cow<'static, str>,
 }
 
 impl BorderChars {
-    pub fn border_width(&self) -> usize {
-        self.top.len()
+    /// Builds a character set from owned strings, for use with `BorderStyle::Custom`.
+    /// Corners are given clockwise from the top left. Each glyph has to be a
+    /// single character one cell wide, or boxes would no longer line up.
+    pub fn custom(horizontal: &str, vertical: &str, corners: [&str; 4]) -> Result<Self, String> {
+        let [top_left, top_right, bottom_right, bottom_left] = corners;
+
+        for glyph in [
+            horizontal,
+            vertical,
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        ] {
+            let mut chars = glyph.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.width() == Some(1) => {}
+                _ => {
+                    return Err(format!(
+                        "'{}' is not a border glyph: it should be one character, one cell wide.",
+                        glyph
+                    ));
+                }
+            }
+        }
+
+        Ok(BorderChars {
+            top: Cow::Owned(horizontal.to_string()),
+            left: Cow::Owned(vertical.to_string()),
+            right: Cow::Owned(vertical.to_string()),
+            bottom: Cow::Owned(horizontal.to_string()),
+            top_left: Cow::Owned(top_left.to_string()),
+            top_right: Cow::Owned(top_right.to_string()),
+            bottom_left: Cow::Owned(bottom_left.to_string()),
+            bottom_right: Cow::Owned(bottom_right.to_string()),
+        })
+    }
+
+    /// Whether `glyph` is one of the characters of this set.
+    pub fn contains(&self, glyph: &str) -> bool {
+        [
+            &self.top,
+            &self.left,
+            &self.right,
+            &self.bottom,
+            &self.top_left,
+            &self.top_right,
+            &self.bottom_left,
+            &self.bottom_right,
+        ]
+        .iter()
+        .any(|c| c.as_ref() == glyph)
     }
 }
 
 const BLOCK_BORDER_CHARS: BorderChars = BorderChars {
-    top: "▄",
-    left: "▐",
-    right: "▌",
-    bottom: "▀",
-    top_left: "▗",
-    top_right: "▖",
-    bottom_left: "▝",
-    bottom_right: "▘",
+    top: Cow::Borrowed("▄"),
+    left: Cow::Borrowed("▐"),
+    right: Cow::Borrowed("▌"),
+    bottom: Cow::Borrowed("▀"),
+    top_left: Cow::Borrowed("▗"),
+    top_right: Cow::Borrowed("▖"),
+    bottom_left: Cow::Borrowed("▝"),
+    bottom_right: Cow::Borrowed("▘"),
 };
 
 const ROUNDED_BORDER_CHARS: BorderChars = BorderChars {
-    top: "─",
-    left: "│",
-    right: "│",
-    bottom: "─",
-    top_left: "╭",
-    top_right: "╮",
-    bottom_left: "╰",
-    bottom_right: "╯",
+    top: Cow::Borrowed("─"),
+    left: Cow::Borrowed("│"),
+    right: Cow::Borrowed("│"),
+    bottom: Cow::Borrowed("─"),
+    top_left: Cow::Borrowed("╭"),
+    top_right: Cow::Borrowed("╮"),
+    bottom_left: Cow::Borrowed("╰"),
+    bottom_right: Cow::Borrowed("╯"),
 };
 
 const SHARP_BORDER_CHARS: BorderChars = BorderChars {
-    top: "─",
-    left: "│",
-    right: "│",
-    bottom: "─",
-    top_left: "┌",
-    top_right: "┐",
-    bottom_left: "└",
-    bottom_right: "┘",
+    top: Cow::Borrowed("─"),
+    left: Cow::Borrowed("│"),
+    right: Cow::Borrowed("│"),
+    bottom: Cow::Borrowed("─"),
+    top_left: Cow::Borrowed("┌"),
+    top_right: Cow::Borrowed("┐"),
+    bottom_left: Cow::Borrowed("└"),
+    bottom_right: Cow::Borrowed("┘"),
 };
 
 const THICK_BORDER_CHARS: BorderChars = BorderChars {
-    top: "━",
-    left: "┃",
-    right: "┃",
-    bottom: "━",
-    top_left: "┏",
-    top_right: "┓",
-    bottom_left: "┗",
-    bottom_right: "┛",
+    top: Cow::Borrowed("━"),
+    left: Cow::Borrowed("┃"),
+    right: Cow::Borrowed("┃"),
+    bottom: Cow::Borrowed("━"),
+    top_left: Cow::Borrowed("┏"),
+    top_right: Cow::Borrowed("┓"),
+    bottom_left: Cow::Borrowed("┗"),
+    bottom_right: Cow::Borrowed("┛"),
 };
 
 const DOUBLE_BORDER_CHARS: BorderChars = BorderChars {
-    top: "═",
-    left: "║",
-    right: "║",
-    bottom: "═",
-    top_left: "╔",
-    top_right: "╗",
-    bottom_left: "╚",
-    bottom_right: "╝",
+    top: Cow::Borrowed("═"),
+    left: Cow::Borrowed("║"),
+    right: Cow::Borrowed("║"),
+    bottom: Cow::Borrowed("═"),
+    top_left: Cow::Borrowed("╔"),
+    top_right: Cow::Borrowed("╗"),
+    bottom_left: Cow::Borrowed("╚"),
+    bottom_right: Cow::Borrowed("╝"),
 };
 
 const DOTTED_BORDER_CHARS: BorderChars = BorderChars {
-    top: "╌",
-    left: "╎",
-    right: "╎",
-    bottom: "╌",
-    top_left: "┌",
-    top_right: "┐",
-    bottom_left: "└",
-    bottom_right: "┘",
+    top: Cow::Borrowed("╌"),
+    left: Cow::Borrowed("╎"),
+    right: Cow::Borrowed("╎"),
+    bottom: Cow::Borrowed("╌"),
+    top_left: Cow::Borrowed("┌"),
+    top_right: Cow::Borrowed("┐"),
+    bottom_left: Cow::Borrowed("└"),
+    bottom_right: Cow::Borrowed("┘"),
 };
 
-#[derive(Clone)]
+const ASCII_BORDER_CHARS: BorderChars = BorderChars {
+    top: Cow::Borrowed("-"),
+    left: Cow::Borrowed("|"),
+    right: Cow::Borrowed("|"),
+    bottom: Cow::Borrowed("-"),
+    top_left: Cow::Borrowed("+"),
+    top_right: Cow::Borrowed("+"),
+    bottom_left: Cow::Borrowed("+"),
+    bottom_right: Cow::Borrowed("+"),
+};
+
+const HEAVY_DASHED_BORDER_CHARS: BorderChars = BorderChars {
+    top: Cow::Borrowed("┅"),
+    left: Cow::Borrowed("┇"),
+    right: Cow::Borrowed("┇"),
+    bottom: Cow::Borrowed("┅"),
+    top_left: Cow::Borrowed("┏"),
+    top_right: Cow::Borrowed("┓"),
+    bottom_left: Cow::Borrowed("┗"),
+    bottom_right: Cow::Borrowed("┛"),
+};
+
+const DOUBLE_DASHED_BORDER_CHARS: BorderChars = BorderChars {
+    top: Cow::Borrowed("╍"),
+    left: Cow::Borrowed("╏"),
+    right: Cow::Borrowed("╏"),
+    bottom: Cow::Borrowed("╍"),
+    top_left: Cow::Borrowed("┏"),
+    top_right: Cow::Borrowed("┓"),
+    bottom_left: Cow::Borrowed("┗"),
+    bottom_right: Cow::Borrowed("┛"),
+};
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum BorderStyle {
     Block,
     Rounded,
@@ -91,10 +180,23 @@ pub enum BorderStyle {
     Thick,
     Double,
     Dotted,
+    /// `+`, `-` and `|` only, for terminals without box-drawing glyphs.
+    Ascii,
+    /// Heavy triple-dash lines with heavy corners.
+    HeavyDashed,
+    /// Heavy double-dash lines with heavy corners.
+    DoubleDashed,
+    Custom(BorderChars),
 }
 
 impl BorderStyle {
+    /// The characters this style draws with. Built-in styles fall back to
+    /// `BorderStyle::Ascii` when the locale is not UTF-8; custom sets are used as given.
     pub fn chars(&self) -> &BorderChars {
+        if !matches!(self, BorderStyle::Custom(_)) && !common::locale_is_utf8() {
+            return &ASCII_BORDER_CHARS;
+        }
+
         match self {
             BorderStyle::Block => &BLOCK_BORDER_CHARS,
             BorderStyle::Rounded => &ROUNDED_BORDER_CHARS,
@@ -102,6 +204,10 @@ impl BorderStyle {
             BorderStyle::Thick => &THICK_BORDER_CHARS,
             BorderStyle::Double => &DOUBLE_BORDER_CHARS,
             BorderStyle::Dotted => &DOTTED_BORDER_CHARS,
+            BorderStyle::Ascii => &ASCII_BORDER_CHARS,
+            BorderStyle::HeavyDashed => &HEAVY_DASHED_BORDER_CHARS,
+            BorderStyle::DoubleDashed => &DOUBLE_DASHED_BORDER_CHARS,
+            BorderStyle::Custom(chars) => chars,
         }
     }
 }
@@ -124,12 +230,287 @@ bitflags! {
     }
 }
 
-pub fn determine_edge(
-    flags: &BorderFlags,
-    style: &BorderStyle,
+/// Width of one side of a border, like CSS `border-width`. A terminal cell cannot
+/// be partially filled, so widths map onto the weight of the line instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BorderWidth {
+    None,
+    Normal,
+    Thick,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LineWeight {
+    Light,
+    Heavy,
+    Double,
+}
+
+impl BorderStyle {
+    /// The built-in style called `name`, such as `rounded` or `heavy-dashed`.
+    pub fn from_name(name: &str) -> Option<BorderStyle> {
+        match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "block" => Some(BorderStyle::Block),
+            "rounded" => Some(BorderStyle::Rounded),
+            "sharp" => Some(BorderStyle::Sharp),
+            "thick" => Some(BorderStyle::Thick),
+            "double" => Some(BorderStyle::Double),
+            "dotted" => Some(BorderStyle::Dotted),
+            "ascii" => Some(BorderStyle::Ascii),
+            "heavy-dashed" => Some(BorderStyle::HeavyDashed),
+            "double-dashed" => Some(BorderStyle::DoubleDashed),
+            _ => None,
+        }
+    }
+
+    /// The heavy counterpart of a light style, used for `BorderWidth::Thick`.
+    pub fn thickened(&self) -> BorderStyle {
+        match self {
+            BorderStyle::Rounded | BorderStyle::Sharp => BorderStyle::Thick,
+            BorderStyle::Dotted => BorderStyle::DoubleDashed,
+            other => other.clone(),
+        }
+    }
+
+    fn weight(&self) -> Option<LineWeight> {
+        match self {
+            BorderStyle::Rounded | BorderStyle::Sharp | BorderStyle::Dotted => {
+                Some(LineWeight::Light)
+            }
+            BorderStyle::Thick | BorderStyle::HeavyDashed | BorderStyle::DoubleDashed => {
+                Some(LineWeight::Heavy)
+            }
+            BorderStyle::Double => Some(LineWeight::Double),
+            _ => None,
+        }
+    }
+
+    /// The glyph where lines of this style meet, with arms reaching towards
+    /// the sides in `arms`. Straight lines and corners use the style's own
+    /// characters; tees and crosses fall back to `+` for styles with no
+    /// box-drawing weight.
+    pub fn junction(&self, arms: BorderFlags) -> &str {
+        let chars = self.chars();
+        let up_down = BorderFlags::TOP | BorderFlags::BOTTOM;
+        let sideways = BorderFlags::LEFT | BorderFlags::RIGHT;
+        let (vertical, horizontal) = (arms & up_down, arms & sideways);
+
+        match (vertical.bits().count_ones(), horizontal.bits().count_ones()) {
+            (_, 0) => return &chars.left,
+            (0, _) => return &chars.top,
+            // A corner is named after where it sits, the opposite of its arms.
+            (1, 1) => return own_corner(chars, (vertical ^ up_down) | (horizontal ^ sideways)),
+            _ => {}
+        }
+
+        let weight = self.weight().filter(|_| common::locale_is_utf8());
+        // Indexed as [down, up, right, left, all four].
+        let tees: [&'static str; 5] = match weight {
+            Some(LineWeight::Light) => ["┬", "┴", "├", "┤", "┼"],
+            Some(LineWeight::Heavy) => ["┳", "┻", "┣", "┫", "╋"],
+            Some(LineWeight::Double) => ["╦", "╩", "╠", "╣", "╬"],
+            None => return "+",
+        };
+
+        match (vertical == up_down, horizontal == sideways) {
+            (true, true) => tees[4],
+            (false, _) if vertical == BorderFlags::BOTTOM => tees[0],
+            (false, _) => tees[1],
+            (true, false) if horizontal == BorderFlags::RIGHT => tees[2],
+            (true, false) => tees[3],
+        }
+    }
+}
+
+/// Overrides for one side of a border, like CSS `border-top-style`,
+/// `border-top-color` and `border-top-width`. Unset fields use the box's own values.
+#[derive(Clone, Debug, Default)]
+pub struct BorderSide {
+    pub style: Option<BorderStyle>,
+    pub color: Option<common::Color>,
+    pub width: Option<BorderWidth>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct BorderSides {
+    pub top: BorderSide,
+    pub right: BorderSide,
+    pub bottom: BorderSide,
+    pub left: BorderSide,
+}
+
+/// One side of a `Border` with its overrides applied.
+#[derive(Clone, Debug)]
+pub struct ResolvedSide {
+    pub style: BorderStyle,
+    pub color: Option<common::Color>,
+}
+
+/// The border of a box after per-side overrides are applied to its flags,
+/// style and color.
+#[derive(Clone, Debug)]
+pub struct Border {
+    pub flags: BorderFlags,
+    pub top: ResolvedSide,
+    pub right: ResolvedSide,
+    pub bottom: ResolvedSide,
+    pub left: ResolvedSide,
+}
+
+impl Border {
+    pub fn new(
+        flags: BorderFlags,
+        style: &BorderStyle,
+        color: &Option<common::Color>,
+        sides: &BorderSides,
+    ) -> Self {
+        let mut flags = flags;
+
+        let mut resolve = |side: &BorderSide, flag: BorderFlags| {
+            let mut side_style = side.style.clone().unwrap_or_else(|| style.clone());
+
+            match side.width {
+                Some(BorderWidth::None) => flags.remove(flag),
+                Some(BorderWidth::Thick) => side_style = side_style.thickened(),
+                _ => {}
+            }
+
+            ResolvedSide {
+                style: side_style,
+                color: side.color.clone().or_else(|| color.clone()),
+            }
+        };
+
+        let top = resolve(&sides.top, BorderFlags::TOP);
+        let right = resolve(&sides.right, BorderFlags::RIGHT);
+        let bottom = resolve(&sides.bottom, BorderFlags::BOTTOM);
+        let left = resolve(&sides.left, BorderFlags::LEFT);
+
+        Border {
+            flags,
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    /// A border drawn with the same style and color on every side.
+    pub fn uniform(flags: BorderFlags, style: &BorderStyle, color: &Option<common::Color>) -> Self {
+        Border::new(flags, style, color, &BorderSides::default())
+    }
+
+    pub fn contains(&self, side: BorderFlags) -> bool {
+        self.flags.contains(side)
+    }
+
+    /// The color of the cell at `position`. Corners take the color of the
+    /// horizontal side when it is drawn, like the full-width top and bottom edges.
+    pub fn color_at(&self, position: BorderFlags) -> &Option<common::Color> {
+        let horizontal = if position.contains(BorderFlags::TOP) {
+            Some(&self.top)
+        } else if position.contains(BorderFlags::BOTTOM) {
+            Some(&self.bottom)
+        } else {
+            None
+        };
+
+        let vertical = if position.contains(BorderFlags::LEFT) {
+            Some(&self.left)
+        } else if position.contains(BorderFlags::RIGHT) {
+            Some(&self.right)
+        } else {
+            None
+        };
+
+        let horizontal_drawn = position.intersects(self.flags & BorderFlags::HORIZONTAL);
+
+        match (horizontal, vertical) {
+            (Some(_), Some(v)) if !horizontal_drawn => &v.color,
+            (Some(h), _) => &h.color,
+            (None, Some(v)) => &v.color,
+            (None, None) => &None,
+        }
+    }
+
+    fn corner(&self, position: BorderFlags) -> &str {
+        let (horizontal, vertical) = match (
+            position.contains(BorderFlags::TOP),
+            position.contains(BorderFlags::LEFT),
+        ) {
+            (true, true) => (&self.top.style, &self.left.style),
+            (true, false) => (&self.top.style, &self.right.style),
+            (false, true) => (&self.bottom.style, &self.left.style),
+            (false, false) => (&self.bottom.style, &self.right.style),
+        };
+
+        // A corner only one side asks for, or where both sides agree, is the
+        // style's own glyph (this keeps `Rounded` corners rounded).
+        let horizontal_only = !self.flags.intersects(position & BorderFlags::VERTICAL);
+        let vertical_only = !self.flags.intersects(position & BorderFlags::HORIZONTAL);
+        let owner = if vertical_only { vertical } else { horizontal };
+
+        if horizontal == vertical || horizontal_only || vertical_only || !common::locale_is_utf8() {
+            return own_corner(owner.chars(), position);
+        }
+
+        match (horizontal.weight(), vertical.weight()) {
+            (Some(h), Some(v)) => {
+                mixed_corner(h, v, position).unwrap_or(own_corner(owner.chars(), position))
+            }
+            _ => own_corner(owner.chars(), position),
+        }
+    }
+}
+
+fn own_corner(chars: &BorderChars, position: BorderFlags) -> &str {
+    match (
+        position.contains(BorderFlags::TOP),
+        position.contains(BorderFlags::LEFT),
+    ) {
+        (true, true) => &chars.top_left,
+        (true, false) => &chars.top_right,
+        (false, true) => &chars.bottom_left,
+        (false, false) => &chars.bottom_right,
+    }
+}
+
+/// Box-drawing corners joining a horizontal and a vertical line of different weights.
+/// Heavy and double lines have no shared glyphs, so those return `None`.
+fn mixed_corner(
+    horizontal: LineWeight,
+    vertical: LineWeight,
     position: BorderFlags,
-) -> &'static str {
-    let chars = style.chars();
+) -> Option<&'static str> {
+    use LineWeight::*;
+
+    // Indexed as [top left, top right, bottom left, bottom right].
+    let corners: [&'static str; 4] = match (horizontal, vertical) {
+        (Light, Light) => ["┌", "┐", "└", "┘"],
+        (Heavy, Heavy) => ["┏", "┓", "┗", "┛"],
+        (Double, Double) => ["╔", "╗", "╚", "╝"],
+        (Heavy, Light) => ["┍", "┑", "┕", "┙"],
+        (Light, Heavy) => ["┎", "┒", "┖", "┚"],
+        (Double, Light) => ["╒", "╕", "╘", "╛"],
+        (Light, Double) => ["╓", "╖", "╙", "╜"],
+        _ => return None,
+    };
+
+    let index = match (
+        position.contains(BorderFlags::TOP),
+        position.contains(BorderFlags::LEFT),
+    ) {
+        (true, true) => 0,
+        (true, false) => 1,
+        (false, true) => 2,
+        (false, false) => 3,
+    };
+
+    Some(corners[index])
+}
+
+pub fn determine_edge(border: &Border, position: BorderFlags) -> &str {
+    let flags = &border.flags;
 
     match (
         position.contains(BorderFlags::TOP),
@@ -142,11 +523,11 @@ pub fn determine_edge(
                 || (flags.contains(BorderFlags::PRESERVE_CORNERS)
                     && (flags.contains(BorderFlags::TOP) || flags.contains(BorderFlags::LEFT)))
             {
-                chars.top_left
+                border.corner(BorderFlags::TOP | BorderFlags::LEFT)
             } else if flags.contains(BorderFlags::TOP) {
-                chars.top
+                &border.top.style.chars().top
             } else if flags.contains(BorderFlags::LEFT) {
-                chars.left
+                &border.left.style.chars().left
             } else {
                 " "
             }
@@ -157,11 +538,11 @@ pub fn determine_edge(
                 || (flags.contains(BorderFlags::PRESERVE_CORNERS)
                     && (flags.contains(BorderFlags::TOP) || flags.contains(BorderFlags::RIGHT)))
             {
-                chars.top_right
+                border.corner(BorderFlags::TOP | BorderFlags::RIGHT)
             } else if flags.contains(BorderFlags::TOP) {
-                chars.top
+                &border.top.style.chars().top
             } else if flags.contains(BorderFlags::RIGHT) {
-                chars.right
+                &border.right.style.chars().right
             } else {
                 " "
             }
@@ -172,11 +553,11 @@ pub fn determine_edge(
                 || (flags.contains(BorderFlags::PRESERVE_CORNERS)
                     && (flags.contains(BorderFlags::BOTTOM) || flags.contains(BorderFlags::LEFT)))
             {
-                chars.bottom_left
+                border.corner(BorderFlags::BOTTOM | BorderFlags::LEFT)
             } else if flags.contains(BorderFlags::BOTTOM) {
-                chars.bottom
+                &border.bottom.style.chars().bottom
             } else if flags.contains(BorderFlags::LEFT) {
-                chars.left
+                &border.left.style.chars().left
             } else {
                 " "
             }
@@ -187,48 +568,221 @@ pub fn determine_edge(
                 || (flags.contains(BorderFlags::PRESERVE_CORNERS)
                     && (flags.contains(BorderFlags::BOTTOM) || flags.contains(BorderFlags::RIGHT)))
             {
-                chars.bottom_right
+                border.corner(BorderFlags::BOTTOM | BorderFlags::RIGHT)
             } else if flags.contains(BorderFlags::BOTTOM) {
-                chars.bottom
+                &border.bottom.style.chars().bottom
             } else if flags.contains(BorderFlags::RIGHT) {
-                chars.right
+                &border.right.style.chars().right
             } else {
                 " "
             }
         }
 
-        (true, _, _, _) => {
-            if flags.contains(BorderFlags::TOP) {
-                chars.top
-            } else {
-                " "
-            }
+        (true, _, _, _) if flags.contains(BorderFlags::TOP) => &border.top.style.chars().top,
+        (_, true, _, _) if flags.contains(BorderFlags::BOTTOM) => {
+            &border.bottom.style.chars().bottom
         }
+        (_, _, true, _) if flags.contains(BorderFlags::LEFT) => &border.left.style.chars().left,
+        (_, _, _, true) if flags.contains(BorderFlags::RIGHT) => &border.right.style.chars().right,
+        _ => " ",
+    }
+}
 
-        (_, true, _, _) => {
-            if flags.contains(BorderFlags::BOTTOM) {
-                chars.bottom
-            } else {
-                " "
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        (_, _, true, _) => {
-            if flags.contains(BorderFlags::LEFT) {
-                chars.left
-            } else {
-                " "
-            }
-        }
+    #[test]
+    fn custom_accepts_single_width_glyphs() {
+        let chars = BorderChars::custom("=", "!", ["+", "+", "+", "+"]).unwrap();
+        assert_eq!(chars.top, "=");
+        assert_eq!(chars.right, "!");
+        assert!(chars.contains("+"));
+    }
 
-        (_, _, _, true) => {
-            if flags.contains(BorderFlags::RIGHT) {
-                chars.right
-            } else {
-                " "
-            }
+    #[test]
+    fn custom_rejects_wide_empty_and_long_glyphs() {
+        assert!(BorderChars::custom("==", "|", ["+", "+", "+", "+"]).is_err());
+        assert!(BorderChars::custom("-", "", ["+", "+", "+", "+"]).is_err());
+        assert!(BorderChars::custom("-", "|", ["＋", "+", "+", "+"]).is_err());
+        assert!(BorderChars::custom("-", "|", ["+", "+", "+", "\u{7}"]).is_err());
+    }
+
+    /// `utf8` where the locale allows box drawing, and `ascii` otherwise.
+    fn glyph<'a>(utf8: &'a str, ascii: &'a str) -> &'a str {
+        match common::locale_is_utf8() {
+            true => utf8,
+            false => ascii,
         }
+    }
 
-        _ => " ",
+    fn mixed(top: BorderStyle, left: BorderStyle, flags: BorderFlags) -> Border {
+        let sides = BorderSides {
+            top: BorderSide {
+                style: Some(top),
+                ..BorderSide::default()
+            },
+            left: BorderSide {
+                style: Some(left),
+                ..BorderSide::default()
+            },
+            ..BorderSides::default()
+        };
+        Border::new(flags, &BorderStyle::Sharp, &None, &sides)
+    }
+
+    #[test]
+    fn mixed_corners() {
+        use LineWeight::*;
+        let top_left = BorderFlags::TOP | BorderFlags::LEFT;
+        let bottom_right = BorderFlags::BOTTOM | BorderFlags::RIGHT;
+
+        assert_eq!(mixed_corner(Light, Light, top_left), Some("┌"));
+        assert_eq!(mixed_corner(Heavy, Light, top_left), Some("┍"));
+        assert_eq!(mixed_corner(Light, Heavy, bottom_right), Some("┚"));
+        assert_eq!(
+            mixed_corner(Double, Light, BorderFlags::TOP | BorderFlags::RIGHT),
+            Some("╕")
+        );
+        assert_eq!(
+            mixed_corner(Light, Double, BorderFlags::BOTTOM | BorderFlags::LEFT),
+            Some("╙")
+        );
+        assert_eq!(mixed_corner(Double, Double, bottom_right), Some("╝"));
+        assert_eq!(mixed_corner(Heavy, Double, top_left), None);
+        assert_eq!(mixed_corner(Double, Heavy, top_left), None);
+    }
+
+    #[test]
+    fn corners_join_mixed_sides() {
+        let top_left = BorderFlags::TOP | BorderFlags::LEFT;
+        let corner = |top, left, flags| mixed(top, left, flags).corner(top_left).to_string();
+
+        let all = BorderFlags::ALL;
+        assert_eq!(
+            corner(BorderStyle::Thick, BorderStyle::Sharp, all),
+            glyph("┍", "+")
+        );
+        assert_eq!(
+            corner(BorderStyle::Sharp, BorderStyle::Double, all),
+            glyph("╓", "+")
+        );
+        assert_eq!(
+            corner(BorderStyle::Rounded, BorderStyle::Rounded, all),
+            glyph("╭", "+")
+        );
+        // Light lines of different styles meet in a plain light corner.
+        assert_eq!(
+            corner(BorderStyle::Rounded, BorderStyle::Dotted, all),
+            glyph("┌", "+")
+        );
+        // Heavy and double lines have no shared corner, so the top wins.
+        assert_eq!(
+            corner(BorderStyle::Thick, BorderStyle::Double, all),
+            glyph("┏", "+")
+        );
+        assert_eq!(
+            corner(BorderStyle::Block, BorderStyle::Sharp, all),
+            glyph("▗", "+")
+        );
+
+        // A corner only one side asks for uses that side's own glyph.
+        let left_only = BorderFlags::LEFT | BorderFlags::PRESERVE_CORNERS;
+        assert_eq!(
+            corner(BorderStyle::Thick, BorderStyle::Double, left_only),
+            glyph("╔", "+")
+        );
+        let top_only = BorderFlags::TOP | BorderFlags::PRESERVE_CORNERS;
+        assert_eq!(
+            corner(BorderStyle::Sharp, BorderStyle::Thick, top_only),
+            glyph("┌", "+")
+        );
+    }
+
+    #[test]
+    fn side_widths() {
+        let sides = BorderSides {
+            top: BorderSide {
+                width: Some(BorderWidth::Thick),
+                ..BorderSide::default()
+            },
+            right: BorderSide {
+                width: Some(BorderWidth::None),
+                ..BorderSide::default()
+            },
+            ..BorderSides::default()
+        };
+        let border = Border::new(BorderFlags::ALL, &BorderStyle::Rounded, &None, &sides);
+
+        assert_eq!(border.top.style, BorderStyle::Thick);
+        assert!(!border.contains(BorderFlags::RIGHT));
+        assert_eq!(
+            border.corner(BorderFlags::BOTTOM | BorderFlags::LEFT),
+            glyph("╰", "+")
+        );
+        assert_eq!(
+            determine_edge(&border, BorderFlags::TOP | BorderFlags::LEFT),
+            glyph("┍", "+")
+        );
+        // The right side is gone, but its corners are kept for the top and bottom.
+        assert_eq!(
+            determine_edge(&border, BorderFlags::TOP | BorderFlags::RIGHT),
+            glyph("┓", "+")
+        );
+        assert_eq!(determine_edge(&border, BorderFlags::RIGHT), " ");
+    }
+
+    #[test]
+    fn junctions() {
+        let (top, bottom, left, right) = (
+            BorderFlags::TOP,
+            BorderFlags::BOTTOM,
+            BorderFlags::LEFT,
+            BorderFlags::RIGHT,
+        );
+        let junction = |style: BorderStyle, arms| style.junction(arms).to_string();
+
+        assert_eq!(junction(BorderStyle::Sharp, top | bottom), glyph("│", "|"));
+        assert_eq!(junction(BorderStyle::Sharp, left | right), glyph("─", "-"));
+        assert_eq!(junction(BorderStyle::Sharp, top), glyph("│", "|"));
+        // Corners sit opposite their arms, and keep the style's own glyph.
+        assert_eq!(
+            junction(BorderStyle::Sharp, bottom | right),
+            glyph("┌", "+")
+        );
+        assert_eq!(junction(BorderStyle::Rounded, top | left), glyph("╯", "+"));
+        assert_eq!(
+            junction(BorderStyle::Double, bottom | left),
+            glyph("╗", "+")
+        );
+
+        assert_eq!(
+            junction(BorderStyle::Sharp, top | bottom | left | right),
+            glyph("┼", "+")
+        );
+        assert_eq!(
+            junction(BorderStyle::Sharp, top | bottom | left),
+            glyph("┤", "+")
+        );
+        assert_eq!(
+            junction(BorderStyle::Thick, top | bottom | right),
+            glyph("┣", "+")
+        );
+        assert_eq!(
+            junction(BorderStyle::HeavyDashed, left | right | top),
+            glyph("┻", "+")
+        );
+        assert_eq!(
+            junction(BorderStyle::Double, left | right | bottom),
+            glyph("╦", "+")
+        );
+        assert_eq!(
+            junction(BorderStyle::Double, BorderFlags::ALL),
+            glyph("╬", "+")
+        );
+        // Styles without box-drawing weights have no tees.
+        assert_eq!(junction(BorderStyle::Block, top | bottom | left), "+");
+        assert_eq!(junction(BorderStyle::Ascii, BorderFlags::ALL), "+");
+        assert_eq!(junction(BorderStyle::Ascii, top | bottom), "|");
     }
 }
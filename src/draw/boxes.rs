@@ -1,7 +1,11 @@
-use std::cmp;
+use std::fmt;
 
 use crate::common;
-use crate::draw::border::{self, BorderFlags, determine_edge};
+use crate::draw::border::{self, Border, BorderFlags, determine_edge};
+use crate::draw::content::{HorizontalAlign, Padding, PlacedLine, VerticalAlign, place_content};
+use crate::draw::gradient::Gradient;
+use crate::draw::shadow::{Shadow, draw_shadow};
+use crate::draw::text::{StyledLine, TextAttributes, TextStyle, printable};
 
 #[derive(Debug)]
 pub enum DrawError {
@@ -17,37 +21,105 @@ pub struct BoxOptions<'a> {
 
     pub border_options: BorderFlags,
     pub border_style: border::BorderStyle,
+    pub border_sides: border::BorderSides,
 
     pub border_color: Option<common::Color>,
     pub background_color: Option<common::Color>,
     pub text_color: Option<common::Color>,
 
-    pub content: Option<Vec<String>>,
+    /// Takes precedence over `background_color` when set.
+    pub background_gradient: Option<Gradient>,
+    /// Runs around the perimeter and takes precedence over all border colors when set.
+    pub border_gradient: Option<Gradient>,
+
+    pub shadow: Option<Shadow>,
+
+    pub padding: Padding,
+    pub text_align: HorizontalAlign,
+    pub vertical_align: VerticalAlign,
+
+    pub content: Option<Vec<StyledLine>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BoxChar {
-    fg: String,
-    bg: String,
+    pub(crate) fg: Option<common::Color>,
+    pub(crate) bg: Option<common::Color>,
+    pub(crate) attributes: TextAttributes,
+    pub(crate) link: Option<String>,
+    pub(crate) widget: Option<usize>,
     // prefix: String,
-    content: String,
+    pub(crate) content: String,
 }
 
 impl Default for BoxChar {
     fn default() -> Self {
         BoxChar {
-            fg: String::new(),
-            bg: String::new(),
+            fg: None,
+            bg: None,
+            attributes: TextAttributes::NONE,
+            link: None,
+            widget: None,
             // prefix: String::new(),
             content: String::from(" "),
         }
     }
 }
 
+impl fmt::Display for BoxChar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // write!(f, "{}{}{}", self.prefix, self.content, self.suffix)
+        if let Some(fg) = &self.fg {
+            write!(f, "{}", fg.fg())?;
+        }
+        if let Some(bg) = &self.bg {
+            write!(f, "{}", bg.bg())?;
+        }
+        write!(f, "{}", self.attributes.sgr())?;
+
+        match &self.link {
+            Some(link) => write!(
+                f,
+                "\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\",
+                link_target(link),
+                self.content
+            )?,
+            None => write!(f, "{}", self.content)?,
+        }
+
+        write!(f, "\x1b[0m")
+    }
+}
+
+/// `link` as it can be written into an OSC 8 sequence: anything but
+/// printable ASCII is percent-encoded, so a link holding ESC or BEL cannot
+/// end the sequence early and write escapes of its own.
+fn link_target(link: &str) -> String {
+    let mut target = String::with_capacity(link.len());
+    for byte in link.bytes() {
+        match byte {
+            b' '..=b'~' => target.push(byte as char),
+            _ => target.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    target
+}
+
 impl BoxChar {
-    pub fn to_string(&self) -> String {
-        // format!("{}{}{}", self.prefix, self.content, self.suffix)
-        format!("{}{}{}\x1b[0m", self.fg, self.bg, self.content)
+    /// Writes `c` into the cell with `style`, keeping the cell's own colors
+    /// where the style leaves them unset.
+    fn set_styled(&mut self, c: char, style: &TextStyle) {
+        self.content = printable(c).to_string();
+
+        if style.fg.is_some() {
+            self.fg = style.fg.clone();
+        }
+        if style.bg.is_some() {
+            self.bg = style.bg.clone();
+        }
+        self.attributes = style.attributes;
+        self.link = style.link.clone();
+        self.widget = style.widget;
     }
 }
 
@@ -55,311 +127,309 @@ fn make_border(left: &str, middle: &str, right: &str, width: usize) -> Vec<BoxCh
     let mut border_str = Vec::new();
 
     border_str.push(BoxChar {
-        fg: String::new(),
-        bg: String::new(),
         content: left.to_string(),
+        ..Default::default()
     });
     for _ in 0..width {
         border_str.push(BoxChar {
-            fg: String::new(),
-            bg: String::new(),
             content: middle.to_string(),
+            ..Default::default()
         });
     }
 
     border_str.push(BoxChar {
-        fg: String::new(),
-        bg: String::new(),
         content: right.to_string(),
+        ..Default::default()
     });
 
     border_str
 }
 
+/// Range of cells in a row of `width` cells that lie between the left and right borders.
+fn inner_range(border: &Border, width: usize) -> std::ops::Range<usize> {
+    let start = border.contains(BorderFlags::LEFT) as usize;
+    let end = width.saturating_sub(border.contains(BorderFlags::RIGHT) as usize);
+
+    start..end.max(start)
+}
+
 fn add_background_color(
-    border: &mut Vec<BoxChar>,
-    border_style: &border::BorderStyle,
+    row: &mut [BoxChar],
+    border: &Border,
     background_color: &Option<common::Color>,
 ) {
     if let Some(bg_color) = background_color {
-        let border_width = border_style.chars().border_width();
-        let bg_ansi = bg_color.bg();
-
-        let skipping = (border.first().unwrap().content.len() == border_width) as usize;
-        let taking = border.len()
-            - (border.last().unwrap().content.len() == border_width) as usize
-            - skipping;
+        let inner = inner_range(border, row.len());
 
-        for char in border.iter_mut().skip(skipping).take(taking) {
-            char.bg = bg_ansi.clone();
+        for char in &mut row[inner] {
+            char.bg = Some(bg_color.clone());
         }
     }
 }
 
-fn add_left_border_color(border: &mut Vec<BoxChar>, border_color: &Option<common::Color>) {
-    if let Some(border_color) = border_color {
-        let border_ansi = border_color.fg();
-        if let Some(first) = border.first_mut() {
-            first.fg = border_ansi.clone();
-        }
+fn add_left_border_color(row: &mut [BoxChar], border_color: &Option<common::Color>) {
+    if let Some(border_color) = border_color
+        && let Some(first) = row.first_mut()
+    {
+        first.fg = Some(border_color.clone());
     }
 }
 
-fn add_right_border_color(border: &mut Vec<BoxChar>, border_color: &Option<common::Color>) {
-    if let Some(border_color) = border_color {
-        let border_ansi = border_color.fg();
-        if let Some(last) = border.last_mut() {
-            last.fg = border_ansi.clone();
-        }
+fn add_right_border_color(row: &mut [BoxChar], border_color: &Option<common::Color>) {
+    if let Some(border_color) = border_color
+        && let Some(last) = row.last_mut()
+    {
+        last.fg = Some(border_color.clone());
     }
 }
 
-fn add_edge_border_color(border: &mut Vec<BoxChar>, border_color: &Option<common::Color>) {
-    if let Some(border_color) = border_color {
-        let border_ansi = border_color.fg();
+fn add_edge_border_color(row: &mut [BoxChar], border: &Border, edge: BorderFlags) {
+    let last = row.len().saturating_sub(1);
+
+    for (i, char) in row.iter_mut().enumerate() {
+        let mut position = edge;
+        if i == 0 {
+            position |= BorderFlags::LEFT;
+        } else if i == last {
+            position |= BorderFlags::RIGHT;
+        }
 
-        for char in border.iter_mut() {
-            char.fg = border_ansi.clone();
+        if let Some(border_color) = border.color_at(position) {
+            char.fg = Some(border_color.clone());
         }
     }
 }
 
-fn add_text_color(
-    border: &mut Vec<BoxChar>,
-    border_style: &border::BorderStyle,
-    text_color: &Option<common::Color>,
+fn add_background_gradient(
+    row: &mut [BoxChar],
+    border: &Border,
+    gradient: &Gradient,
+    y: usize,
+    size: &common::Vec2,
 ) {
-    if let Some(text_color) = text_color {
-        let border_width = border_style.chars().border_width();
-        let text_ansi = text_color.fg();
+    let inner = inner_range(border, row.len());
 
-        let mut start_index = 0;
-        if border.first().unwrap().content.len() == border_width {
-            start_index = 1;
-        }
+    for x in inner {
+        row[x].bg = Some(gradient.sample(&common::Vec2::new(x, y), size));
+    }
+}
+
+fn add_border_gradient(
+    row: &mut [BoxChar],
+    border: &Border,
+    gradient: &Gradient,
+    y: usize,
+    size: &common::Vec2,
+) {
+    let edge = (y == 0 && border.contains(BorderFlags::TOP))
+        || (y + 1 == size.y && border.contains(BorderFlags::BOTTOM));
 
-        let mut last_index = border.len() - 1;
-        if border.last().unwrap().content.len() == border_width {
-            last_index -= 1;
+    let last = row.len().saturating_sub(1);
+    for (x, char) in row.iter_mut().enumerate() {
+        let is_border = edge
+            || (x == 0 && border.contains(BorderFlags::LEFT))
+            || (x == last && border.contains(BorderFlags::RIGHT));
+
+        if is_border {
+            char.fg = Some(gradient.sample_perimeter(&common::Vec2::new(x, y), size));
         }
+    }
+}
 
-        for char in border
-            .iter_mut()
-            .skip(start_index)
-            .take(last_index - start_index + 1)
-        {
-            char.fg = text_ansi.clone();
+fn add_text_color(row: &mut [BoxChar], border: &Border, text_color: &Option<common::Color>) {
+    if let Some(text_color) = text_color {
+        let inner = inner_range(border, row.len());
+
+        for char in &mut row[inner] {
+            char.fg = Some(text_color.clone());
         }
     }
 }
 
-fn draw_edge(
-    buffer: &mut Vec<Vec<BoxChar>>,
+fn make_edge(border: &Border, width: usize, flags: BorderFlags) -> Vec<BoxChar> {
+    let mut edge = make_border(
+        determine_edge(border, flags | BorderFlags::LEFT),
+        determine_edge(border, flags),
+        determine_edge(border, flags | BorderFlags::RIGHT),
+        width.saturating_sub(2),
+    );
+    edge.truncate(width);
+
+    add_edge_border_color(&mut edge, border, flags);
+
+    edge
+}
+
+fn make_middle(
     options: &BoxOptions,
-    flags: BorderFlags,
-    index: usize,
-) {
-    let (left, middle, right) = (
-        determine_edge(
-            &options.border_options,
-            &options.border_style,
-            flags | BorderFlags::LEFT,
-        ),
-        determine_edge(&options.border_options, &options.border_style, flags),
-        determine_edge(
-            &options.border_options,
-            &options.border_style,
-            flags | BorderFlags::RIGHT,
-        ),
+    border: &Border,
+    y: usize,
+    line: Option<&PlacedLine>,
+    content_end: usize,
+) -> Vec<BoxChar> {
+    let mut middle = make_border(
+        determine_edge(border, BorderFlags::LEFT),
+        " ",
+        determine_edge(border, BorderFlags::RIGHT),
+        options.size.x.saturating_sub(2),
     );
+    middle.truncate(options.size.x);
 
-    let mut edge_data = make_border(left, middle, right, options.size.x.saturating_sub(2));
-
-    let mut edge = edge_data
-        .iter_mut()
-        .skip(cmp::max(0, -options.position.x) as usize)
-        .take(cmp::min(
-            options.screen_size.x,
-            cmp::min(
-                options.size.x,
-                (options.screen_size.x as i16 - options.position.x) as usize,
-            ),
-        ))
-        .map(|c| c.clone())
-        .collect::<Vec<_>>();
-
-    let prefix = buffer[index]
-        .iter()
-        .take(cmp::max(options.position.x, 0) as usize)
-        .map(|c| c.clone())
-        .collect::<Vec<_>>();
-
-    let suffix = buffer[index]
-        .get(prefix.len() + edge.len()..)
-        .unwrap_or(&vec![])
-        .iter()
-        .map(|c| c.clone())
-        .collect::<Vec<_>>();
-
-    if options.border_options.contains(BorderFlags::TOP)
-        || options.border_options.contains(BorderFlags::BOTTOM)
-    {
-        add_edge_border_color(&mut edge, &options.border_color);
+    match &options.background_gradient {
+        Some(gradient) => add_background_gradient(&mut middle, border, gradient, y, &options.size),
+        None => add_background_color(&mut middle, border, &options.background_color),
     }
 
-    if !((flags == BorderFlags::TOP && options.border_options.contains(BorderFlags::TOP))
-        || (flags == BorderFlags::BOTTOM && options.border_options.contains(BorderFlags::BOTTOM)))
-    {
-        add_background_color(&mut edge, &options.border_style, &options.background_color);
+    if border.contains(BorderFlags::LEFT) {
+        add_left_border_color(&mut middle, &border.left.color);
     }
 
-    let mut new_row = Vec::new();
-    new_row.extend(prefix);
-    new_row.extend(edge.into_iter().map(|c| c.clone()));
-    new_row.extend(suffix);
+    if border.contains(BorderFlags::RIGHT) {
+        add_right_border_color(&mut middle, &border.right.color);
+    }
 
-    buffer[index] = new_row;
-}
+    add_text_color(&mut middle, border, &options.text_color);
 
-pub fn draw_box(buffer: &mut Vec<Vec<BoxChar>>, options: BoxOptions) -> () {
-    if options.position.y >= 0 && options.border_options.contains(BorderFlags::TOP) {
-        draw_edge(
-            buffer,
-            &options,
-            BorderFlags::TOP,
-            cmp::max(options.position.y, 0) as usize,
-        );
-    }
+    if let Some(line) = line {
+        let end = content_end.min(middle.len());
 
-    // Part 2: Bottom border
-    let bottom_index = options.position.y + (options.size.y as i16) - 1;
-    if bottom_index >= 0
-        && bottom_index < (buffer.len() as i16)
-        && options.border_options.contains(BorderFlags::BOTTOM)
-    {
-        draw_edge(buffer, &options, BorderFlags::BOTTOM, bottom_index as usize);
+        for (cell, (char, style)) in middle[line.column.min(end)..end]
+            .iter_mut()
+            .zip(line.text.chars())
+        {
+            cell.set_styled(char, style);
+        }
     }
 
-    // Part 3: Middle border
-    let middle_border_data = make_border(
-        determine_edge(
-            &options.border_options,
-            &options.border_style,
-            BorderFlags::LEFT,
-        ),
-        " ",
-        determine_edge(
-            &options.border_options,
-            &options.border_style,
-            BorderFlags::RIGHT,
+    middle
+}
+
+/// The area inside the border and padding of a box of `size`, as its origin
+/// relative to the box and its size.
+pub fn content_area(
+    border: &Border,
+    padding: &Padding,
+    size: &common::Vec2,
+) -> (common::Vec2, common::Vec2) {
+    let left = border.contains(BorderFlags::LEFT) as usize + padding.left;
+    let top = border.contains(BorderFlags::TOP) as usize + padding.top;
+    let right = border.contains(BorderFlags::RIGHT) as usize + padding.right;
+    let bottom = border.contains(BorderFlags::BOTTOM) as usize + padding.bottom;
+
+    (
+        common::Vec2::new(left, top),
+        common::Vec2::new(
+            size.x.saturating_sub(left + right),
+            size.y.saturating_sub(top + bottom),
         ),
-        options.size.x.saturating_sub(2),
-    );
+    )
+}
 
-    let mut middle_border = middle_border_data
-        .iter()
-        .skip(cmp::max(0, -options.position.x) as usize)
-        .take(cmp::min(
-            options.screen_size.x,
-            cmp::min(
-                options.size.x,
-                (options.screen_size.x as i16 - options.position.x) as usize,
-            ),
-        ))
-        .map(|c| c.clone())
-        .collect::<Vec<_>>();
-
-    add_background_color(
-        &mut middle_border,
+/// Renders the box on its own, as `size.y` rows of `size.x` cells.
+fn render_box(options: &BoxOptions) -> Vec<Vec<BoxChar>> {
+    let border = Border::new(
+        options.border_options,
         &options.border_style,
-        &options.background_color,
+        &options.border_color,
+        &options.border_sides,
     );
 
-    if options.border_options.contains(BorderFlags::LEFT) && options.position.x >= 0 {
-        add_left_border_color(&mut middle_border, &options.border_color);
-    }
-
-    if options.border_options.contains(BorderFlags::RIGHT)
-        && (options.position.x + options.size.x as i16) <= (options.screen_size.x as i16)
-    {
-        add_right_border_color(&mut middle_border, &options.border_color);
-    }
+    let top = border.contains(BorderFlags::TOP);
+    let bottom = border.contains(BorderFlags::BOTTOM);
 
-    add_text_color(
-        &mut middle_border,
-        &options.border_style,
-        &options.text_color,
+    let (origin, area) = content_area(&border, &options.padding, &options.size);
+    let placed = place_content(
+        options.content.as_deref().unwrap_or(&[]),
+        &origin,
+        &area,
+        options.text_align,
+        options.vertical_align,
     );
 
-    for i in 0..options.size.y {
-        if i == 0 && options.border_options.contains(BorderFlags::TOP) {
-            continue;
-        }
+    (0..options.size.y)
+        .map(|i| {
+            let mut row = if i == 0 && top {
+                make_edge(&border, options.size.x, BorderFlags::TOP)
+            } else if i == options.size.y - 1 && bottom {
+                make_edge(&border, options.size.x, BorderFlags::BOTTOM)
+            } else {
+                let line = placed.iter().find(|line| line.row == i);
+                make_middle(options, &border, i, line, origin.x + area.x)
+            };
+
+            if let Some(gradient) = &options.border_gradient {
+                add_border_gradient(&mut row, &border, gradient, i, &options.size);
+            }
 
-        if i == options.size.y - 1 && options.border_options.contains(BorderFlags::BOTTOM) {
+            row
+        })
+        .collect()
+}
+
+/// Copies `tile` onto `buffer` at `position`, clipping it to `screen_size` and the buffer.
+fn blit(
+    buffer: &mut [Vec<BoxChar>],
+    tile: Vec<Vec<BoxChar>>,
+    position: &common::Vec2<i16>,
+    screen_size: &common::Vec2,
+) {
+    for (i, row) in tile.into_iter().enumerate() {
+        let y = position.y as isize + i as isize;
+        if y < 0 || y as usize >= buffer.len() {
             continue;
         }
 
-        let middle_index = options.position.y + (i as i16);
-        if middle_index >= 0 && middle_index < (buffer.len() as i16) {
-            let mut this_line = middle_border
-                .iter()
-                .map(|c| (*c).clone())
-                .collect::<Vec<_>>();
-
-            let middle_prefix = buffer[middle_index as usize]
-                .iter()
-                .take(cmp::max(options.position.x, 0) as usize)
-                .map(|c| c.clone())
-                .collect::<Vec<_>>();
-
-            let middle_suffix = buffer[middle_index as usize]
-                .get(middle_prefix.len() + this_line.len()..)
-                .unwrap_or(&vec![])
-                .iter()
-                .map(|c| c.clone())
-                .collect::<Vec<_>>();
-
-            if options.content.is_some()
-                && options.content.as_ref().unwrap().len()
-                    > i - (options.border_options.contains(BorderFlags::TOP) as usize)
-            {
-                let content = options
-                    .content
-                    .as_ref()
-                    .unwrap()
-                    .get(i - (options.border_options.contains(BorderFlags::TOP) as usize))
-                    .unwrap();
-
-                for (j, char) in content
-                    .chars()
-                    .skip(cmp::max(0, -options.position.x - 1) as usize)
-                    .enumerate()
-                {
-                    let index = j
-                        + (options.border_options.contains(BorderFlags::LEFT)
-                            && options.position.x >= 0) as usize;
-
-                    if index
-                        >= this_line.len()
-                            - (options.border_options.contains(BorderFlags::RIGHT)
-                                && (options.position.x + options.size.x as i16)
-                                    < (options.screen_size.x as i16))
-                                as usize
-                    {
-                        continue;
-                    } else {
-                        this_line[index].content = char.to_string();
-                    }
-                }
-            }
+        let line = &mut buffer[y as usize];
+        let width = screen_size.x.min(line.len());
 
-            // Combine the prefix, content, and suffix into the final line
-            let mut buffer_line = Vec::new();
-            buffer_line.extend(middle_prefix);
-            buffer_line.extend(this_line.iter().map(|c| c.clone()));
-            buffer_line.extend(middle_suffix);
-            buffer[middle_index as usize] = buffer_line;
+        for (j, cell) in row.into_iter().enumerate() {
+            let x = position.x as isize + j as isize;
+            if x >= 0 && (x as usize) < width {
+                line[x as usize] = cell;
+            }
         }
     }
 }
+
+pub fn draw_box(buffer: &mut [Vec<BoxChar>], options: BoxOptions) {
+    let tile = render_box(&options);
+    blit(buffer, tile, &options.position, options.screen_size);
+
+    if let Some(shadow) = &options.shadow {
+        draw_shadow(
+            buffer,
+            &options.position,
+            &options.size,
+            options.screen_size,
+            shadow,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn link_targets_cannot_escape_the_osc_8_sequence() {
+        assert_eq!(
+            link_target("https://example.com/a?b=c"),
+            "https://example.com/a?b=c"
+        );
+        assert_eq!(
+            link_target("https://x/\x1b]52;c;aGk=\x07"),
+            "https://x/%1B]52;c;aGk=%07"
+        );
+        assert_eq!(link_target("https://x/é"), "https://x/%C3%A9");
+
+        let cell = BoxChar {
+            content: "a".to_string(),
+            link: Some("x\x1b\\y".to_string()),
+            ..Default::default()
+        };
+        let written = cell.to_string();
+        assert!(written.contains("\x1b]8;;x%1B\\y\x1b\\a"));
+        // Two to start the link, two to end it and one to reset the style.
+        assert_eq!(written.matches('\x1b').count(), 5);
+    }
+}
@@ -0,0 +1,668 @@
+use crate::common::Color;
+
+/// The colors CSS knows by name, sorted by name.
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+impl Color {
+    /// Reads a color written the way CSS writes it: `#rgb`, `#rgba`,
+    /// `#rrggbb`, `#rrggbbaa`, `rgb()`, `rgba()`, `hsl()`, `hsla()` or one of
+    /// the named colors, such as `orange`. Transparency is dropped; see
+    /// [`Color::parse_with_alpha`] to keep it.
+    pub fn parse(text: &str) -> Option<Color> {
+        Color::parse_with_alpha(text).map(|(color, _)| color)
+    }
+
+    /// Like [`Color::parse`], but also returns the opacity, from 0.0 for
+    /// transparent to 1.0 for opaque, for blending with what is behind.
+    pub fn parse_with_alpha(text: &str) -> Option<(Color, f32)> {
+        let text = text.trim().to_ascii_lowercase();
+
+        if let Some(hex) = text.strip_prefix('#') {
+            return parse_hex(hex);
+        }
+        if let Some((function, rest)) = text.split_once('(')
+            && let Some(arguments) = rest.strip_suffix(')')
+        {
+            return match function.trim_end() {
+                "rgb" | "rgba" => parse_rgb(arguments),
+                "hsl" | "hsla" => parse_hsl(arguments),
+                _ => None,
+            };
+        }
+        match text.as_str() {
+            "transparent" => Some((Color::BLACK, 0.0)),
+            name => Color::named(name).map(|color| (color, 1.0)),
+        }
+    }
+
+    /// The CSS color called `name`, in lowercase.
+    pub fn named(name: &str) -> Option<Color> {
+        NAMED_COLORS
+            .binary_search_by(|(n, _)| (*n).cmp(name))
+            .ok()
+            .map(|index| from_hex(NAMED_COLORS[index].1))
+    }
+
+    /// The color as hue in degrees, from 0 to 360, and saturation and
+    /// lightness, from 0 to 1.
+    pub fn to_hsl(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = hue(self);
+        let lightness = (max + min) / 2.0;
+        let saturation = match max - min {
+            0.0 => 0.0,
+            delta => delta / (1.0 - (2.0 * lightness - 1.0).abs()),
+        };
+        (hue, saturation, lightness)
+    }
+
+    /// The color with `hue` in degrees, and `saturation` and `lightness`
+    /// from 0 to 1.
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Color {
+        let (saturation, lightness) = (saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        from_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    /// The color as hue in degrees, from 0 to 360, and saturation and value,
+    /// from 0 to 1.
+    pub fn to_hsv(&self) -> (f32, f32, f32) {
+        let (hue, max, min) = hue(self);
+        let saturation = match max {
+            0.0 => 0.0,
+            max => (max - min) / max,
+        };
+        (hue, saturation, max)
+    }
+
+    /// The color with `hue` in degrees, and `saturation` and `value` from 0
+    /// to 1.
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Color {
+        let (saturation, value) = (saturation.clamp(0.0, 1.0), value.clamp(0.0, 1.0));
+        let chroma = value * saturation;
+        from_chroma(hue, chroma, value - chroma)
+    }
+
+    /// The color with its HSL lightness raised by `amount`, from 0 to 1.
+    pub fn lighten(&self, amount: f32) -> Color {
+        let (hue, saturation, lightness) = self.to_hsl();
+        Color::from_hsl(hue, saturation, lightness + amount)
+    }
+
+    /// The color with its HSL lightness lowered by `amount`, from 0 to 1.
+    pub fn darken(&self, amount: f32) -> Color {
+        self.lighten(-amount)
+    }
+
+    /// The color `t` of the way from this one to `other`, mixed in Oklab so
+    /// the colors between keep their lightness instead of going muddy.
+    pub fn mix(&self, other: &Color, t: f32) -> Color {
+        let (a, b) = (to_oklab(self), to_oklab(other));
+        let t = t.clamp(0.0, 1.0);
+
+        from_oklab([
+            a[0] + (b[0] - a[0]) * t,
+            a[1] + (b[1] - a[1]) * t,
+            a[2] + (b[2] - a[2]) * t,
+        ])
+    }
+
+    /// How bright the color is to the eye, from 0 for black to 1 for white,
+    /// as WCAG defines it.
+    pub fn luminance(&self) -> f32 {
+        0.2126 * to_linear(self.r) + 0.7152 * to_linear(self.g) + 0.0722 * to_linear(self.b)
+    }
+
+    /// The WCAG contrast ratio between the two colors, from 1 for the same
+    /// color to 21 for black and white. Text wants at least 4.5.
+    pub fn contrast_ratio(&self, other: &Color) -> f32 {
+        let (a, b) = (self.luminance(), other.luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
+}
+
+fn from_hex(hex: u32) -> Color {
+    Color {
+        r: (hex >> 16) as u8,
+        g: (hex >> 8) as u8,
+        b: hex as u8,
+    }
+}
+
+/// Reads the digits after the `#` of a hex color.
+fn parse_hex(hex: &str) -> Option<(Color, f32)> {
+    let digits = hex
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<Vec<_>>>()?;
+    // Short forms repeat each digit: #f80 is #ff8800.
+    let channels = match digits.len() {
+        3 | 4 => digits.iter().map(|d| d * 17).collect::<Vec<_>>(),
+        6 | 8 => digits
+            .chunks(2)
+            .map(|pair| pair[0] * 16 + pair[1])
+            .collect(),
+        _ => return None,
+    };
+
+    let color = Color {
+        r: channels[0],
+        g: channels[1],
+        b: channels[2],
+    };
+    let alpha = channels.get(3).map_or(1.0, |&alpha| alpha as f32 / 255.0);
+    Some((color, alpha))
+}
+
+/// Splits the arguments of a color function, written either with commas,
+/// like `255, 0, 0, 0.5`, or with spaces, like `255 0 0 / 50%`. The alpha
+/// is the fourth argument, if there is one.
+fn arguments(text: &str) -> Option<Vec<&str>> {
+    let arguments = match text.contains(',') {
+        true => text.split(',').map(str::trim).collect::<Vec<_>>(),
+        false => {
+            let (channels, alpha) = match text.split_once('/') {
+                Some((channels, alpha)) => (channels, Some(alpha.trim())),
+                None => (text, None),
+            };
+            let mut arguments = channels.split_whitespace().collect::<Vec<_>>();
+            if arguments.len() != 3 {
+                return None;
+            }
+            arguments.extend(alpha);
+            arguments
+        }
+    };
+
+    match arguments.len() {
+        3 | 4 => Some(arguments),
+        _ => None,
+    }
+}
+
+fn parse_rgb(text: &str) -> Option<(Color, f32)> {
+    let arguments = arguments(text)?;
+    let channel = |text: &str| {
+        let value = match text.strip_suffix('%') {
+            Some(percent) => number(percent)? * 2.55,
+            None => number(text)?,
+        };
+        Some(value.clamp(0.0, 255.0).round() as u8)
+    };
+
+    let color = Color {
+        r: channel(arguments[0])?,
+        g: channel(arguments[1])?,
+        b: channel(arguments[2])?,
+    };
+    Some((color, alpha(arguments.get(3))?))
+}
+
+fn parse_hsl(text: &str) -> Option<(Color, f32)> {
+    let arguments = arguments(text)?;
+    let hue = parse_hue(arguments[0])?;
+    // Saturation and lightness are percentages, with or without the `%`.
+    let fraction = |text: &str| Some(number(text.strip_suffix('%').unwrap_or(text))? / 100.0);
+
+    let color = Color::from_hsl(hue, fraction(arguments[1])?, fraction(arguments[2])?);
+    Some((color, alpha(arguments.get(3))?))
+}
+
+/// Reads an angle in degrees, or in the unit it is written with.
+fn parse_hue(text: &str) -> Option<f32> {
+    let units = [
+        ("deg", 1.0),
+        ("grad", 0.9),
+        ("rad", 180.0 / std::f32::consts::PI),
+        ("turn", 360.0),
+    ];
+    for (unit, degrees) in units {
+        if let Some(value) = text.strip_suffix(unit) {
+            return Some(number(value)? * degrees);
+        }
+    }
+    number(text)
+}
+
+/// Reads an opacity written as a number from 0 to 1 or as a percentage.
+/// Without one, the color is opaque.
+fn alpha(text: Option<&&str>) -> Option<f32> {
+    let Some(text) = text else {
+        return Some(1.0);
+    };
+    let value = match text.strip_suffix('%') {
+        Some(percent) => number(percent)? / 100.0,
+        None => number(text)?,
+    };
+    Some(value.clamp(0.0, 1.0))
+}
+
+fn number(text: &str) -> Option<f32> {
+    text.trim()
+        .parse()
+        .ok()
+        .filter(|value: &f32| value.is_finite())
+}
+
+/// The hue of `color` in degrees, with its largest and smallest channels
+/// from 0 to 1.
+fn hue(color: &Color) -> (f32, f32, f32) {
+    let (r, g, b) = (
+        color.r as f32 / 255.0,
+        color.g as f32 / 255.0,
+        color.b as f32 / 255.0,
+    );
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    (hue, max, min)
+}
+
+/// The color with `hue` in degrees and `chroma`, lifted by `lightest`, all
+/// from 0 to 1, which is where HSL and HSV meet.
+fn from_chroma(hue: f32, chroma: f32, lightest: f32) -> Color {
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let channel = |value: f32| ((value + lightest).clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    Color {
+        r: channel(r),
+        g: channel(g),
+        b: channel(b),
+    }
+}
+
+fn to_linear(channel: u8) -> f32 {
+    let c = channel as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(channel: f32) -> u8 {
+    let c = if channel <= 0.0031308 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    };
+
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn to_oklab(color: &Color) -> [f32; 3] {
+    let (r, g, b) = (to_linear(color.r), to_linear(color.g), to_linear(color.b));
+
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+fn from_oklab(lab: [f32; 3]) -> Color {
+    let l = (lab[0] + 0.396_337_78 * lab[1] + 0.215_803_76 * lab[2]).powi(3);
+    let m = (lab[0] - 0.105_561_346 * lab[1] - 0.063_854_17 * lab[2]).powi(3);
+    let s = (lab[0] - 0.089_484_18 * lab[1] - 1.291_485_5 * lab[2]).powi(3);
+
+    Color {
+        r: from_linear(4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s),
+        g: from_linear(-1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s),
+        b: from_linear(-0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn parses_hex() {
+        assert_eq!(Color::parse("#ff8800"), Some(rgb(255, 136, 0)));
+        assert_eq!(Color::parse("  #FF8800 "), Some(rgb(255, 136, 0)));
+        assert_eq!(Color::parse("#f80"), Some(rgb(255, 136, 0)));
+        assert_eq!(
+            Color::parse_with_alpha("#f808"),
+            Some((rgb(255, 136, 0), 136.0 / 255.0))
+        );
+        assert_eq!(
+            Color::parse_with_alpha("#ff880000"),
+            Some((rgb(255, 136, 0), 0.0))
+        );
+
+        for bad in [
+            "#",
+            "#ff",
+            "#ff880",
+            "#ff8800a",
+            "#ff88000000",
+            "#gg8800",
+            "ff8800",
+        ] {
+            assert_eq!(Color::parse(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn parses_rgb() {
+        assert_eq!(Color::parse("rgb(255, 136, 0)"), Some(rgb(255, 136, 0)));
+        assert_eq!(Color::parse("RGB(255 136 0)"), Some(rgb(255, 136, 0)));
+        assert_eq!(Color::parse("rgb (100%, 50%, 0%)"), Some(rgb(255, 128, 0)));
+        assert_eq!(Color::parse("rgb(300, -5, 12.6)"), Some(rgb(255, 0, 13)));
+        assert_eq!(
+            Color::parse_with_alpha("rgba(0, 0, 255, 0.5)"),
+            Some((rgb(0, 0, 255), 0.5))
+        );
+        assert_eq!(
+            Color::parse_with_alpha("rgb(0 0 255 / 25%)"),
+            Some((rgb(0, 0, 255), 0.25))
+        );
+        assert_eq!(
+            Color::parse_with_alpha("rgb(0 0 255 / 2)"),
+            Some((rgb(0, 0, 255), 1.0))
+        );
+
+        for bad in [
+            "rgb(1, 2)",
+            "rgb(1, 2, 3, 4, 5)",
+            "rgb(1 2 3 4)",
+            "rgb(1, 2, 3,)",
+            "rgb(1 2 3 /)",
+            "rgb(1, 2 3)",
+            "rgb(red, 0, 0)",
+            "rgb(nan, 0, 0)",
+            "rgb(inf, 0, 0)",
+            "rgb(1, 2, 3",
+            "rgbx(1, 2, 3)",
+        ] {
+            assert_eq!(Color::parse(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn parses_hsl() {
+        assert_eq!(Color::parse("hsl(0, 100%, 50%)"), Some(Color::RED));
+        assert_eq!(Color::parse("hsl(120 100% 50%)"), Some(Color::GREEN));
+        assert_eq!(Color::parse("hsl(-120deg, 100%, 50%)"), Some(Color::BLUE));
+        assert_eq!(Color::parse("hsl(600, 100, 50)"), Some(Color::BLUE));
+        assert_eq!(
+            Color::parse("hsl(0.5turn 100% 50%)"),
+            Some(rgb(0, 255, 255))
+        );
+        assert_eq!(
+            Color::parse("hsl(200grad 100% 50%)"),
+            Some(rgb(0, 255, 255))
+        );
+        assert_eq!(
+            Color::parse("hsl(3.14159rad 100% 50%)"),
+            Some(rgb(0, 255, 255))
+        );
+        assert_eq!(Color::parse("hsl(0, 0%, 100%)"), Some(Color::WHITE));
+        assert_eq!(Color::parse("hsl(0, 150%, -10%)"), Some(Color::BLACK));
+        assert_eq!(
+            Color::parse_with_alpha("hsla(0, 100%, 50%, 50%)"),
+            Some((Color::RED, 0.5))
+        );
+
+        for bad in [
+            "hsl(0, 100%)",
+            "hsl(red, 100%, 50%)",
+            "hsl(1deg2 1 1)",
+            "hsl(0 1 1 / x)",
+        ] {
+            assert_eq!(Color::parse(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(Color::parse("darkorange"), Some(rgb(255, 140, 0)));
+        assert_eq!(Color::parse("RebeccaPurple"), Some(rgb(102, 51, 153)));
+        assert_eq!(
+            Color::parse_with_alpha("transparent"),
+            Some((Color::BLACK, 0.0))
+        );
+        assert_eq!(Color::parse("notacolor"), None);
+        assert_eq!(Color::parse(""), None);
+
+        // Names are looked up by binary search, which needs them sorted.
+        assert!(NAMED_COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn hsl_round_trips() {
+        for color in [
+            Color::BLACK,
+            Color::WHITE,
+            Color::RED,
+            rgb(255, 136, 0),
+            rgb(18, 52, 86),
+            rgb(128, 128, 128),
+            rgb(200, 10, 250),
+            rgb(1, 254, 127),
+        ] {
+            let (h, s, l) = color.to_hsl();
+            assert_eq!(Color::from_hsl(h, s, l), color);
+            let (h, s, v) = color.to_hsv();
+            assert_eq!(Color::from_hsv(h, s, v), color);
+        }
+
+        let (h, s, l) = rgb(255, 136, 0).to_hsl();
+        assert!(close(h, 32.0) && close(s, 1.0) && close(l, 0.5));
+        let (h, s, v) = Color::BLUE.to_hsv();
+        assert!(close(h, 240.0) && close(s, 1.0) && close(v, 1.0));
+        assert_eq!(Color::WHITE.to_hsl(), (0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn lightens_and_darkens() {
+        assert_eq!(Color::RED.lighten(0.5), Color::WHITE);
+        assert_eq!(Color::RED.darken(0.5), Color::BLACK);
+        assert_eq!(Color::RED.darken(0.25), rgb(128, 0, 0));
+        assert_eq!(Color::WHITE.lighten(1.0), Color::WHITE);
+    }
+
+    #[test]
+    fn mixes_in_oklab() {
+        assert_eq!(Color::RED.mix(&Color::BLUE, 0.0), Color::RED);
+        assert_eq!(Color::RED.mix(&Color::BLUE, 1.0), Color::BLUE);
+        assert_eq!(Color::RED.mix(&Color::BLUE, 2.0), Color::BLUE);
+        // Halfway between black and white is Oklab lightness 0.5, a grey
+        // with no tint.
+        assert_eq!(Color::BLACK.mix(&Color::WHITE, 0.5), rgb(99, 99, 99));
+    }
+
+    #[test]
+    fn contrast_ratios() {
+        assert!(close(Color::BLACK.contrast_ratio(&Color::WHITE), 21.0));
+        assert!(close(Color::WHITE.contrast_ratio(&Color::BLACK), 21.0));
+        assert!(close(Color::RED.contrast_ratio(&Color::RED), 1.0));
+        assert!(close(Color::WHITE.luminance(), 1.0));
+        assert_eq!(Color::BLACK.luminance(), 0.0);
+        // Known values from WCAG checkers.
+        assert!(close(
+            rgb(118, 118, 118).contrast_ratio(&Color::WHITE),
+            4.54
+        ));
+        assert!(close(Color::BLUE.contrast_ratio(&Color::WHITE), 8.59));
+        assert!(close(Color::RED.contrast_ratio(&Color::WHITE), 4.0));
+    }
+}
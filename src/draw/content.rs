@@ -0,0 +1,290 @@
+use crate::common::Vec2;
+use crate::draw::text::{StyledLine, TextStyle, char_width};
+
+/// Space between a box's border and its content, like CSS `padding`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Padding {
+    pub top: usize,
+    pub right: usize,
+    pub bottom: usize,
+    pub left: usize,
+}
+
+impl Padding {
+    pub fn uniform(amount: usize) -> Self {
+        Padding {
+            top: amount,
+            right: amount,
+            bottom: amount,
+            left: amount,
+        }
+    }
+
+    pub fn symmetric(vertical: usize, horizontal: usize) -> Self {
+        Padding {
+            top: vertical,
+            right: horizontal,
+            bottom: vertical,
+            left: horizontal,
+        }
+    }
+}
+
+/// Like CSS `text-align`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HorizontalAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretch the gaps between words to fill the line. The last line is left aligned.
+    Justify,
+}
+
+/// Like CSS `vertical-align` on a table cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// A line of content with its position inside the box, in cells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlacedLine {
+    pub row: usize,
+    pub column: usize,
+    pub text: StyledLine,
+}
+
+fn offset(free: usize, start: bool, center: bool) -> usize {
+    if start {
+        0
+    } else if center {
+        free / 2
+    } else {
+        free
+    }
+}
+
+fn justify(line: &StyledLine, width: usize) -> StyledLine {
+    let chars = line.chars().collect::<Vec<_>>();
+    let words = chars
+        .split(|(c, _)| c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    let used = words
+        .iter()
+        .flat_map(|word| word.iter())
+        .map(|(c, _)| char_width(*c))
+        .sum::<usize>();
+
+    if words.len() < 2 || used + words.len() - 1 > width {
+        return line.clone();
+    }
+
+    let gaps = words.len() - 1;
+    let spaces = width - used;
+    let gap_style = TextStyle::default();
+
+    let mut justified = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        justified.extend(word.iter().copied());
+        if i < gaps {
+            // Leftmost gaps take the remainder, as most word processors do.
+            let gap = spaces / gaps + (i < spaces % gaps) as usize;
+            justified.extend(std::iter::repeat_n((' ', &gap_style), gap));
+        }
+    }
+
+    StyledLine::from_chars(justified)
+}
+
+/// Positions `lines` in the area at `origin` with `size`, dropping lines that
+/// do not fit vertically. Lines wider than the area are left for the caller to clip.
+pub fn place_content(
+    lines: &[StyledLine],
+    origin: &Vec2,
+    size: &Vec2,
+    text_align: HorizontalAlign,
+    vertical_align: VerticalAlign,
+) -> Vec<PlacedLine> {
+    let visible = lines.len().min(size.y);
+    let top = origin.y
+        + offset(
+            size.y - visible,
+            vertical_align == VerticalAlign::Top,
+            vertical_align == VerticalAlign::Middle,
+        );
+
+    lines
+        .iter()
+        .take(visible)
+        .enumerate()
+        .map(|(i, line)| {
+            let text = if text_align == HorizontalAlign::Justify && i + 1 < lines.len() {
+                justify(line, size.x)
+            } else {
+                line.clone()
+            };
+
+            let free = size.x.saturating_sub(text.width());
+            let column = origin.x
+                + offset(
+                    free,
+                    matches!(text_align, HorizontalAlign::Left | HorizontalAlign::Justify),
+                    text_align == HorizontalAlign::Center,
+                );
+
+            PlacedLine {
+                row: top + i,
+                column,
+                text,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::draw::text::{Span, TextAttributes};
+
+    fn lines(texts: &[&str]) -> Vec<StyledLine> {
+        texts.iter().map(|text| StyledLine::from(*text)).collect()
+    }
+
+    /// Each placed line as `(row, column, text)`.
+    fn placed(
+        texts: &[&str],
+        size: (usize, usize),
+        text_align: HorizontalAlign,
+        vertical_align: VerticalAlign,
+    ) -> Vec<(usize, usize, String)> {
+        place_content(
+            &lines(texts),
+            &Vec2::new(2, 1),
+            &Vec2::new(size.0, size.1),
+            text_align,
+            vertical_align,
+        )
+        .into_iter()
+        .map(|line| (line.row, line.column, line.text.to_string()))
+        .collect()
+    }
+
+    fn columns(text_align: HorizontalAlign) -> Vec<usize> {
+        placed(&["ab", "abcd"], (8, 4), text_align, VerticalAlign::Top)
+            .iter()
+            .map(|(_, column, _)| *column)
+            .collect()
+    }
+
+    fn rows(vertical_align: VerticalAlign) -> Vec<usize> {
+        placed(
+            &["ab", "abcd"],
+            (8, 4),
+            HorizontalAlign::Left,
+            vertical_align,
+        )
+        .iter()
+        .map(|(row, _, _)| *row)
+        .collect()
+    }
+
+    #[test]
+    fn aligns() {
+        assert_eq!(columns(HorizontalAlign::Left), [2, 2]);
+        assert_eq!(columns(HorizontalAlign::Center), [5, 4]);
+        assert_eq!(columns(HorizontalAlign::Right), [8, 6]);
+
+        assert_eq!(rows(VerticalAlign::Top), [1, 2]);
+        assert_eq!(rows(VerticalAlign::Middle), [2, 3]);
+        assert_eq!(rows(VerticalAlign::Bottom), [3, 4]);
+
+        // Lines wider than the area start at its left edge, whatever the alignment.
+        assert_eq!(
+            placed(
+                &["far too long"],
+                (4, 1),
+                HorizontalAlign::Right,
+                VerticalAlign::Top
+            ),
+            [(1, 2, "far too long".to_string())]
+        );
+    }
+
+    #[test]
+    fn justifies_gaps() {
+        let justified = |text: &str, width| justify(&StyledLine::from(text), width).to_string();
+
+        assert_eq!(justified("a bb c", 10), "a   bb   c");
+        // The leftmost gaps get the spaces that do not divide evenly.
+        assert_eq!(justified("a bb c", 9), "a   bb  c");
+        assert_eq!(justified("a b c d", 10), "a  b  c  d");
+        assert_eq!(justified("  a   b ", 5), "a   b");
+        // Single words and lines that do not fit are left alone.
+        assert_eq!(justified("word", 10), "word");
+        assert_eq!(justified("aa bb", 4), "aa bb");
+    }
+
+    #[test]
+    fn justifying_keeps_word_styles() {
+        let bold = TextStyle {
+            attributes: TextAttributes::BOLD,
+            ..TextStyle::default()
+        };
+        let line = StyledLine::new(vec![Span::plain("a "), Span::new("b", bold.clone())]);
+        assert_eq!(
+            justify(&line, 4),
+            StyledLine::new(vec![Span::plain("a  "), Span::new("b", bold)])
+        );
+    }
+
+    #[test]
+    fn leaves_the_last_line_unjustified() {
+        assert_eq!(
+            placed(
+                &["a b", "c d"],
+                (6, 3),
+                HorizontalAlign::Justify,
+                VerticalAlign::Top
+            ),
+            [(1, 2, "a    b".to_string()), (2, 2, "c d".to_string())]
+        );
+        assert_eq!(
+            placed(
+                &["a b"],
+                (6, 3),
+                HorizontalAlign::Justify,
+                VerticalAlign::Top
+            ),
+            [(1, 2, "a b".to_string())]
+        );
+    }
+
+    #[test]
+    fn drops_lines_past_the_last_row() {
+        let texts = ["a b", "c d", "e f", "g h", "i j"];
+        for vertical_align in [
+            VerticalAlign::Top,
+            VerticalAlign::Middle,
+            VerticalAlign::Bottom,
+        ] {
+            assert_eq!(
+                placed(&texts, (5, 3), HorizontalAlign::Left, vertical_align),
+                [
+                    (1, 2, "a b".to_string()),
+                    (2, 2, "c d".to_string()),
+                    (3, 2, "e f".to_string())
+                ]
+            );
+        }
+
+        // The last visible line is not the last line, so it is justified too.
+        let justified = placed(&texts, (5, 3), HorizontalAlign::Justify, VerticalAlign::Top);
+        assert_eq!(justified[2], (3, 2, "e   f".to_string()));
+        assert!(placed(&texts, (5, 0), HorizontalAlign::Left, VerticalAlign::Bottom).is_empty());
+    }
+}
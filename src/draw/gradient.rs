@@ -0,0 +1,198 @@
+use crate::common::{Color, Vec2};
+
+/// Direction a linear gradient runs in, from its first stop to its last.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientDirection {
+    /// Left to right.
+    Horizontal,
+    /// Top to bottom.
+    Vertical,
+    /// Top left to bottom right.
+    Diagonal,
+    /// Bottom left to top right.
+    DiagonalUp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientKind {
+    Linear(GradientDirection),
+    /// From the center of the area out to its corners.
+    Radial,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorStop {
+    /// Offset along the gradient, from 0.0 to 1.0.
+    pub position: f32,
+    pub color: Color,
+}
+
+/// A gradient for box backgrounds and borders, like CSS `linear-gradient` and
+/// `radial-gradient`. Colors are interpolated in Oklab so midpoints keep
+/// their lightness instead of going muddy.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    pub stops: Vec<ColorStop>,
+}
+
+impl Gradient {
+    /// A gradient with `colors` spread evenly from start to end.
+    pub fn new(kind: GradientKind, colors: Vec<Color>) -> Self {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+
+        Gradient {
+            kind,
+            stops: colors
+                .into_iter()
+                .enumerate()
+                .map(|(i, color)| ColorStop {
+                    position: i as f32 / last,
+                    color,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn linear(direction: GradientDirection, colors: Vec<Color>) -> Self {
+        Gradient::new(GradientKind::Linear(direction), colors)
+    }
+
+    pub fn radial(colors: Vec<Color>) -> Self {
+        Gradient::new(GradientKind::Radial, colors)
+    }
+
+    /// A gradient with explicit stops. Stops are sorted by position, and
+    /// ones without a finite position are left out.
+    pub fn with_stops(kind: GradientKind, mut stops: Vec<ColorStop>) -> Self {
+        stops.retain(|stop| stop.position.is_finite());
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        Gradient { kind, stops }
+    }
+
+    /// The color at offset `t` along the gradient, clamped to the first and last stops.
+    pub fn color_at(&self, t: f32) -> Color {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Color::BLACK,
+        };
+
+        if t <= first.position {
+            return first.color.clone();
+        }
+        if t >= last.position {
+            return last.color.clone();
+        }
+
+        // Only an offset that is not a number is past none of the stops.
+        let Some(end) = self.stops.iter().position(|s| s.position >= t) else {
+            return last.color.clone();
+        };
+        let (a, b) = (&self.stops[end - 1], &self.stops[end]);
+
+        let span = b.position - a.position;
+        if span <= f32::EPSILON {
+            return b.color.clone();
+        }
+
+        a.color.mix(&b.color, (t - a.position) / span)
+    }
+
+    /// The color of cell `cell` when the gradient fills an area of `size` cells.
+    pub fn sample(&self, cell: &Vec2, size: &Vec2) -> Color {
+        let fx = fraction(cell.x, size.x);
+        let fy = fraction(cell.y, size.y);
+
+        let t = match self.kind {
+            GradientKind::Linear(GradientDirection::Horizontal) => fx,
+            GradientKind::Linear(GradientDirection::Vertical) => fy,
+            GradientKind::Linear(GradientDirection::Diagonal) => (fx + fy) / 2.0,
+            GradientKind::Linear(GradientDirection::DiagonalUp) => (fx + 1.0 - fy) / 2.0,
+            GradientKind::Radial => {
+                // Cells are about twice as tall as they are wide.
+                let dx = (cell.x as f32 + 0.5) - size.x as f32 / 2.0;
+                let dy = ((cell.y as f32 + 0.5) - size.y as f32 / 2.0) * 2.0;
+                let radius = ((size.x as f32 / 2.0).powi(2) + (size.y as f32).powi(2)).sqrt();
+
+                if radius > 0.0 {
+                    (dx * dx + dy * dy).sqrt() / radius
+                } else {
+                    0.0
+                }
+            }
+        };
+
+        self.color_at(t)
+    }
+
+    /// The color of a border cell at `cell`, running clockwise around the
+    /// perimeter of a box of `size` cells from its top left corner.
+    pub fn sample_perimeter(&self, cell: &Vec2, size: &Vec2) -> Color {
+        let (w, h) = (size.x.saturating_sub(1), size.y.saturating_sub(1));
+        let perimeter = 2 * (w + h);
+
+        let distance = if cell.y == 0 {
+            cell.x
+        } else if cell.x == w {
+            w + cell.y
+        } else if cell.y == h {
+            w + h + (w - cell.x)
+        } else {
+            2 * w + h + (h - cell.y)
+        };
+
+        self.color_at(fraction_of(distance, perimeter))
+    }
+}
+
+fn fraction(index: usize, length: usize) -> f32 {
+    fraction_of(index, length.saturating_sub(1))
+}
+
+fn fraction_of(value: usize, total: usize) -> f32 {
+    if total == 0 {
+        0.0
+    } else {
+        value as f32 / total as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stop(position: f32, color: Color) -> ColorStop {
+        ColorStop { position, color }
+    }
+
+    #[test]
+    fn stops_without_a_finite_position_are_dropped() {
+        let gradient = Gradient::with_stops(
+            GradientKind::Radial,
+            vec![
+                stop(f32::NAN, Color::RED),
+                stop(1.0, Color::WHITE),
+                stop(f32::INFINITY, Color::BLUE),
+                stop(0.0, Color::BLACK),
+            ],
+        );
+
+        let positions = gradient
+            .stops
+            .iter()
+            .map(|s| s.position)
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![0.0, 1.0]);
+        assert_eq!(gradient.color_at(0.5), Color::BLACK.mix(&Color::WHITE, 0.5));
+    }
+
+    #[test]
+    fn color_at_clamps_and_survives_nan() {
+        let gradient = Gradient::radial(vec![Color::BLACK, Color::WHITE]);
+
+        assert_eq!(gradient.color_at(-1.0), Color::BLACK);
+        assert_eq!(gradient.color_at(2.0), Color::WHITE);
+        assert_eq!(gradient.color_at(f32::NAN), Color::WHITE);
+        assert_eq!(Gradient::radial(vec![]).color_at(0.5), Color::BLACK);
+    }
+}
@@ -1,2 +1,8 @@
 pub mod border;
 pub mod boxes;
+pub mod color;
+pub mod content;
+pub mod gradient;
+pub mod shadow;
+pub mod text;
+pub mod theme;
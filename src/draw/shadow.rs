@@ -0,0 +1,314 @@
+use crate::common::{Color, Vec2};
+use crate::draw::boxes::BoxChar;
+
+/// How the cells under a shadow are recolored.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShadowShade {
+    /// Paint the cells with this color.
+    Color(Color),
+    /// Scale the colors of the cells by this factor, from 0.0 (black) to 1.0 (unchanged).
+    Darken(f32),
+}
+
+/// A drop shadow cast below and to the right of a box, roughly like CSS `box-shadow`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shadow {
+    pub offset: Vec2<i16>,
+    pub shade: ShadowShade,
+    /// Draw the outer row and column of the shadow with half blocks, so the
+    /// shadow fades out over half a cell.
+    pub soft: bool,
+}
+
+impl Default for Shadow {
+    fn default() -> Self {
+        Shadow {
+            offset: Vec2::new(2, 1),
+            shade: ShadowShade::Darken(0.4),
+            soft: false,
+        }
+    }
+}
+
+impl Shadow {
+    fn apply(&self, color: &Option<Color>) -> Color {
+        match &self.shade {
+            ShadowShade::Color(color) => color.clone(),
+            ShadowShade::Darken(factor) => {
+                // Cells without a background show the terminal's own, which we
+                // cannot know, so they are treated as black.
+                let color = color.clone().unwrap_or(Color::BLACK);
+                let scale = |c: u8| (c as f32 * factor.clamp(0.0, 1.0)).round() as u8;
+
+                Color {
+                    r: scale(color.r),
+                    g: scale(color.g),
+                    b: scale(color.b),
+                }
+            }
+        }
+    }
+}
+
+/// Shades the cells covered by the shadow of a box at `position` with `size`,
+/// leaving the box itself alone. Must run after the box is drawn.
+pub fn draw_shadow(
+    buffer: &mut [Vec<BoxChar>],
+    position: &Vec2<i16>,
+    size: &Vec2,
+    screen_size: &Vec2,
+    shadow: &Shadow,
+) {
+    let (left, top) = (position.x as isize, position.y as isize);
+    let (right, bottom) = (left + size.x as isize, top + size.y as isize);
+
+    let shadow_left = left + shadow.offset.x as isize;
+    let shadow_top = top + shadow.offset.y as isize;
+    let shadow_right = shadow_left + size.x as isize;
+    let shadow_bottom = shadow_top + size.y as isize;
+
+    for y in shadow_top.max(0)..shadow_bottom.min(buffer.len() as isize) {
+        let line = &mut buffer[y as usize];
+        let width = screen_size.x.min(line.len()) as isize;
+
+        for x in shadow_left.max(0)..shadow_right.min(width) {
+            if (left..right).contains(&x) && (top..bottom).contains(&y) {
+                continue;
+            }
+
+            let cell = &mut line[x as usize];
+            let outer_row = shadow.offset.y > 0 && y == shadow_bottom - 1;
+            let outer_column = shadow.offset.x > 0 && x == shadow_right - 1;
+
+            if shadow.soft && cell.content == " " && (outer_row || outer_column) {
+                // The half block's foreground is the shaded half; the rest of the
+                // cell keeps its own background.
+                cell.content = if outer_row { "▀" } else { "▌" }.to_string();
+                cell.fg = Some(shadow.apply(&cell.bg));
+            } else {
+                if let ShadowShade::Darken(_) = shadow.shade {
+                    cell.fg = cell.fg.as_ref().map(|fg| shadow.apply(&Some(fg.clone())));
+                }
+                cell.bg = Some(shadow.apply(&cell.bg));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHADE: Color = Color { r: 9, g: 9, b: 9 };
+
+    /// A 10 by 6 screen with a box of `#` cells drawn at `position`.
+    fn screen(position: (i16, i16), size: (usize, usize)) -> Vec<Vec<BoxChar>> {
+        let mut buffer = vec![vec![BoxChar::default(); 10]; 6];
+        for (y, line) in buffer.iter_mut().enumerate() {
+            for (x, cell) in line.iter_mut().enumerate() {
+                let (x, y) = (x as i16 - position.0, y as i16 - position.1);
+                if (0..size.0 as i16).contains(&x) && (0..size.1 as i16).contains(&y) {
+                    cell.content = "#".to_string();
+                    cell.bg = Some(Color::WHITE);
+                }
+            }
+        }
+        buffer
+    }
+
+    fn shadow(offset: (i16, i16), soft: bool) -> Shadow {
+        Shadow {
+            offset: Vec2::new(offset.0, offset.1),
+            shade: ShadowShade::Color(SHADE),
+            soft,
+        }
+    }
+
+    /// The screen as text: `#` for the box, `x` for shaded cells and any
+    /// half blocks as they are.
+    fn shown(buffer: &[Vec<BoxChar>]) -> Vec<String> {
+        buffer
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|cell| match (cell.content.as_str(), &cell.bg) {
+                        ("#", Some(Color::WHITE)) => "#",
+                        ("#", _) => "!",
+                        (" ", Some(_)) => "x",
+                        (" ", None) => ".",
+                        (half, _) => half,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn cast(
+        position: (i16, i16),
+        size: (usize, usize),
+        screen_width: usize,
+        shadow: &Shadow,
+    ) -> Vec<String> {
+        let mut buffer = screen(position, size);
+        draw_shadow(
+            &mut buffer,
+            &Vec2::new(position.0, position.1),
+            &Vec2::new(size.0, size.1),
+            &Vec2::new(screen_width, 6),
+            shadow,
+        );
+        shown(&buffer)
+    }
+
+    #[test]
+    fn shades_around_the_box() {
+        assert_eq!(
+            cast((1, 1), (3, 2), 10, &shadow((2, 1), false)),
+            [
+                "..........",
+                ".###......",
+                ".###xx....",
+                "...xxx....",
+                "..........",
+                "..........",
+            ]
+        );
+        // The box's own cells are never shaded, even under a shadow cast up and left.
+        assert_eq!(
+            cast((2, 2), (3, 2), 10, &shadow((-1, -1), false)),
+            [
+                "..........",
+                ".xxx......",
+                ".x###.....",
+                "..###.....",
+                "..........",
+                "..........",
+            ]
+        );
+    }
+
+    #[test]
+    fn clips_at_the_screen_edges() {
+        assert_eq!(
+            cast((7, 4), (3, 2), 10, &shadow((2, 1), false)),
+            [
+                "..........",
+                "..........",
+                "..........",
+                "..........",
+                ".......###",
+                ".......###",
+            ]
+        );
+        // Past the screen's width, even cells still in the buffer are left alone.
+        assert_eq!(
+            cast((4, 0), (3, 1), 8, &shadow((2, 1), false)),
+            [
+                "....###...",
+                "......xx..",
+                "..........",
+                "..........",
+                "..........",
+                "..........",
+            ]
+        );
+    }
+
+    #[test]
+    fn clips_at_negative_positions() {
+        assert_eq!(
+            cast((-3, -1), (4, 3), 10, &shadow((2, 1), false)),
+            [
+                "#xx.......",
+                "#xx.......",
+                "xxx.......",
+                "..........",
+                "..........",
+                "..........",
+            ]
+        );
+        // A shadow entirely off screen draws nothing.
+        assert_eq!(
+            cast((-5, -5), (2, 2), 10, &shadow((2, 1), false)),
+            vec![".........."; 6]
+        );
+    }
+
+    #[test]
+    fn soft_edges() {
+        assert_eq!(
+            cast((1, 1), (3, 2), 10, &shadow((2, 1), true)),
+            [
+                "..........",
+                ".###......",
+                ".###x▌....",
+                "...▀▀▀....",
+                "..........",
+                "..........",
+            ]
+        );
+
+        let mut buffer = screen((1, 1), (3, 2));
+        buffer[3][4].content = "t".to_string();
+        buffer[3][5].bg = Some(Color::WHITE);
+        draw_shadow(
+            &mut buffer,
+            &Vec2::new(1, 1),
+            &Vec2::new(3, 2),
+            &Vec2::new(10, 6),
+            &shadow((2, 1), true),
+        );
+        // Cells with text are shaded whole; half blocks keep the cell's background.
+        assert_eq!(buffer[3][4].content, "t");
+        assert_eq!(buffer[3][4].bg, Some(SHADE));
+        assert_eq!(buffer[3][5].content, "▀");
+        assert_eq!(buffer[3][5].fg, Some(SHADE));
+        assert_eq!(buffer[3][5].bg, Some(Color::WHITE));
+
+        // Without a vertical offset, there is no outer row.
+        assert_eq!(
+            cast((1, 1), (3, 2), 10, &shadow((1, 0), true))[1..3],
+            [".###▌.....", ".###▌....."]
+        );
+    }
+
+    #[test]
+    fn darkens() {
+        let mut buffer = vec![vec![BoxChar::default(); 4]; 2];
+        buffer[1][2].bg = Some(Color {
+            r: 100,
+            g: 200,
+            b: 50,
+        });
+        buffer[1][2].fg = Some(Color {
+            r: 10,
+            g: 20,
+            b: 31,
+        });
+        let shadow = Shadow {
+            offset: Vec2::new(2, 1),
+            shade: ShadowShade::Darken(0.5),
+            soft: false,
+        };
+        draw_shadow(
+            &mut buffer,
+            &Vec2::new(0, 0),
+            &Vec2::new(2, 1),
+            &Vec2::new(4, 2),
+            &shadow,
+        );
+
+        assert_eq!(
+            buffer[1][2].bg,
+            Some(Color {
+                r: 50,
+                g: 100,
+                b: 25
+            })
+        );
+        assert_eq!(buffer[1][2].fg, Some(Color { r: 5, g: 10, b: 16 }));
+        // Cells without a background are taken to be black.
+        assert_eq!(buffer[1][3].bg, Some(Color::BLACK));
+        assert_eq!(buffer[1][3].fg, None);
+    }
+}
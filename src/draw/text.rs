@@ -0,0 +1,339 @@
+use std::fmt;
+
+use bitflags::bitflags;
+use unicode_width::UnicodeWidthChar;
+
+use crate::common::Color;
+use crate::draw::theme::Role;
+
+bitflags! {
+    #[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+    pub struct TextAttributes: u8 {
+        const NONE = 0b0000_0000;
+        const BOLD = 0b0000_0001;
+        const DIM = 0b0000_0010;
+        const ITALIC = 0b0000_0100;
+        const UNDERLINE = 0b0000_1000;
+        const REVERSE = 0b0001_0000;
+        const STRIKETHROUGH = 0b0010_0000;
+    }
+}
+
+impl TextAttributes {
+    /// The SGR escape sequence that turns these attributes on.
+    pub fn sgr(&self) -> String {
+        let codes = [
+            (TextAttributes::BOLD, "1"),
+            (TextAttributes::DIM, "2"),
+            (TextAttributes::ITALIC, "3"),
+            (TextAttributes::UNDERLINE, "4"),
+            (TextAttributes::REVERSE, "7"),
+            (TextAttributes::STRIKETHROUGH, "9"),
+        ]
+        .iter()
+        .filter(|(flag, _)| self.contains(*flag))
+        .map(|(_, code)| *code)
+        .collect::<Vec<_>>();
+
+        if codes.is_empty() {
+            String::new()
+        } else {
+            format!("\x1b[{}m", codes.join(";"))
+        }
+    }
+}
+
+/// Styling of a run of text. Unset colors fall back to the box's `text_color`
+/// and background.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TextStyle {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    /// Colors from the theme, which take precedence over `fg` and `bg`. Text
+    /// with only a background role is written in a color readable on it.
+    pub role: Option<Role>,
+    pub background_role: Option<Role>,
+    pub attributes: TextAttributes,
+    /// Target of a hyperlink, emitted as an OSC 8 link where the terminal supports it.
+    pub link: Option<String>,
+    /// Identifies an interactive element the text belongs to, such as a form
+    /// control, so clicks on it can be traced back.
+    pub widget: Option<usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: TextStyle,
+}
+
+impl Span {
+    pub fn new(text: impl Into<String>, style: TextStyle) -> Self {
+        Span {
+            text: text.into(),
+            style,
+        }
+    }
+
+    pub fn plain(text: impl Into<String>) -> Self {
+        Span::new(text, TextStyle::default())
+    }
+}
+
+/// `c`, or U+FFFD in place of a control character, which the terminal
+/// would act on instead of showing.
+pub fn printable(c: char) -> char {
+    match c.is_control() {
+        true => '\u{fffd}',
+        false => c,
+    }
+}
+
+/// Cells `c` takes up on screen once made printable: two for wide
+/// characters such as CJK, none for combining marks.
+pub fn char_width(c: char) -> usize {
+    printable(c).width().unwrap_or(0)
+}
+
+/// One line of box content made of differently styled spans, such as a
+/// paragraph with inline `<a>`, `<code>` or `<strong>` elements.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StyledLine {
+    pub spans: Vec<Span>,
+}
+
+impl StyledLine {
+    pub fn new(spans: Vec<Span>) -> Self {
+        StyledLine { spans }
+    }
+
+    /// Width of the line in cells.
+    pub fn width(&self) -> usize {
+        self.spans
+            .iter()
+            .flat_map(|span| span.text.chars())
+            .map(char_width)
+            .sum()
+    }
+
+    /// Each character of the line with its style.
+    pub fn chars(&self) -> impl Iterator<Item = (char, &TextStyle)> {
+        self.spans
+            .iter()
+            .flat_map(|span| span.text.chars().map(move |c| (c, &span.style)))
+    }
+
+    /// Builds a line from styled characters, merging neighbours with the same style.
+    pub fn from_chars<'a>(chars: impl IntoIterator<Item = (char, &'a TextStyle)>) -> Self {
+        let mut spans: Vec<Span> = Vec::new();
+
+        for (c, style) in chars {
+            match spans.last_mut() {
+                Some(span) if &span.style == style => span.text.push(c),
+                _ => spans.push(Span::new(c, style.clone())),
+            }
+        }
+
+        StyledLine { spans }
+    }
+
+    /// The line with its control characters replaced, so text from a page
+    /// cannot write escape sequences of its own.
+    pub fn to_printable(&self) -> StyledLine {
+        StyledLine {
+            spans: self
+                .spans
+                .iter()
+                .map(|span| {
+                    Span::new(
+                        span.text.chars().map(printable).collect::<String>(),
+                        span.style.clone(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Splits the line into lines of at most `width` cells, breaking at spaces
+    /// where possible. An empty line stays a single empty line.
+    pub fn wrap(&self, width: usize) -> Vec<StyledLine> {
+        let width = width.max(1);
+        let chars = self.chars().collect::<Vec<_>>();
+        let mut lines = Vec::new();
+
+        let mut start = 0;
+        loop {
+            // The characters that fit from `start`, and always at least one.
+            let (mut end, mut used) = (start, 0);
+            while end < chars.len() && (end == start || used + char_width(chars[end].0) <= width) {
+                used += char_width(chars[end].0);
+                end += 1;
+            }
+            if end == chars.len() {
+                break;
+            }
+
+            // A space right after the characters that fit is a break too.
+            let window = &chars[start..=end];
+            match window.iter().rposition(|(c, _)| *c == ' ') {
+                Some(space) if space > 0 => {
+                    lines.push(StyledLine::from_chars(window[..space].iter().copied()));
+                    start += space + 1;
+                }
+                _ => {
+                    lines.push(StyledLine::from_chars(chars[start..end].iter().copied()));
+                    start = end;
+                }
+            }
+        }
+        lines.push(StyledLine::from_chars(chars[start..].iter().copied()));
+
+        lines
+    }
+}
+
+impl fmt::Display for StyledLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for span in &self.spans {
+            write!(f, "{}", span.text)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<String> for StyledLine {
+    fn from(text: String) -> Self {
+        StyledLine::new(vec![Span::plain(text)])
+    }
+}
+
+impl From<&str> for StyledLine {
+    fn from(text: &str) -> Self {
+        StyledLine::from(text.to_string())
+    }
+}
+
+impl From<Span> for StyledLine {
+    fn from(span: Span) -> Self {
+        StyledLine::new(vec![span])
+    }
+}
+
+impl From<Vec<Span>> for StyledLine {
+    fn from(spans: Vec<Span>) -> Self {
+        StyledLine::new(spans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bold() -> TextStyle {
+        TextStyle {
+            attributes: TextAttributes::BOLD,
+            ..TextStyle::default()
+        }
+    }
+
+    fn wrapped(text: &str, width: usize) -> Vec<String> {
+        StyledLine::from(text)
+            .wrap(width)
+            .iter()
+            .map(StyledLine::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn measures_cells() {
+        assert_eq!(StyledLine::from("hello").width(), 5);
+        assert_eq!(StyledLine::from("日本語").width(), 6);
+        assert_eq!(StyledLine::from("e\u{301}").width(), 1);
+        assert_eq!(StyledLine::from("a☕b").width(), 4);
+        // Control characters are drawn as U+FFFD.
+        assert_eq!(StyledLine::from("a\u{1b}b").width(), 3);
+        let spans = StyledLine::new(vec![Span::plain("ab"), Span::new("漢字", bold())]);
+        assert_eq!(spans.width(), 6);
+        assert_eq!(StyledLine::default().width(), 0);
+    }
+
+    #[test]
+    fn wraps_at_spaces() {
+        assert_eq!(
+            wrapped("the quick brown fox", 10),
+            ["the quick", "brown fox"]
+        );
+        // A space just past the edge is a break as well.
+        assert_eq!(wrapped("abc def", 3), ["abc", "def"]);
+        assert_eq!(wrapped("short", 10), ["short"]);
+        assert_eq!(wrapped("", 10), [""]);
+        assert_eq!(wrapped("  ", 1), [" ", ""]);
+    }
+
+    #[test]
+    fn breaks_long_words() {
+        assert_eq!(wrapped("abcdefgh", 3), ["abc", "def", "gh"]);
+        assert_eq!(wrapped("ab", 0), ["a", "b"]);
+        assert_eq!(wrapped("a verylongword", 5), ["a", "veryl", "ongwo", "rd"]);
+    }
+
+    #[test]
+    fn wraps_by_cells() {
+        assert_eq!(wrapped("日本語テキスト", 5), ["日本", "語テ", "キス", "ト"]);
+        assert_eq!(wrapped("日本 語", 4), ["日本", "語"]);
+        // Combining marks stay with their letter.
+        assert_eq!(
+            wrapped("e\u{301}e\u{301}e\u{301}", 2),
+            ["e\u{301}e\u{301}", "e\u{301}"]
+        );
+        // A wide character gets a line of its own where it cannot fit.
+        assert_eq!(wrapped("a日b", 1), ["a", "日", "b"]);
+    }
+
+    #[test]
+    fn wrapping_keeps_styles() {
+        let line = StyledLine::new(vec![Span::plain("one "), Span::new("two three", bold())]);
+        assert_eq!(
+            line.wrap(7),
+            [
+                StyledLine::new(vec![Span::plain("one "), Span::new("two", bold())]),
+                StyledLine::new(vec![Span::new("three", bold())]),
+            ]
+        );
+    }
+
+    #[test]
+    fn builds_from_chars() {
+        let plain = TextStyle::default();
+        let bold = bold();
+        let line =
+            StyledLine::from_chars([('a', &plain), ('b', &plain), ('c', &bold), ('d', &plain)]);
+        assert_eq!(
+            line,
+            StyledLine::new(vec![
+                Span::plain("ab"),
+                Span::new("c", bold.clone()),
+                Span::plain("d")
+            ])
+        );
+        assert_eq!(StyledLine::from_chars(line.chars()), line);
+        assert_eq!(StyledLine::from_chars([]), StyledLine::default());
+    }
+
+    #[test]
+    fn makes_lines_printable() {
+        let line = StyledLine::new(vec![
+            Span::plain("a\u{1b}[31m"),
+            Span::new("b\tc\u{7f}é", bold()),
+        ]);
+        assert_eq!(
+            line.to_printable(),
+            StyledLine::new(vec![
+                Span::plain("a\u{fffd}[31m"),
+                Span::new("b\u{fffd}c\u{fffd}é", bold()),
+            ])
+        );
+        assert_eq!(printable('日'), '日');
+        assert_eq!(printable('\n'), '\u{fffd}');
+    }
+}
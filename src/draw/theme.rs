@@ -0,0 +1,396 @@
+use crate::common::Color;
+use crate::draw::text::TextStyle;
+
+/// What a color is used for. Boxes and text given a role rather than a color
+/// take theirs from the theme, and change with it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Background,
+    Text,
+    Link,
+    /// Links to pages in the history.
+    VisitedLink,
+    Border,
+    /// What stands out: the active tab, the current match and code.
+    Focus,
+    /// What has been picked or found, such as matches and hint labels.
+    Selection,
+    Error,
+}
+
+impl Role {
+    pub const ALL: [Role; 8] = [
+        Role::Background,
+        Role::Text,
+        Role::Link,
+        Role::VisitedLink,
+        Role::Border,
+        Role::Focus,
+        Role::Selection,
+        Role::Error,
+    ];
+
+    /// How the role is named in the config file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Role::Background => "background",
+            Role::Text => "text",
+            Role::Link => "link",
+            Role::VisitedLink => "visited_link",
+            Role::Border => "border",
+            Role::Focus => "focus",
+            Role::Selection => "selection",
+            Role::Error => "error",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Role> {
+        Role::ALL.into_iter().find(|role| role.name() == name)
+    }
+}
+
+/// A color for every role.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub background: Color,
+    pub text: Color,
+    pub link: Color,
+    pub visited_link: Color,
+    pub border: Color,
+    pub focus: Color,
+    pub selection: Color,
+    pub error: Color,
+}
+
+/// The names of the built-in themes, in the order they are switched through.
+pub const THEMES: &[&str] = &["dark", "light", "solarized", "high-contrast"];
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Theme {
+        Theme {
+            name: "dark".to_string(),
+            background: Color::BLACK,
+            text: Color {
+                r: 229,
+                g: 229,
+                b: 229,
+            },
+            link: Color::CYAN,
+            visited_link: Color {
+                r: 200,
+                g: 130,
+                b: 255,
+            },
+            border: Color {
+                r: 96,
+                g: 96,
+                b: 96,
+            },
+            focus: Color::ORANGE,
+            selection: Color::YELLOW,
+            error: Color::RED,
+        }
+    }
+
+    pub fn light() -> Theme {
+        Theme {
+            name: "light".to_string(),
+            background: Color {
+                r: 250,
+                g: 250,
+                b: 250,
+            },
+            text: Color {
+                r: 30,
+                g: 30,
+                b: 30,
+            },
+            link: Color {
+                r: 0,
+                g: 90,
+                b: 200,
+            },
+            visited_link: Color {
+                r: 120,
+                g: 60,
+                b: 170,
+            },
+            border: Color {
+                r: 170,
+                g: 170,
+                b: 170,
+            },
+            focus: Color {
+                r: 200,
+                g: 80,
+                b: 0,
+            },
+            selection: Color {
+                r: 255,
+                g: 220,
+                b: 80,
+            },
+            error: Color { r: 200, g: 0, b: 0 },
+        }
+    }
+
+    /// Ethan Schoonover's Solarized, in its dark variant.
+    pub fn solarized() -> Theme {
+        Theme {
+            name: "solarized".to_string(),
+            background: Color { r: 0, g: 43, b: 54 },
+            text: Color {
+                r: 131,
+                g: 148,
+                b: 150,
+            },
+            link: Color {
+                r: 38,
+                g: 139,
+                b: 210,
+            },
+            visited_link: Color {
+                r: 108,
+                g: 113,
+                b: 196,
+            },
+            border: Color {
+                r: 88,
+                g: 110,
+                b: 117,
+            },
+            focus: Color {
+                r: 203,
+                g: 75,
+                b: 22,
+            },
+            selection: Color {
+                r: 181,
+                g: 137,
+                b: 0,
+            },
+            error: Color {
+                r: 220,
+                g: 50,
+                b: 47,
+            },
+        }
+    }
+
+    /// Pure colors on black, for reading in glare or with low vision.
+    pub fn high_contrast() -> Theme {
+        Theme {
+            name: "high-contrast".to_string(),
+            background: Color::BLACK,
+            text: Color::WHITE,
+            link: Color::YELLOW,
+            visited_link: Color {
+                r: 255,
+                g: 160,
+                b: 255,
+            },
+            border: Color::WHITE,
+            focus: Color::CYAN,
+            selection: Color::GREEN,
+            error: Color {
+                r: 255,
+                g: 80,
+                b: 80,
+            },
+        }
+    }
+
+    /// The built-in theme called `name`.
+    pub fn built_in(name: &str) -> Option<Theme> {
+        match name {
+            "dark" => Some(Theme::dark()),
+            "light" => Some(Theme::light()),
+            "solarized" => Some(Theme::solarized()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            _ => None,
+        }
+    }
+
+    pub fn color(&self, role: Role) -> &Color {
+        match role {
+            Role::Background => &self.background,
+            Role::Text => &self.text,
+            Role::Link => &self.link,
+            Role::VisitedLink => &self.visited_link,
+            Role::Border => &self.border,
+            Role::Focus => &self.focus,
+            Role::Selection => &self.selection,
+            Role::Error => &self.error,
+        }
+    }
+
+    pub fn color_mut(&mut self, role: Role) -> &mut Color {
+        match role {
+            Role::Background => &mut self.background,
+            Role::Text => &mut self.text,
+            Role::Link => &mut self.link,
+            Role::VisitedLink => &mut self.visited_link,
+            Role::Border => &mut self.border,
+            Role::Focus => &mut self.focus,
+            Role::Selection => &mut self.selection,
+            Role::Error => &mut self.error,
+        }
+    }
+
+    /// The color to write text in over the color of `role`: the theme's text
+    /// or background color, whichever contrasts more with it.
+    pub fn text_on(&self, role: Role) -> &Color {
+        let under = self.color(role);
+        match self.text.contrast_ratio(under) >= self.background.contrast_ratio(under) {
+            true => &self.text,
+            false => &self.background,
+        }
+    }
+
+    /// `style` with the colors of its roles filled in. Text with only a
+    /// background role is written in whichever color is readable on it.
+    pub fn resolve(&self, style: &TextStyle) -> TextStyle {
+        let fg = match (style.role, &style.fg, style.background_role) {
+            (Some(role), _, _) => Some(self.color(role).clone()),
+            (None, Some(fg), _) => Some(fg.clone()),
+            (None, None, Some(under)) => Some(self.text_on(under).clone()),
+            (None, None, None) => None,
+        };
+        let bg = match style.background_role {
+            Some(role) => Some(self.color(role).clone()),
+            None => style.bg.clone(),
+        };
+
+        TextStyle {
+            fg,
+            bg,
+            role: None,
+            background_role: None,
+            ..style.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::draw::text::TextAttributes;
+
+    const PAGE: Color = Color { r: 1, g: 2, b: 3 };
+
+    #[test]
+    fn names_roles() {
+        for role in Role::ALL {
+            assert_eq!(Role::from_name(role.name()), Some(role));
+        }
+        assert_eq!(Role::from_name("visited_link"), Some(Role::VisitedLink));
+        assert_eq!(Role::from_name("Text"), None);
+
+        for name in THEMES {
+            assert_eq!(Theme::built_in(name).unwrap().name, *name);
+        }
+        assert_eq!(Theme::built_in("sepia"), None);
+    }
+
+    #[test]
+    fn colors_of_roles() {
+        let mut theme = Theme::dark();
+        assert_eq!(theme.color(Role::Link), &Color::CYAN);
+        assert_eq!(theme.color(Role::Error), &Color::RED);
+
+        *theme.color_mut(Role::Border) = PAGE;
+        assert_eq!(theme.border, PAGE);
+        assert_eq!(theme.color(Role::Border), &PAGE);
+    }
+
+    #[test]
+    fn readable_text_on_roles() {
+        let dark = Theme::dark();
+        assert_eq!(dark.text_on(Role::Background), &dark.text);
+        // Light text is hard to read on yellow, so the background is used.
+        assert_eq!(dark.text_on(Role::Selection), &dark.background);
+        assert_eq!(dark.text_on(Role::Border), &dark.text);
+
+        let light = Theme::light();
+        assert_eq!(light.text_on(Role::Background), &light.text);
+        assert_eq!(light.text_on(Role::Selection), &light.text);
+        assert_eq!(light.text_on(Role::Text), &light.background);
+
+        let high_contrast = Theme::high_contrast();
+        assert_eq!(
+            high_contrast.text_on(Role::Selection),
+            &high_contrast.background
+        );
+    }
+
+    #[test]
+    fn resolves_roles() {
+        let theme = Theme::dark();
+        let bold = TextStyle {
+            attributes: TextAttributes::BOLD,
+            link: Some("http://example.com/".to_string()),
+            ..TextStyle::default()
+        };
+        let resolve = |style: TextStyle| theme.resolve(&style);
+
+        // Roles win over the page's own colors, and are cleared once resolved.
+        assert_eq!(
+            resolve(TextStyle {
+                fg: Some(PAGE),
+                bg: Some(PAGE),
+                role: Some(Role::Link),
+                background_role: Some(Role::Focus),
+                ..bold.clone()
+            }),
+            TextStyle {
+                fg: Some(Color::CYAN),
+                bg: Some(Color::ORANGE),
+                ..bold.clone()
+            }
+        );
+        assert_eq!(
+            resolve(TextStyle {
+                fg: Some(PAGE),
+                bg: Some(PAGE),
+                ..bold.clone()
+            }),
+            TextStyle {
+                fg: Some(PAGE),
+                bg: Some(PAGE),
+                ..bold.clone()
+            }
+        );
+        assert_eq!(resolve(bold.clone()), bold);
+    }
+
+    #[test]
+    fn text_with_only_a_background_role_stays_readable() {
+        let theme = Theme::dark();
+        let on = |role| {
+            theme.resolve(&TextStyle {
+                background_role: Some(role),
+                ..TextStyle::default()
+            })
+        };
+
+        assert_eq!(on(Role::Selection).fg, Some(theme.background.clone()));
+        assert_eq!(on(Role::Selection).bg, Some(Color::YELLOW));
+        assert_eq!(on(Role::Border).fg, Some(theme.text.clone()));
+        assert_eq!(on(Role::Border).bg, Some(theme.border.clone()));
+
+        // A color of the page's own is kept, readable or not.
+        let own = theme.resolve(&TextStyle {
+            fg: Some(Color::YELLOW),
+            background_role: Some(Role::Selection),
+            ..TextStyle::default()
+        });
+        assert_eq!(own.fg, Some(Color::YELLOW));
+    }
+}
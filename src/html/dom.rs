@@ -0,0 +1,95 @@
+/// What a node in the document tree is.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeKind {
+    Document,
+    Element {
+        /// Lowercase tag name.
+        name: String,
+        /// Attributes in source order, with lowercase names.
+        attributes: Vec<(String, String)>,
+    },
+    Text(String),
+    Comment(String),
+}
+
+/// A node of a parsed HTML document, owning its children.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn document() -> Self {
+        Node {
+            kind: NodeKind::Document,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn element(name: impl Into<String>, attributes: Vec<(String, String)>) -> Self {
+        Node {
+            kind: NodeKind::Element {
+                name: name.into(),
+                attributes,
+            },
+            children: Vec::new(),
+        }
+    }
+
+    pub fn text(text: impl Into<String>) -> Self {
+        Node {
+            kind: NodeKind::Text(text.into()),
+            children: Vec::new(),
+        }
+    }
+
+    /// The tag name, if this is an element.
+    pub fn name(&self) -> Option<&str> {
+        match &self.kind {
+            NodeKind::Element { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    pub fn is(&self, name: &str) -> bool {
+        self.name() == Some(name)
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        match &self.kind {
+            NodeKind::Element { attributes, .. } => attributes
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str()),
+            _ => None,
+        }
+    }
+
+    /// All text inside this node, like the DOM `textContent`.
+    pub fn text_content(&self) -> String {
+        let mut text = String::new();
+        for node in self.descendants() {
+            if let NodeKind::Text(t) = &node.kind {
+                text.push_str(t);
+            }
+        }
+        text
+    }
+
+    /// This node and everything under it, in document order.
+    pub fn descendants(&self) -> Vec<&Node> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            nodes.push(node);
+            stack.extend(node.children.iter().rev());
+        }
+        nodes
+    }
+
+    /// The first element called `name` under this node.
+    pub fn find(&self, name: &str) -> Option<&Node> {
+        self.descendants().into_iter().find(|node| node.is(name))
+    }
+}
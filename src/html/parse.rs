@@ -0,0 +1,521 @@
+use crate::html::dom::{Node, NodeKind};
+
+/// Elements that never have children or an end tag.
+const VOID: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose contents are text up to their end tag, never markup.
+const RAW_TEXT: &[&str] = &["script", "style", "textarea", "title"];
+
+/// Elements that end an open `<p>` when they start.
+const CLOSES_P: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "details",
+    "div",
+    "dl",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+const ENTITIES: &[(&str, &str)] = &[
+    ("amp", "&"),
+    ("lt", "<"),
+    ("gt", ">"),
+    ("quot", "\""),
+    ("apos", "'"),
+    ("nbsp", "\u{a0}"),
+    ("shy", "\u{ad}"),
+    ("copy", "©"),
+    ("reg", "®"),
+    ("trade", "™"),
+    ("hellip", "…"),
+    ("mdash", "—"),
+    ("ndash", "–"),
+    ("lsquo", "‘"),
+    ("rsquo", "’"),
+    ("ldquo", "“"),
+    ("rdquo", "”"),
+    ("laquo", "«"),
+    ("raquo", "»"),
+    ("bull", "•"),
+    ("middot", "·"),
+    ("deg", "°"),
+    ("times", "×"),
+    ("divide", "÷"),
+    ("plusmn", "±"),
+    ("sect", "§"),
+    ("para", "¶"),
+    ("euro", "€"),
+    ("pound", "£"),
+    ("yen", "¥"),
+    ("cent", "¢"),
+    ("frac12", "½"),
+    ("frac14", "¼"),
+    ("frac34", "¾"),
+    ("larr", "←"),
+    ("rarr", "→"),
+    ("uarr", "↑"),
+    ("darr", "↓"),
+    ("hearts", "♥"),
+];
+
+/// Replaces character references such as `&amp;` and `&#x41;` in `text`.
+/// Unknown references are kept as written.
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let reference = rest[1..]
+            .find(';')
+            .filter(|&end| end > 0 && end <= 10)
+            .map(|end| &rest[1..end + 1]);
+
+        let replacement = reference.and_then(|name| {
+            let code = match name.strip_prefix('#') {
+                Some(hex) if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok(),
+                Some(decimal) => decimal.parse::<u32>().ok(),
+                None => {
+                    return ENTITIES
+                        .iter()
+                        .find(|(entity, _)| *entity == name)
+                        .map(|(_, value)| value.to_string());
+                }
+            };
+            // References to control characters other than whitespace stand
+            // for nothing that can be shown.
+            code.map(|code| match char::from_u32(code) {
+                Some(c) if !c.is_control() || matches!(c, '\t' | '\n' | '\r' | '\x0c') => {
+                    c.to_string()
+                }
+                _ => '\u{fffd}'.to_string(),
+            })
+        });
+
+        match (reference, replacement) {
+            (Some(name), Some(replacement)) => {
+                decoded.push_str(&replacement);
+                rest = &rest[name.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+/// A start tag, as read from the source.
+struct Tag {
+    name: String,
+    attributes: Vec<(String, String)>,
+    self_closing: bool,
+}
+
+/// Reads a start or end tag from just after its `<` or `</`. Returns the tag
+/// and the number of bytes it took, including the closing `>`.
+fn read_tag(source: &str) -> (Tag, usize) {
+    let chars = source.char_indices().collect::<Vec<_>>();
+    let mut i = 0;
+    let at = |i: usize| chars.get(i).map(|(_, c)| *c);
+    let offset = |i: usize| chars.get(i).map_or(source.len(), |(offset, _)| *offset);
+
+    let mut name = String::new();
+    while let Some(c) = at(i)
+        && !c.is_whitespace()
+        && c != '>'
+        && c != '/'
+    {
+        name.push(c.to_ascii_lowercase());
+        i += 1;
+    }
+
+    let mut attributes: Vec<(String, String)> = Vec::new();
+    let mut self_closing = false;
+
+    loop {
+        while at(i).is_some_and(char::is_whitespace) {
+            i += 1;
+        }
+
+        match at(i) {
+            None => break,
+            Some('>') => {
+                i += 1;
+                break;
+            }
+            Some('/') => {
+                self_closing = at(i + 1) == Some('>');
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        let mut key = String::new();
+        while let Some(c) = at(i)
+            && !c.is_whitespace()
+            && !"=>".contains(c)
+            && !(c == '/' && at(i + 1) == Some('>'))
+        {
+            key.push(c.to_ascii_lowercase());
+            i += 1;
+        }
+
+        while at(i).is_some_and(char::is_whitespace) {
+            i += 1;
+        }
+
+        let mut value = String::new();
+        if at(i) == Some('=') {
+            i += 1;
+            while at(i).is_some_and(char::is_whitespace) {
+                i += 1;
+            }
+
+            match at(i) {
+                Some(quote @ ('"' | '\'')) => {
+                    i += 1;
+                    let start = offset(i);
+                    while at(i).is_some_and(|c| c != quote) {
+                        i += 1;
+                    }
+                    value = decode_entities(&source[start..offset(i)]);
+                    i += 1;
+                }
+                _ => {
+                    let start = offset(i);
+                    while at(i).is_some_and(|c| !c.is_whitespace() && c != '>') {
+                        i += 1;
+                    }
+                    value = decode_entities(&source[start..offset(i)]);
+                }
+            }
+        }
+
+        // The first of duplicated attributes wins.
+        if !key.is_empty() && !attributes.iter().any(|(k, _)| *k == key) {
+            attributes.push((key, value));
+        }
+    }
+
+    let tag = Tag {
+        name,
+        attributes,
+        self_closing,
+    };
+    (tag, offset(i).min(source.len()))
+}
+
+/// Builds the tree from tags and text, closing elements the way browsers do
+/// when authors leave end tags out.
+struct TreeBuilder {
+    /// Open elements, with the document at the bottom.
+    stack: Vec<Node>,
+}
+
+impl TreeBuilder {
+    fn append(&mut self, node: Node) {
+        self.stack.last_mut().unwrap().children.push(node);
+    }
+
+    fn text(&mut self, text: String) {
+        if text.is_empty() {
+            return;
+        }
+
+        let parent = self.stack.last_mut().unwrap();
+        if let Some(Node {
+            kind: NodeKind::Text(previous),
+            ..
+        }) = parent.children.last_mut()
+        {
+            previous.push_str(&text);
+        } else {
+            parent.children.push(Node::text(text));
+        }
+    }
+
+    fn pop(&mut self) {
+        if self.stack.len() > 1 {
+            let node = self.stack.pop().unwrap();
+            self.append(node);
+        }
+    }
+
+    /// Whether `name` is open, without looking past any of `boundaries`.
+    fn is_open(&self, name: &str, boundaries: &[&str]) -> bool {
+        for node in self.stack.iter().rev() {
+            if node.is(name) {
+                return true;
+            }
+            if node.name().is_some_and(|n| boundaries.contains(&n)) {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// Closes every element up to and including the innermost `name`.
+    fn close(&mut self, name: &str) {
+        if !self.is_open(name, &[]) {
+            return;
+        }
+
+        while self.stack.len() > 1 {
+            let done = self.stack.last().unwrap().is(name);
+            self.pop();
+            if done {
+                break;
+            }
+        }
+    }
+
+    fn close_if_open(&mut self, names: &[&str], boundaries: &[&str]) {
+        for name in names {
+            if self.is_open(name, boundaries) {
+                self.close(name);
+            }
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        let name = tag.name.as_str();
+
+        if CLOSES_P.contains(&name) {
+            self.close_if_open(&["p"], &["button", "table", "td", "th"]);
+        }
+
+        match name {
+            "li" => self.close_if_open(&["li"], &["ul", "ol"]),
+            "dt" | "dd" => self.close_if_open(&["dt", "dd"], &["dl"]),
+            "tr" => self.close_if_open(&["tr"], &["table"]),
+            "td" | "th" => self.close_if_open(&["td", "th"], &["tr", "table"]),
+            "option" => self.close_if_open(&["option"], &["select"]),
+            _ => {}
+        }
+
+        let void = VOID.contains(&name) || tag.self_closing;
+        self.stack.push(Node::element(tag.name, tag.attributes));
+        if void {
+            self.pop();
+        }
+    }
+
+    fn end(&mut self, name: &str) {
+        match name {
+            // `</br>` is treated as `<br>`, as browsers do.
+            "br" => self.append(Node::element("br", Vec::new())),
+            name => self.close(name),
+        }
+    }
+
+    fn finish(mut self) -> Node {
+        while self.stack.len() > 1 {
+            self.pop();
+        }
+        self.stack.pop().unwrap()
+    }
+}
+
+/// Parses an HTML document. Parsing never fails; malformed markup is
+/// repaired or kept as text.
+pub fn parse(source: &str) -> Node {
+    let mut builder = TreeBuilder {
+        stack: vec![Node::document()],
+    };
+    let mut rest = source;
+
+    while let Some(start) = rest.find('<') {
+        builder.text(decode_entities(&rest[..start]));
+        rest = &rest[start..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").unwrap_or(comment.len());
+            builder.append(Node {
+                kind: NodeKind::Comment(comment[..end].to_string()),
+                children: Vec::new(),
+            });
+            rest = &comment[(end + 3).min(comment.len())..];
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            // Doctypes and processing instructions are skipped.
+            rest = &rest[rest.find('>').map_or(rest.len(), |end| end + 1)..];
+        } else if let Some(end_tag) = rest.strip_prefix("</")
+            && end_tag.starts_with(|c: char| c.is_ascii_alphabetic())
+        {
+            let (tag, length) = read_tag(end_tag);
+            builder.end(&tag.name);
+            rest = &end_tag[length..];
+        } else if rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            let (tag, length) = read_tag(&rest[1..]);
+            rest = &rest[1 + length..];
+
+            let raw = RAW_TEXT.contains(&tag.name.as_str()) && !tag.self_closing;
+            let name = tag.name.clone();
+            builder.start(tag);
+
+            if raw {
+                let closing = format!("</{}", name);
+                let end = rest
+                    .to_ascii_lowercase()
+                    .find(&closing)
+                    .unwrap_or(rest.len());
+                let text = &rest[..end];
+
+                if name == "title" || name == "textarea" {
+                    builder.text(decode_entities(text));
+                } else {
+                    builder.text(text.to_string());
+                }
+                builder.close(&name);
+
+                rest = &rest[end..];
+                rest = &rest[rest.find('>').map_or(rest.len(), |end| end + 1)..];
+            }
+        } else {
+            builder.text("<".to_string());
+            rest = &rest[1..];
+        }
+    }
+
+    builder.text(decode_entities(rest));
+    builder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The tree written back as markup, without attributes, to compare its shape.
+    fn outline(node: &Node) -> String {
+        let children = node.children.iter().map(outline).collect::<String>();
+        match &node.kind {
+            NodeKind::Document => children,
+            NodeKind::Element { name, .. } if VOID.contains(&name.as_str()) => {
+                format!("<{}>", name)
+            }
+            NodeKind::Element { name, .. } => format!("<{}>{}</{}>", name, children, name),
+            NodeKind::Text(text) => text.clone(),
+            NodeKind::Comment(text) => format!("<!--{}-->", text),
+        }
+    }
+
+    #[test]
+    fn decodes_character_references() {
+        assert_eq!(decode_entities("a &amp; b &lt;c&gt;"), "a & b <c>");
+        assert_eq!(decode_entities("&#65;&#x42;&#X43;"), "ABC");
+        assert_eq!(decode_entities("&copy; &hellip;"), "© …");
+        assert_eq!(decode_entities("&unknown; &amp"), "&unknown; &amp");
+        assert_eq!(decode_entities("a&b; & c"), "a&b; & c");
+        assert_eq!(decode_entities("&#xD800;&#1114112;"), "\u{fffd}\u{fffd}");
+    }
+
+    #[test]
+    fn references_to_control_characters_are_replaced() {
+        assert_eq!(
+            decode_entities("&#27;]52;c;aGk=&#7;"),
+            "\u{fffd}]52;c;aGk=\u{fffd}"
+        );
+        assert_eq!(
+            decode_entities("&#x9b;&#0;&#127;"),
+            "\u{fffd}\u{fffd}\u{fffd}"
+        );
+        assert_eq!(decode_entities("&#9;&#10;&#13;"), "\t\n\r");
+    }
+
+    #[test]
+    fn builds_nested_elements() {
+        let tree = parse("<!DOCTYPE html><p>Hello <b>bold <i>both</i></b>!</p><!-- note -->");
+        assert_eq!(
+            outline(&tree),
+            "<p>Hello <b>bold <i>both</i></b>!</p><!-- note -->"
+        );
+    }
+
+    #[test]
+    fn reads_attributes() {
+        let tree = parse(
+            "<a HREF=\"/x?a=1&amp;b=2\" title='it&apos;s' data-n=3 hidden href=\"/ignored\">x</a>",
+        );
+        let a = tree.find("a").unwrap();
+        assert_eq!(a.attr("href"), Some("/x?a=1&b=2"));
+        assert_eq!(a.attr("title"), Some("it's"));
+        assert_eq!(a.attr("data-n"), Some("3"));
+        assert_eq!(a.attr("hidden"), Some(""));
+    }
+
+    #[test]
+    fn closes_elements_left_open() {
+        assert_eq!(
+            outline(&parse("<p>one<p>two<div>three</div>")),
+            "<p>one</p><p>two</p><div>three</div>"
+        );
+        assert_eq!(
+            outline(&parse("<ul><li>a<li>b</ul>")),
+            "<ul><li>a</li><li>b</li></ul>"
+        );
+        assert_eq!(
+            outline(&parse("<table><tr><td>1<td>2<tr><td>3</table>")),
+            "<table><tr><td>1</td><td>2</td></tr><tr><td>3</td></tr></table>"
+        );
+        assert_eq!(outline(&parse("<b>unclosed")), "<b>unclosed</b>");
+        assert_eq!(outline(&parse("a</i>b")), "ab");
+    }
+
+    #[test]
+    fn void_and_self_closing_elements_have_no_children() {
+        assert_eq!(
+            outline(&parse("a<br>b<img src=x>c</br><span/>d")),
+            "a<br>b<img>c<br><span></span>d"
+        );
+    }
+
+    #[test]
+    fn raw_text_is_not_parsed_as_markup() {
+        let tree = parse("<script>if (a < b) { x = '<p>'; }</SCRIPT><p>after</p>");
+        assert_eq!(
+            tree.find("script").unwrap().text_content(),
+            "if (a < b) { x = '<p>'; }"
+        );
+        assert_eq!(tree.find("p").unwrap().text_content(), "after");
+
+        let tree = parse("<title>A &amp; B</title><textarea><b>&lt;</b></textarea>");
+        assert_eq!(tree.find("title").unwrap().text_content(), "A & B");
+        assert_eq!(tree.find("textarea").unwrap().text_content(), "<b><</b>");
+    }
+
+    #[test]
+    fn stray_angle_brackets_stay_text() {
+        assert_eq!(outline(&parse("1 < 2 <3 <")), "1 < 2 <3 <");
+    }
+}
@@ -0,0 +1,744 @@
+use crate::browser::document::Block;
+use crate::browser::form::{self, Control, ControlKind, Encoding, Form, SelectOption};
+use crate::browser::url::Url;
+use crate::common;
+use crate::draw::text::{Span, StyledLine, TextAttributes, TextStyle};
+use crate::draw::theme::Role;
+use crate::html::dom::{Node, NodeKind};
+
+/// Elements that are never shown.
+const HIDDEN: &[&str] = &[
+    "head", "script", "style", "template", "title", "meta", "link", "base",
+];
+
+/// Elements set off from their surroundings by a blank line.
+const SPACED: &[&str] = &[
+    "p",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "ul",
+    "ol",
+    "dl",
+    "pre",
+    "blockquote",
+    "table",
+    "figure",
+    "fieldset",
+];
+
+/// Elements that start and end on their own lines.
+const BLOCK: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "body",
+    "center",
+    "details",
+    "dd",
+    "div",
+    "dt",
+    "figcaption",
+    "footer",
+    "header",
+    "html",
+    "legend",
+    "li",
+    "main",
+    "nav",
+    "section",
+    "summary",
+    "tr",
+];
+
+/// Collapses runs of whitespace into single spaces.
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Forms and controls found so far, handed on to the renderers of table cells.
+#[derive(Default)]
+struct Forms {
+    forms: Vec<Form>,
+    controls: Vec<Control>,
+    /// The form whose content is being rendered.
+    current: Option<usize>,
+    /// Forms by `id`, for controls that name theirs with a `form` attribute.
+    ids: Vec<(String, usize)>,
+}
+
+/// Walks the document tree, turning it into blocks of styled text.
+struct Renderer<'a> {
+    base: &'a Url,
+    /// Where the document came from, which forms without an action submit to.
+    url: &'a Url,
+    blocks: Vec<Block>,
+    forms: Forms,
+
+    /// Inline content of the block being built.
+    line: Vec<Span>,
+    /// Whitespace seen since the last character, kept until more text follows.
+    space: Option<TextStyle>,
+
+    /// Spans repeated at the start of every line, one per open quote or list item.
+    indent: Vec<Span>,
+    /// A list marker waiting for the item's first line, and the index of the
+    /// item's own entry in `indent`, which the marker replaces on that line.
+    marker: Option<(usize, StyledLine)>,
+    /// Open lists, with the next number of each ordered one.
+    lists: Vec<Option<usize>>,
+
+    /// Depth of open `<pre>` elements.
+    preformatted: usize,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(base: &'a Url, url: &'a Url) -> Self {
+        Renderer {
+            base,
+            url,
+            blocks: Vec::new(),
+            forms: Forms::default(),
+            line: Vec::new(),
+            space: None,
+            indent: Vec::new(),
+            marker: None,
+            lists: Vec::new(),
+            preformatted: 0,
+        }
+    }
+
+    fn push_char(&mut self, c: char, style: &TextStyle) {
+        match self.line.last_mut() {
+            Some(span) if &span.style == style => span.text.push(c),
+            _ => self.line.push(Span::new(c, style.clone())),
+        }
+    }
+
+    fn text(&mut self, text: &str, style: &TextStyle) {
+        if self.preformatted > 0 {
+            for (i, part) in text.split('\n').enumerate() {
+                if i > 0 {
+                    self.flush();
+                }
+                for c in part.chars() {
+                    if c == '\t' {
+                        let column = self
+                            .line
+                            .iter()
+                            .map(|s| s.text.chars().count())
+                            .sum::<usize>();
+                        for _ in 0..8 - column % 8 {
+                            self.push_char(' ', style);
+                        }
+                    } else {
+                        self.push_char(c, style);
+                    }
+                }
+            }
+            return;
+        }
+
+        for c in text.chars() {
+            if c.is_whitespace() && c != '\u{a0}' {
+                if !self.line.is_empty() {
+                    self.space.get_or_insert_with(|| style.clone());
+                }
+                continue;
+            }
+
+            if let Some(space) = self.space.take() {
+                self.push_char(' ', &space);
+            }
+            self.push_char(c, style);
+        }
+    }
+
+    /// Ends the current line, turning it into a block.
+    fn flush(&mut self) {
+        self.space = None;
+
+        if self.line.is_empty() && self.preformatted == 0 {
+            return;
+        }
+
+        let line = StyledLine::new(std::mem::take(&mut self.line));
+        let mut indent = self.indent.clone();
+
+        let block = if self.preformatted > 0 {
+            indent.extend(line.spans);
+            Block::Preformatted(StyledLine::new(indent))
+        } else {
+            let marker = match self.marker.take() {
+                Some((index, marker)) => {
+                    indent.remove(index);
+                    marker
+                }
+                None => StyledLine::default(),
+            };
+
+            Block::Text {
+                indent: StyledLine::new(indent),
+                marker,
+                line,
+            }
+        };
+
+        self.blocks.push(block);
+    }
+
+    /// Ends the current line and leaves a blank line after it.
+    fn margin(&mut self) {
+        self.flush();
+
+        if let Some(last) = self.blocks.last()
+            && *last != Block::text("")
+        {
+            self.blocks.push(Block::text(""));
+        }
+    }
+
+    fn children(&mut self, node: &Node, style: &TextStyle) {
+        for child in &node.children {
+            self.node(child, style);
+        }
+    }
+
+    fn node(&mut self, node: &Node, style: &TextStyle) {
+        let name = match &node.kind {
+            NodeKind::Text(text) => return self.text(text, style),
+            NodeKind::Comment(_) => return,
+            NodeKind::Document => return self.children(node, style),
+            NodeKind::Element { name, .. } => name.as_str(),
+        };
+
+        let hidden = node
+            .attr("style")
+            .is_some_and(|css| css.replace(' ', "").contains("display:none"));
+        if HIDDEN.contains(&name) {
+            return;
+        }
+        // Controls in hidden elements are left out of their form too, so a
+        // page cannot submit anything the user was never shown.
+        if node.attr("hidden").is_some() || hidden {
+            return;
+        }
+
+        let with = |attributes: TextAttributes| TextStyle {
+            attributes: style.attributes | attributes,
+            ..style.clone()
+        };
+        let with_role = |role: Role| TextStyle {
+            role: Some(role),
+            ..style.clone()
+        };
+
+        match name {
+            "br" => {
+                if self.line.is_empty() && self.preformatted == 0 {
+                    self.blocks.push(Block::text(""));
+                }
+                self.flush();
+            }
+            "hr" => {
+                self.flush();
+                self.blocks.push(Block::Rule);
+            }
+            "img" => {
+                let alt = node.attr("alt").unwrap_or("image").trim();
+                if !alt.is_empty() {
+                    self.text(&format!("[{}]", alt), &with(TextAttributes::DIM));
+                }
+            }
+            "a" => {
+                let target = node.attr("href").and_then(|href| self.base.join(href).ok());
+                let style = match target {
+                    Some(target) => TextStyle {
+                        role: Some(Role::Link),
+                        attributes: style.attributes | TextAttributes::UNDERLINE,
+                        link: Some(target.to_string()),
+                        ..style.clone()
+                    },
+                    None => style.clone(),
+                };
+                self.children(node, &style);
+            }
+            "b" | "strong" => self.children(node, &with(TextAttributes::BOLD)),
+            "i" | "em" | "cite" | "var" | "dfn" => {
+                self.children(node, &with(TextAttributes::ITALIC))
+            }
+            "u" | "ins" => self.children(node, &with(TextAttributes::UNDERLINE)),
+            "s" | "strike" | "del" => self.children(node, &with(TextAttributes::STRIKETHROUGH)),
+            "small" => self.children(node, &with(TextAttributes::DIM)),
+            "mark" => self.children(node, &with(TextAttributes::REVERSE)),
+            "code" | "kbd" | "samp" | "tt" => self.children(node, &with_role(Role::Focus)),
+            "q" => {
+                self.text("“", style);
+                self.children(node, style);
+                self.text("”", style);
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let attributes = match name {
+                    "h1" => TextAttributes::BOLD | TextAttributes::UNDERLINE,
+                    _ => TextAttributes::BOLD,
+                };
+                self.margin();
+                self.children(node, &with(attributes));
+                self.margin();
+            }
+            "pre" => {
+                self.margin();
+                self.preformatted += 1;
+
+                // A newline right after `<pre>` is not part of the content.
+                let mut children = node.children.iter().peekable();
+                if let Some(Node {
+                    kind: NodeKind::Text(text),
+                    ..
+                }) = children.peek()
+                    && let Some(text) = text.strip_prefix('\n')
+                {
+                    self.text(text, style);
+                    children.next();
+                }
+                for child in children {
+                    self.node(child, style);
+                }
+
+                if !self.line.is_empty() {
+                    self.flush();
+                }
+                self.preformatted -= 1;
+                self.margin();
+            }
+            "blockquote" => {
+                self.margin();
+                self.indent.push(Span::new("│ ", with(TextAttributes::DIM)));
+                self.children(node, &with(TextAttributes::ITALIC));
+                self.flush();
+                self.indent.pop();
+                self.margin();
+            }
+            "ul" | "ol" => {
+                if self.lists.is_empty() {
+                    self.margin();
+                } else {
+                    self.flush();
+                }
+
+                let start = node.attr("start").and_then(|n| n.parse().ok()).unwrap_or(1);
+                self.lists.push((name == "ol").then_some(start));
+                self.children(node, style);
+                self.lists.pop();
+
+                if self.lists.is_empty() {
+                    self.margin();
+                } else {
+                    self.flush();
+                }
+            }
+            "li" => {
+                self.flush();
+
+                let nested = self.lists.len() > 1;
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ if nested => "◦ ".to_string(),
+                    _ => "• ".to_string(),
+                };
+
+                self.indent
+                    .push(Span::plain(" ".repeat(marker.chars().count())));
+                self.marker = Some((self.indent.len() - 1, marker.into()));
+                self.children(node, style);
+                self.flush();
+                self.marker = None;
+                self.indent.pop();
+            }
+            "dt" => {
+                self.flush();
+                self.children(node, &with(TextAttributes::BOLD));
+                self.flush();
+            }
+            "dd" => {
+                self.flush();
+                self.indent.push(Span::plain("    "));
+                self.children(node, style);
+                self.flush();
+                self.indent.pop();
+            }
+            "table" => {
+                self.margin();
+                self.table(node, style);
+                self.margin();
+            }
+            "form" => {
+                self.flush();
+                let outer = self.forms.current;
+                // Forms cannot be nested; controls belong to the outermost one.
+                if outer.is_none() {
+                    self.form(node);
+                }
+                self.children(node, style);
+                self.flush();
+                self.forms.current = outer;
+            }
+            "input" | "button" | "select" | "textarea" => self.control(node, style),
+            name if SPACED.contains(&name) => {
+                self.margin();
+                self.children(node, style);
+                self.margin();
+            }
+            name if BLOCK.contains(&name) => {
+                self.flush();
+                self.children(node, style);
+                self.flush();
+            }
+            _ => self.children(node, style),
+        }
+    }
+
+    fn form(&mut self, node: &Node) {
+        let action = node
+            .attr("action")
+            .map(str::trim)
+            .filter(|action| !action.is_empty())
+            .and_then(|action| self.base.join(action).ok());
+
+        self.forms.forms.push(Form {
+            action: action.unwrap_or_else(|| self.url.clone()),
+            method: form::parse_method(node.attr("method").unwrap_or("")),
+            encoding: Encoding::parse(node.attr("enctype").unwrap_or("")),
+        });
+
+        let index = self.forms.forms.len() - 1;
+        if let Some(id) = node.attr("id") {
+            self.forms.ids.push((id.to_string(), index));
+        }
+        self.forms.current = Some(index);
+    }
+
+    /// Adds a form control, drawing it unless it is a hidden input.
+    fn control(&mut self, node: &Node, style: &TextStyle) {
+        let element = node.name().unwrap_or("input");
+        let kind_name = node.attr("type").unwrap_or("").to_ascii_lowercase();
+
+        let kind = match (element, kind_name.as_str()) {
+            ("textarea", _) => ControlKind::TextArea,
+            ("select", _) => ControlKind::Select {
+                multiple: node.attr("multiple").is_some(),
+            },
+            ("button", "reset") => ControlKind::Reset,
+            ("button", "button") => ControlKind::Button,
+            ("button", _) => ControlKind::Submit,
+            (_, "hidden") => ControlKind::Hidden,
+            (_, "password") => ControlKind::Password,
+            (_, "checkbox") => ControlKind::Checkbox,
+            (_, "radio") => ControlKind::Radio,
+            (_, "submit") => ControlKind::Submit,
+            (_, "image") => ControlKind::Image,
+            (_, "reset") => ControlKind::Reset,
+            (_, "button") => ControlKind::Button,
+            (_, "file") => ControlKind::File,
+            _ => ControlKind::Text,
+        };
+        // File inputs start empty whatever the page says, so only a path the
+        // user typed is ever uploaded.
+        let value = match kind {
+            ControlKind::File => "",
+            _ => node.attr("value").unwrap_or(""),
+        };
+
+        let mut control = Control::new(kind, node.attr("name").unwrap_or(""), value);
+        control.form = match node.attr("form") {
+            Some(id) => self
+                .forms
+                .ids
+                .iter()
+                .find(|(form, _)| form == id)
+                .map(|(_, index)| *index),
+            None => self.forms.current,
+        };
+        control.disabled = node.attr("disabled").is_some();
+        control.checked = node.attr("checked").is_some();
+        control.default_checked = control.checked;
+
+        let width = match element {
+            "textarea" => node.attr("cols"),
+            _ => node.attr("size"),
+        };
+        if let Some(width) = width.and_then(|width| width.trim().parse::<usize>().ok()) {
+            control.size = width.clamp(1, 80);
+        }
+
+        control.label = match (&control.kind, element) {
+            (_, "button") => collapse(&node.text_content()),
+            (ControlKind::Submit, _) if value.is_empty() => "Submit".to_string(),
+            (ControlKind::Reset, _) if value.is_empty() => "Reset".to_string(),
+            (ControlKind::Image, _) => node.attr("alt").unwrap_or("Submit").to_string(),
+            _ => value.to_string(),
+        };
+
+        match &control.kind {
+            ControlKind::TextArea => {
+                // A newline right after `<textarea>` is not part of the value.
+                let text = node.text_content();
+                let text = text.strip_prefix('\n').unwrap_or(&text);
+                control.value = text.to_string();
+                control.default_value = control.value.clone();
+            }
+            ControlKind::Select { multiple } => {
+                control.options = node
+                    .descendants()
+                    .into_iter()
+                    .filter(|n| n.is("option"))
+                    .map(|option| {
+                        let text = collapse(&option.text_content());
+                        SelectOption {
+                            value: option.attr("value").map_or(text.clone(), str::to_string),
+                            label: option.attr("label").map_or(text, str::to_string),
+                            selected: option.attr("selected").is_some(),
+                            default_selected: false,
+                        }
+                    })
+                    .collect();
+
+                // Without `multiple`, exactly one option is selected: the
+                // last one marked, or else the first.
+                if !multiple {
+                    let chosen = control
+                        .options
+                        .iter()
+                        .rposition(|option| option.selected)
+                        .unwrap_or(0);
+                    for (i, option) in control.options.iter_mut().enumerate() {
+                        option.selected = i == chosen;
+                    }
+                }
+                for option in &mut control.options {
+                    option.default_selected = option.selected;
+                }
+            }
+            _ => {}
+        }
+
+        if control.is_submit() {
+            control.action = node
+                .attr("formaction")
+                .and_then(|action| self.base.join(action.trim()).ok());
+            control.method = node.attr("formmethod").map(form::parse_method);
+            control.encoding = node.attr("formenctype").map(Encoding::parse);
+        }
+
+        control.visible = control.kind != ControlKind::Hidden;
+        let display = control.display();
+        let visible = control.visible;
+        self.forms.controls.push(control);
+        if !visible {
+            return;
+        }
+
+        let mut attributes = style.attributes | TextAttributes::REVERSE;
+        if node.attr("disabled").is_some() {
+            attributes |= TextAttributes::DIM;
+        }
+        let style = TextStyle {
+            attributes,
+            widget: Some(self.forms.controls.len() - 1),
+            ..style.clone()
+        };
+
+        // Written as is: the control's text is replaced when the page is laid out.
+        if let Some(space) = self.space.take() {
+            self.push_char(' ', &space);
+        }
+        for c in display.chars() {
+            self.push_char(c, &style);
+        }
+    }
+
+    /// Renders a node's content on a single line, for table cells.
+    fn inline(&mut self, node: &Node, style: &TextStyle) -> StyledLine {
+        let mut renderer = Renderer::new(self.base, self.url);
+        renderer.forms = std::mem::take(&mut self.forms);
+        renderer.children(node, style);
+        renderer.flush();
+        self.forms = std::mem::take(&mut renderer.forms);
+
+        let mut spans: Vec<Span> = Vec::new();
+        for block in renderer.blocks {
+            let line = match block {
+                Block::Text { line, .. } | Block::Preformatted(line) => line,
+                Block::Rule => continue,
+            };
+            if !spans.is_empty() && !line.spans.is_empty() {
+                spans.push(Span::plain(" "));
+            }
+            spans.extend(line.spans);
+        }
+        StyledLine::new(spans)
+    }
+
+    /// Lays a table out as aligned columns.
+    fn table(&mut self, node: &Node, style: &TextStyle) {
+        let bold = TextStyle {
+            attributes: style.attributes | TextAttributes::BOLD,
+            ..style.clone()
+        };
+
+        let mut rows = Vec::new();
+        for row in node.descendants().into_iter().filter(|n| n.is("tr")) {
+            let mut cells = Vec::new();
+            for cell in row.children.iter().filter(|c| c.is("td") || c.is("th")) {
+                cells.push(self.inline(cell, if cell.is("th") { &bold } else { style }));
+            }
+            if !cells.is_empty() {
+                rows.push(cells);
+            }
+        }
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths = (0..columns)
+            .map(|c| {
+                rows.iter()
+                    .filter_map(|row| row.get(c))
+                    .map(StyledLine::width)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        let separator = if common::locale_is_utf8() {
+            " │ "
+        } else {
+            " | "
+        };
+
+        for row in rows {
+            let mut spans = self.indent.clone();
+            for (c, cell) in row.iter().enumerate() {
+                if c > 0 {
+                    spans.push(Span::new(
+                        separator,
+                        TextStyle {
+                            attributes: TextAttributes::DIM,
+                            ..Default::default()
+                        },
+                    ));
+                }
+                spans.extend(cell.spans.iter().cloned());
+                if c + 1 < row.len() {
+                    spans.push(Span::plain(" ".repeat(widths[c] - cell.width())));
+                }
+            }
+            self.blocks
+                .push(Block::Preformatted(StyledLine::new(spans)));
+        }
+    }
+}
+
+/// The document's title, from its `<title>` element.
+pub fn title(document: &Node) -> String {
+    document
+        .find("title")
+        .map(|title| {
+            title
+                .text_content()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_default()
+}
+
+/// A document laid out as blocks, with the forms found in it.
+pub struct Rendered {
+    pub blocks: Vec<Block>,
+    pub forms: Vec<Form>,
+    /// Controls are drawn as text whose style's `widget` is their index here.
+    pub controls: Vec<Control>,
+}
+
+/// Lays out a parsed document as blocks of styled text. Links are resolved
+/// against `url`, or the document's `<base>` if it has one.
+pub fn render(document: &Node, url: &Url) -> Rendered {
+    let base = document
+        .find("base")
+        .and_then(|base| base.attr("href"))
+        .and_then(|href| url.join(href).ok())
+        .unwrap_or_else(|| url.clone());
+
+    let mut renderer = Renderer::new(&base, url);
+    renderer.node(document, &TextStyle::default());
+    renderer.flush();
+
+    // Trailing blank lines are left over from the last block's margin.
+    while renderer.blocks.last() == Some(&Block::text("")) {
+        renderer.blocks.pop();
+    }
+
+    Rendered {
+        blocks: renderer.blocks,
+        forms: renderer.forms.forms,
+        controls: renderer.forms.controls,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::browser::form::{self, Value};
+    use crate::html::parse::parse;
+
+    fn rendered(html: &str) -> Rendered {
+        render(
+            &parse(html),
+            &Url::parse("http://example.com/form").unwrap(),
+        )
+    }
+
+    #[test]
+    fn file_inputs_ignore_the_page_value() {
+        let page = rendered(
+            "<form method=post enctype=multipart/form-data>\
+             <input type=file name=upload value=/etc/passwd><input type=submit></form>",
+        );
+        let file = &page.controls[0];
+        assert_eq!(file.kind, ControlKind::File);
+        assert_eq!(file.value, "");
+
+        let data = form::data(&page.controls, 0, Some(1), true).unwrap();
+        assert_eq!(
+            data,
+            vec![(
+                "upload".to_string(),
+                Value::File {
+                    name: String::new(),
+                    content: Vec::new()
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn controls_in_hidden_elements_are_left_out() {
+        let page = rendered(
+            "<form><input name=shown>\
+             <div hidden><input name=a></div>\
+             <div style=\"display: none\"><p><select name=b><option>x</select></p></div>\
+             <input type=hidden name=token value=t></form>",
+        );
+        let names = page
+            .controls
+            .iter()
+            .map(|control| (control.name.as_str(), control.visible))
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![("shown", true), ("token", false)]);
+    }
+}
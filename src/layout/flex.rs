@@ -0,0 +1,518 @@
+use crate::common::{Rect, Vec2};
+use crate::layout::distribute;
+
+/// Like CSS `flex-direction`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FlexDirection {
+    #[default]
+    Row,
+    RowReverse,
+    Column,
+    ColumnReverse,
+}
+
+impl FlexDirection {
+    fn is_row(&self) -> bool {
+        matches!(self, FlexDirection::Row | FlexDirection::RowReverse)
+    }
+
+    fn is_reverse(&self) -> bool {
+        matches!(
+            self,
+            FlexDirection::RowReverse | FlexDirection::ColumnReverse
+        )
+    }
+}
+
+/// Like CSS `flex-wrap`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FlexWrap {
+    #[default]
+    NoWrap,
+    Wrap,
+}
+
+/// Like CSS `justify-content`, distributing free space along the main axis.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JustifyContent {
+    #[default]
+    Start,
+    End,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+/// Like CSS `align-items`, positioning items along the cross axis of their line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AlignItems {
+    Start,
+    End,
+    Center,
+    #[default]
+    Stretch,
+}
+
+/// Settings of a box whose children are laid out as a flex container.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FlexContainer {
+    pub direction: FlexDirection,
+    pub wrap: FlexWrap,
+    pub justify_content: JustifyContent,
+    pub align_items: AlignItems,
+    /// Cells between neighbouring items, and between lines when wrapping.
+    pub gap: usize,
+}
+
+/// Like CSS `flex-basis`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FlexBasis {
+    /// Use the item's own size along the main axis.
+    #[default]
+    Auto,
+    Cells(usize),
+}
+
+/// How a child of a flex container grows and shrinks, like CSS `flex`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FlexItem {
+    pub grow: f32,
+    pub shrink: f32,
+    pub basis: FlexBasis,
+}
+
+impl Default for FlexItem {
+    fn default() -> Self {
+        FlexItem {
+            grow: 0.0,
+            shrink: 1.0,
+            basis: FlexBasis::Auto,
+        }
+    }
+}
+
+struct Line {
+    items: Vec<usize>,
+    main: Vec<usize>,
+    cross: usize,
+}
+
+fn main_axis(size: &Vec2, row: bool) -> usize {
+    if row { size.x } else { size.y }
+}
+
+fn cross_axis(size: &Vec2, row: bool) -> usize {
+    if row { size.y } else { size.x }
+}
+
+/// Leading space and space between items for `free` cells left over among `count` items.
+fn justify(justify: JustifyContent, free: usize, count: usize) -> (usize, Vec<usize>) {
+    let gaps = count.saturating_sub(1);
+
+    match justify {
+        JustifyContent::Start => (0, vec![0; gaps]),
+        JustifyContent::End => (free, vec![0; gaps]),
+        JustifyContent::Center => (free / 2, vec![0; gaps]),
+        JustifyContent::SpaceBetween if gaps > 0 => (0, distribute(free, &vec![1.0; gaps])),
+        JustifyContent::SpaceBetween => (0, vec![]),
+        JustifyContent::SpaceAround => {
+            // Each item gets half a share on either side.
+            let shares = distribute(free, &vec![1.0; count * 2]);
+            let between = (0..gaps).map(|i| shares[i * 2 + 1] + shares[i * 2 + 2]);
+            (shares[0], between.collect())
+        }
+        JustifyContent::SpaceEvenly => {
+            let shares = distribute(free, &vec![1.0; count + 1]);
+            (shares[0], shares[1..count].to_vec())
+        }
+    }
+}
+
+/// Resolves the main sizes of a line of items, growing or shrinking them to fill `available`.
+fn flex_line(bases: &[usize], items: &[&FlexItem], available: usize) -> Vec<usize> {
+    let used = bases.iter().sum::<usize>();
+
+    if used < available {
+        let grow = items.iter().map(|i| i.grow.max(0.0)).collect::<Vec<_>>();
+        if grow.iter().sum::<f32>() > 0.0 {
+            let extra = distribute(available - used, &grow);
+            return bases.iter().zip(extra).map(|(b, e)| b + e).collect();
+        }
+    } else if used > available {
+        // Like CSS, shrinking is weighted by the basis so small items are not crushed first.
+        let shrink = items
+            .iter()
+            .zip(bases)
+            .map(|(i, b)| i.shrink.max(0.0) * *b as f32)
+            .collect::<Vec<_>>();
+        if shrink.iter().sum::<f32>() > 0.0 {
+            let cut = distribute(used - available, &shrink);
+            return bases
+                .iter()
+                .zip(cut)
+                .map(|(b, c)| b.saturating_sub(c))
+                .collect();
+        }
+    }
+
+    bases.to_vec()
+}
+
+/// Positions and sizes of `children` laid out inside `area`. Each child is given as
+/// its flex settings and its own size, which is used for `FlexBasis::Auto` and for
+/// the cross axis unless items are stretched.
+pub fn layout(area: &Rect, container: &FlexContainer, children: &[(FlexItem, Vec2)]) -> Vec<Rect> {
+    if children.is_empty() {
+        return vec![];
+    }
+
+    let row = container.direction.is_row();
+    let available = main_axis(&area.size, row);
+    let gap = container.gap;
+
+    let bases = children
+        .iter()
+        .map(|(item, size)| match item.basis {
+            FlexBasis::Auto => main_axis(size, row),
+            FlexBasis::Cells(cells) => cells,
+        })
+        .collect::<Vec<_>>();
+
+    // Break items into lines.
+    let mut groups: Vec<Vec<usize>> = vec![vec![]];
+    let mut used = 0;
+    for (i, basis) in bases.iter().enumerate() {
+        let current = groups.last_mut().unwrap();
+        let needed = if current.is_empty() {
+            *basis
+        } else {
+            gap + basis
+        };
+
+        if container.wrap == FlexWrap::Wrap && !current.is_empty() && used + needed > available {
+            groups.push(vec![i]);
+            used = *basis;
+        } else {
+            current.push(i);
+            used += needed;
+        }
+    }
+
+    let single_line = groups.len() == 1;
+    let lines = groups
+        .into_iter()
+        .map(|items| {
+            let gaps = gap * items.len().saturating_sub(1);
+            let line_bases = items.iter().map(|&i| bases[i]).collect::<Vec<_>>();
+            let flex = items.iter().map(|&i| &children[i].0).collect::<Vec<_>>();
+            let main = flex_line(&line_bases, &flex, available.saturating_sub(gaps));
+
+            let cross = if single_line {
+                cross_axis(&area.size, row)
+            } else {
+                items
+                    .iter()
+                    .map(|&i| cross_axis(&children[i].1, row))
+                    .max()
+                    .unwrap_or(0)
+            };
+
+            Line { items, main, cross }
+        })
+        .collect::<Vec<_>>();
+
+    let mut rects = vec![
+        Rect {
+            position: area.position.clone(),
+            size: Vec2::new(0, 0),
+        };
+        children.len()
+    ];
+
+    let mut cross_offset = 0;
+    for line in &lines {
+        let count = line.items.len();
+        let occupied = line.main.iter().sum::<usize>() + gap * count.saturating_sub(1);
+        let (lead, between) = justify(
+            container.justify_content,
+            available.saturating_sub(occupied),
+            count,
+        );
+
+        let mut main_offset = lead;
+        for (k, &i) in line.items.iter().enumerate() {
+            let main_size = line.main[k];
+            let own_cross = cross_axis(&children[i].1, row).min(line.cross);
+            let free_cross = line.cross - own_cross;
+
+            let (cross_size, cross_start) = match container.align_items {
+                AlignItems::Stretch => (line.cross, 0),
+                AlignItems::Start => (own_cross, 0),
+                AlignItems::End => (own_cross, free_cross),
+                AlignItems::Center => (own_cross, free_cross / 2),
+            };
+
+            let main_start = if container.direction.is_reverse() {
+                available.saturating_sub(main_offset + main_size)
+            } else {
+                main_offset
+            };
+
+            let (x, y, w, h) = if row {
+                (
+                    main_start,
+                    cross_offset + cross_start,
+                    main_size,
+                    cross_size,
+                )
+            } else {
+                (
+                    cross_offset + cross_start,
+                    main_start,
+                    cross_size,
+                    main_size,
+                )
+            };
+
+            rects[i] = Rect {
+                position: Vec2::new(area.position.x + x as i16, area.position.y + y as i16),
+                size: Vec2::new(w, h),
+            };
+
+            main_offset += main_size + gap + between.get(k).copied().unwrap_or(0);
+        }
+
+        cross_offset += line.cross + gap;
+    }
+
+    rects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(width: usize, height: usize) -> Rect {
+        Rect {
+            position: Vec2::new(0, 0),
+            size: Vec2::new(width, height),
+        }
+    }
+
+    fn sized(width: usize, height: usize) -> (FlexItem, Vec2) {
+        (FlexItem::default(), Vec2::new(width, height))
+    }
+
+    fn flexed(grow: f32, shrink: f32, width: usize) -> (FlexItem, Vec2) {
+        let item = FlexItem {
+            grow,
+            shrink,
+            basis: FlexBasis::Cells(width),
+        };
+        (item, Vec2::new(0, 1))
+    }
+
+    /// Each rect as `(x, y, width, height)`.
+    fn boxes(
+        area: &Rect,
+        container: &FlexContainer,
+        children: &[(FlexItem, Vec2)],
+    ) -> Vec<[i64; 4]> {
+        layout(area, container, children)
+            .iter()
+            .map(|rect| {
+                [
+                    rect.position.x as i64,
+                    rect.position.y as i64,
+                    rect.size.x as i64,
+                    rect.size.y as i64,
+                ]
+            })
+            .collect()
+    }
+
+    /// The x of each item in a row of three 2-cell items in 12 cells.
+    fn justified(justify_content: JustifyContent) -> Vec<i64> {
+        let container = FlexContainer {
+            justify_content,
+            ..FlexContainer::default()
+        };
+        let children = [sized(2, 1), sized(2, 1), sized(2, 1)];
+        boxes(&area(12, 1), &container, &children)
+            .iter()
+            .map(|rect| rect[0])
+            .collect()
+    }
+
+    #[test]
+    fn justifies() {
+        assert_eq!(justified(JustifyContent::Start), [0, 2, 4]);
+        assert_eq!(justified(JustifyContent::End), [6, 8, 10]);
+        assert_eq!(justified(JustifyContent::Center), [3, 5, 7]);
+        assert_eq!(justified(JustifyContent::SpaceBetween), [0, 5, 10]);
+        assert_eq!(justified(JustifyContent::SpaceAround), [1, 5, 9]);
+        // Six cells do not split evenly in four, so the first spaces get the extra.
+        assert_eq!(justified(JustifyContent::SpaceEvenly), [2, 6, 9]);
+    }
+
+    #[test]
+    fn justifies_a_single_item() {
+        let one = |justify_content| {
+            let container = FlexContainer {
+                justify_content,
+                ..FlexContainer::default()
+            };
+            boxes(&area(10, 1), &container, &[sized(4, 1)])[0][0]
+        };
+        assert_eq!(one(JustifyContent::SpaceBetween), 0);
+        assert_eq!(one(JustifyContent::SpaceAround), 3);
+        assert_eq!(one(JustifyContent::SpaceEvenly), 3);
+        assert_eq!(one(JustifyContent::End), 6);
+    }
+
+    #[test]
+    fn aligns() {
+        let aligned = |align_items| {
+            let container = FlexContainer {
+                align_items,
+                ..FlexContainer::default()
+            };
+            let children = [sized(2, 1), sized(2, 2), sized(2, 4)];
+            boxes(&area(12, 4), &container, &children)
+                .iter()
+                .map(|rect| (rect[1], rect[3]))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(aligned(AlignItems::Stretch), [(0, 4), (0, 4), (0, 4)]);
+        assert_eq!(aligned(AlignItems::Start), [(0, 1), (0, 2), (0, 4)]);
+        assert_eq!(aligned(AlignItems::End), [(3, 1), (2, 2), (0, 4)]);
+        assert_eq!(aligned(AlignItems::Center), [(1, 1), (1, 2), (0, 4)]);
+    }
+
+    #[test]
+    fn columns_and_reversed_rows() {
+        let column = FlexContainer {
+            direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            gap: 1,
+            ..FlexContainer::default()
+        };
+        assert_eq!(
+            boxes(&area(6, 10), &column, &[sized(2, 3), sized(4, 2)]),
+            [[2, 0, 2, 3], [1, 4, 4, 2]]
+        );
+
+        let reversed = FlexContainer {
+            direction: FlexDirection::RowReverse,
+            ..FlexContainer::default()
+        };
+        let placed = Rect {
+            position: Vec2::new(5, 2),
+            size: Vec2::new(10, 1),
+        };
+        assert_eq!(
+            boxes(&placed, &reversed, &[sized(2, 1), sized(3, 1)]),
+            [[13, 2, 2, 1], [10, 2, 3, 1]]
+        );
+    }
+
+    #[test]
+    fn wraps() {
+        let children = [sized(3, 1), sized(3, 2), sized(3, 1)];
+        let wrapping = FlexContainer {
+            wrap: FlexWrap::Wrap,
+            gap: 1,
+            ..FlexContainer::default()
+        };
+        // Each line is as tall as its tallest item, with the gap between lines.
+        assert_eq!(
+            boxes(&area(7, 5), &wrapping, &children),
+            [[0, 0, 3, 2], [4, 0, 3, 2], [0, 3, 3, 1]]
+        );
+
+        // An item wider than the line still gets a line of its own.
+        let children = [sized(2, 1), sized(9, 1), sized(2, 1)];
+        assert_eq!(
+            boxes(&area(7, 5), &wrapping, &children)
+                .iter()
+                .map(|rect| rect[1])
+                .collect::<Vec<_>>(),
+            [0, 2, 4]
+        );
+
+        // Without wrapping, the same items shrink to fit on one line.
+        let single = FlexContainer {
+            gap: 1,
+            ..FlexContainer::default()
+        };
+        assert_eq!(
+            boxes(
+                &area(7, 3),
+                &single,
+                &[sized(3, 1), sized(3, 2), sized(3, 1)]
+            ),
+            [[0, 0, 1, 3], [2, 0, 2, 3], [5, 0, 2, 3]]
+        );
+    }
+
+    #[test]
+    fn grows() {
+        let container = FlexContainer::default();
+        let children = [
+            flexed(1.0, 1.0, 2),
+            flexed(3.0, 1.0, 2),
+            flexed(0.0, 1.0, 2),
+        ];
+        assert_eq!(
+            boxes(&area(10, 1), &container, &children),
+            [[0, 0, 3, 1], [3, 0, 5, 1], [8, 0, 2, 1]]
+        );
+
+        // Nothing grows by default, so free space is left for justification.
+        let children = [sized(2, 1), sized(2, 1)];
+        assert_eq!(
+            boxes(&area(10, 1), &container, &children),
+            [[0, 0, 2, 1], [2, 0, 2, 1]]
+        );
+    }
+
+    #[test]
+    fn shrinks() {
+        let container = FlexContainer::default();
+        // Shrinking is weighted by the basis.
+        let children = [flexed(0.0, 1.0, 6), flexed(0.0, 1.0, 3)];
+        assert_eq!(
+            boxes(&area(6, 1), &container, &children),
+            [[0, 0, 4, 1], [4, 0, 2, 1]]
+        );
+
+        let children = [flexed(0.0, 0.0, 6), flexed(0.0, 1.0, 3)];
+        assert_eq!(
+            boxes(&area(6, 1), &container, &children),
+            [[0, 0, 6, 1], [6, 0, 0, 1]]
+        );
+    }
+
+    #[test]
+    fn zero_and_overflowing_space() {
+        let centered = FlexContainer {
+            justify_content: JustifyContent::Center,
+            ..FlexContainer::default()
+        };
+        assert_eq!(
+            boxes(&area(0, 1), &centered, &[sized(2, 1), sized(2, 1)]),
+            [[0, 0, 0, 1], [0, 0, 0, 1]]
+        );
+
+        // Items that cannot shrink run past the end, with no free space to justify.
+        let rigid = [flexed(0.0, 0.0, 3), flexed(0.0, 0.0, 3)];
+        assert_eq!(
+            boxes(&area(4, 1), &centered, &rigid),
+            [[0, 0, 3, 1], [3, 0, 3, 1]]
+        );
+
+        assert!(layout(&area(4, 1), &centered, &[]).is_empty());
+    }
+}
@@ -0,0 +1,590 @@
+use crate::common::{Rect, Vec2};
+use crate::layout::distribute;
+
+/// Size of a grid row or column, like a CSS `grid-template-columns` entry.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TrackSize {
+    Cells(usize),
+    /// A share of the space left after the other tracks, like CSS `fr`.
+    Fraction(f32),
+    /// As large as the largest item that sits only in this track.
+    Auto,
+    /// At least `min` cells and at most the second size, like CSS `minmax()`.
+    MinMax(usize, Box<TrackSize>),
+}
+
+/// Where an item goes along one axis. Lines are numbered from 0; an unset
+/// start lets the item be placed automatically.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GridPlacement {
+    pub start: Option<usize>,
+    pub span: usize,
+}
+
+impl Default for GridPlacement {
+    fn default() -> Self {
+        GridPlacement {
+            start: None,
+            span: 1,
+        }
+    }
+}
+
+impl GridPlacement {
+    pub fn at(start: usize) -> Self {
+        GridPlacement {
+            start: Some(start),
+            span: 1,
+        }
+    }
+
+    pub fn spanning(start: usize, span: usize) -> Self {
+        GridPlacement {
+            start: Some(start),
+            span: span.max(1),
+        }
+    }
+}
+
+/// Settings of a box whose children are laid out on a grid.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GridContainer {
+    pub columns: Vec<TrackSize>,
+    /// Rows past the end of this list are added as needed and sized `TrackSize::Auto`.
+    pub rows: Vec<TrackSize>,
+    /// Named areas, one string per row with a name per column, like CSS
+    /// `grid-template-areas`. A `.` leaves a cell unnamed.
+    pub areas: Vec<String>,
+    pub row_gap: usize,
+    pub column_gap: usize,
+}
+
+/// Where a child of a grid container goes, like CSS `grid-column`, `grid-row`
+/// and `grid-area`. A named area takes precedence over the placements.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GridItem {
+    pub column: GridPlacement,
+    pub row: GridPlacement,
+    pub area: Option<String>,
+}
+
+/// An item's cells, as (column, row, column span, row span).
+type Cells = (usize, usize, usize, usize);
+
+impl GridContainer {
+    /// The cells covered by the area called `name`.
+    fn area(&self, name: &str) -> Option<Cells> {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+
+        for (row, line) in self.areas.iter().enumerate() {
+            for (column, cell) in line.split_whitespace().enumerate() {
+                if cell != name {
+                    continue;
+                }
+
+                bounds = Some(match bounds {
+                    None => (column, row, column, row),
+                    Some((c0, r0, c1, r1)) => {
+                        (c0.min(column), r0.min(row), c1.max(column), r1.max(row))
+                    }
+                });
+            }
+        }
+
+        bounds.map(|(c0, r0, c1, r1)| (c0, r0, c1 - c0 + 1, r1 - r0 + 1))
+    }
+
+    fn column_count(&self) -> usize {
+        let named = self
+            .areas
+            .iter()
+            .map(|line| line.split_whitespace().count())
+            .max()
+            .unwrap_or(0);
+
+        self.columns.len().max(named).max(1)
+    }
+}
+
+/// Assigns cells to every item: explicitly placed items first, then the rest
+/// in reading order into the first free cells they fit.
+fn place(container: &GridContainer, items: &[GridItem]) -> Vec<Cells> {
+    let columns = container.column_count();
+    let mut occupied: Vec<Vec<bool>> = Vec::new();
+
+    let mark = |occupied: &mut Vec<Vec<bool>>, cells: Cells| {
+        let (c, r, cs, rs) = cells;
+        if occupied.len() < r + rs {
+            occupied.resize(r + rs, vec![false; columns]);
+        }
+        for row in &mut occupied[r..r + rs] {
+            for cell in row.iter_mut().skip(c).take(cs) {
+                *cell = true;
+            }
+        }
+    };
+
+    let fixed = |item: &GridItem| -> Option<Cells> {
+        if let Some(cells) = item.area.as_deref().and_then(|name| container.area(name)) {
+            return Some(cells);
+        }
+
+        match (item.column.start, item.row.start) {
+            (Some(c), Some(r)) => Some((c, r, item.column.span, item.row.span)),
+            _ => None,
+        }
+    };
+
+    let mut placed: Vec<Option<Cells>> = items.iter().map(fixed).collect();
+    for cells in placed.iter().flatten() {
+        mark(&mut occupied, *cells);
+    }
+
+    for (item, slot) in items.iter().zip(placed.iter_mut()) {
+        if slot.is_some() {
+            continue;
+        }
+
+        let (cs, rs) = (item.column.span.min(columns), item.row.span);
+        let fits = |occupied: &Vec<Vec<bool>>, c: usize, r: usize| {
+            (r..r + rs).all(|row| {
+                (c..c + cs).all(|column| {
+                    occupied
+                        .get(row)
+                        .is_none_or(|cells| !cells.get(column).copied().unwrap_or(false))
+                })
+            })
+        };
+
+        let candidate_columns = match item.column.start {
+            Some(start) => start..start + 1,
+            None => 0..columns - cs + 1,
+        };
+        // Past the occupied rows everything is free, so the search always ends.
+        let candidate_rows = match item.row.start {
+            Some(start) => start..start + 1,
+            None => 0..occupied.len() + 1,
+        };
+
+        let cells = candidate_rows
+            .clone()
+            .flat_map(|r| candidate_columns.clone().map(move |c| (c, r)))
+            .find(|(c, r)| fits(&occupied, *c, *r))
+            .unwrap_or((candidate_columns.start, candidate_rows.start));
+        let cells = (cells.0, cells.1, cs, rs);
+
+        mark(&mut occupied, cells);
+        *slot = Some(cells);
+    }
+
+    placed.into_iter().map(Option::unwrap).collect()
+}
+
+/// Resolves track sizes so they fill `available` cells where fractions or
+/// auto tracks allow it.
+fn size_tracks(
+    tracks: &[TrackSize],
+    content: &[usize],
+    available: usize,
+    gap: usize,
+) -> Vec<usize> {
+    let min_of = |track: &TrackSize, content: usize| match track {
+        TrackSize::Cells(cells) => *cells,
+        TrackSize::Fraction(_) => 0,
+        TrackSize::Auto => content,
+        TrackSize::MinMax(min, _) => *min,
+    };
+    let max_of = |track: &TrackSize| match track {
+        TrackSize::MinMax(_, max) => max.as_ref().clone(),
+        other => other.clone(),
+    };
+
+    let mut sizes = tracks
+        .iter()
+        .zip(content)
+        .map(|(track, content)| min_of(track, *content))
+        .collect::<Vec<_>>();
+
+    let gaps = gap * tracks.len().saturating_sub(1);
+    let mut free = available.saturating_sub(sizes.iter().sum::<usize>() + gaps);
+
+    // Grow tracks with a fixed maximum towards it.
+    for (size, track) in sizes.iter_mut().zip(tracks) {
+        if let TrackSize::MinMax(_, max) = track
+            && let TrackSize::Cells(max) = max.as_ref()
+        {
+            let growth = max.saturating_sub(*size).min(free);
+            *size += growth;
+            free -= growth;
+        }
+    }
+
+    let fractions = tracks
+        .iter()
+        .map(|track| match max_of(track) {
+            TrackSize::Fraction(fr) => fr.max(0.0),
+            _ => 0.0,
+        })
+        .collect::<Vec<_>>();
+
+    if fractions.iter().sum::<f32>() > 0.0 {
+        // Fractions share the free space along with the minimums already given to them.
+        let claimed = sizes
+            .iter()
+            .zip(&fractions)
+            .filter(|(_, fr)| **fr > 0.0)
+            .map(|(size, _)| *size)
+            .sum::<usize>();
+        let shares = distribute(free + claimed, &fractions);
+
+        for ((size, fr), share) in sizes.iter_mut().zip(&fractions).zip(shares) {
+            if *fr > 0.0 {
+                *size = (*size).max(share);
+            }
+        }
+    } else {
+        // Without fractions, leftover space stretches the auto tracks.
+        let auto = tracks
+            .iter()
+            .map(|track| (max_of(track) == TrackSize::Auto) as u8 as f32)
+            .collect::<Vec<_>>();
+        for (size, extra) in sizes.iter_mut().zip(distribute(free, &auto)) {
+            *size += extra;
+        }
+    }
+
+    sizes
+}
+
+/// Largest size of the items that sit in a single track, for auto sizing.
+fn content_sizes(count: usize, spans: impl Iterator<Item = (usize, usize, usize)>) -> Vec<usize> {
+    let mut sizes = vec![0; count];
+    for (start, span, size) in spans {
+        if span == 1 && start < count {
+            sizes[start] = sizes[start].max(size);
+        }
+    }
+    sizes
+}
+
+fn offsets(sizes: &[usize], gap: usize) -> Vec<usize> {
+    sizes
+        .iter()
+        .scan(0, |offset, size| {
+            let start = *offset;
+            *offset += size + gap;
+            Some(start)
+        })
+        .collect()
+}
+
+/// Positions and sizes of `children` laid out inside `area`. Each child is given as
+/// its grid placement and its own size, which is used to size auto tracks. Items
+/// stretch to fill the cells they cover.
+pub fn layout(area: &Rect, container: &GridContainer, children: &[(GridItem, Vec2)]) -> Vec<Rect> {
+    if children.is_empty() {
+        return vec![];
+    }
+
+    let items = children
+        .iter()
+        .map(|(item, _)| item.clone())
+        .collect::<Vec<_>>();
+    let cells = place(container, &items);
+
+    let column_count = cells
+        .iter()
+        .map(|(c, _, cs, _)| c + cs)
+        .max()
+        .unwrap_or(0)
+        .max(container.column_count());
+    let row_count = cells
+        .iter()
+        .map(|(_, r, _, rs)| r + rs)
+        .max()
+        .unwrap_or(0)
+        .max(container.rows.len())
+        .max(container.areas.len());
+
+    let mut columns = container.columns.clone();
+    columns.resize(column_count, TrackSize::Auto);
+    let mut rows = container.rows.clone();
+    rows.resize(row_count, TrackSize::Auto);
+
+    let column_content = content_sizes(
+        column_count,
+        cells
+            .iter()
+            .zip(children)
+            .map(|((c, _, cs, _), (_, size))| (*c, *cs, size.x)),
+    );
+    let row_content = content_sizes(
+        row_count,
+        cells
+            .iter()
+            .zip(children)
+            .map(|((_, r, _, rs), (_, size))| (*r, *rs, size.y)),
+    );
+
+    let widths = size_tracks(&columns, &column_content, area.size.x, container.column_gap);
+    let heights = size_tracks(&rows, &row_content, area.size.y, container.row_gap);
+
+    let xs = offsets(&widths, container.column_gap);
+    let ys = offsets(&heights, container.row_gap);
+
+    let span_size = |sizes: &[usize], start: usize, span: usize, gap: usize| {
+        let end = (start + span).min(sizes.len());
+        sizes[start.min(end)..end].iter().sum::<usize>()
+            + gap * (end - start.min(end)).saturating_sub(1)
+    };
+
+    cells
+        .iter()
+        .map(|&(c, r, cs, rs)| Rect {
+            position: Vec2::new(
+                area.position.x + xs.get(c).copied().unwrap_or(area.size.x) as i16,
+                area.position.y + ys.get(r).copied().unwrap_or(area.size.y) as i16,
+            ),
+            size: Vec2::new(
+                span_size(&widths, c, cs, container.column_gap),
+                span_size(&heights, r, rs, container.row_gap),
+            ),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(width: usize, height: usize) -> Rect {
+        Rect {
+            position: Vec2::new(0, 0),
+            size: Vec2::new(width, height),
+        }
+    }
+
+    fn auto(width: usize, height: usize) -> (GridItem, Vec2) {
+        (GridItem::default(), Vec2::new(width, height))
+    }
+
+    fn at(column: GridPlacement, row: GridPlacement) -> (GridItem, Vec2) {
+        let item = GridItem {
+            column,
+            row,
+            area: None,
+        };
+        (item, Vec2::new(1, 1))
+    }
+
+    fn named(name: &str) -> (GridItem, Vec2) {
+        let item = GridItem {
+            area: Some(name.to_string()),
+            ..GridItem::default()
+        };
+        (item, Vec2::new(1, 1))
+    }
+
+    /// Each rect as `(x, y, width, height)`.
+    fn boxes(
+        area: &Rect,
+        container: &GridContainer,
+        children: &[(GridItem, Vec2)],
+    ) -> Vec<[i64; 4]> {
+        layout(area, container, children)
+            .iter()
+            .map(|rect| {
+                [
+                    rect.position.x as i64,
+                    rect.position.y as i64,
+                    rect.size.x as i64,
+                    rect.size.y as i64,
+                ]
+            })
+            .collect()
+    }
+
+    /// The widths of the columns, from a row of one-column items.
+    fn widths(columns: Vec<TrackSize>, width: usize, items: &[usize]) -> Vec<i64> {
+        let container = GridContainer {
+            columns,
+            rows: vec![TrackSize::Cells(1)],
+            ..GridContainer::default()
+        };
+        let children = items
+            .iter()
+            .map(|&width| auto(width, 1))
+            .collect::<Vec<_>>();
+        boxes(&area(width, 1), &container, &children)
+            .iter()
+            .map(|rect| rect[2])
+            .collect()
+    }
+
+    #[test]
+    fn fixed_tracks() {
+        let container = GridContainer {
+            columns: vec![TrackSize::Cells(3), TrackSize::Cells(5)],
+            rows: vec![TrackSize::Cells(2), TrackSize::Cells(1)],
+            column_gap: 1,
+            row_gap: 1,
+            ..GridContainer::default()
+        };
+        let placed = Rect {
+            position: Vec2::new(2, 1),
+            size: Vec2::new(20, 10),
+        };
+        // Placed items are put first; the others fill the free cells in reading order.
+        let children = [
+            auto(1, 1),
+            at(GridPlacement::at(0), GridPlacement::at(0)),
+            auto(1, 1),
+        ];
+        assert_eq!(
+            boxes(&placed, &container, &children),
+            [[6, 1, 5, 2], [2, 1, 3, 2], [2, 4, 3, 1]]
+        );
+    }
+
+    #[test]
+    fn fraction_tracks() {
+        let fr = TrackSize::Fraction;
+        assert_eq!(
+            widths(vec![TrackSize::Cells(2), fr(1.0), fr(3.0)], 14, &[1, 1, 1]),
+            [2, 3, 9]
+        );
+        // Content does not make fraction tracks any wider.
+        assert_eq!(widths(vec![fr(1.0), fr(1.0)], 10, &[8, 1]), [5, 5]);
+        // With no space left, fractions get nothing.
+        assert_eq!(
+            widths(vec![TrackSize::Cells(6), fr(1.0)], 4, &[1, 1]),
+            [6, 0]
+        );
+
+        let gapped = GridContainer {
+            columns: vec![fr(1.0), fr(1.0)],
+            column_gap: 1,
+            ..GridContainer::default()
+        };
+        assert_eq!(
+            boxes(&area(9, 1), &gapped, &[auto(1, 1), auto(1, 1)]),
+            [[0, 0, 4, 1], [5, 0, 4, 1]]
+        );
+    }
+
+    #[test]
+    fn auto_tracks() {
+        let tracks = || vec![TrackSize::Auto, TrackSize::Auto, TrackSize::Cells(2)];
+        assert_eq!(widths(tracks(), 7, &[4, 1, 1]), [4, 1, 2]);
+        // Leftover space stretches the auto tracks evenly.
+        assert_eq!(widths(tracks(), 11, &[4, 1, 1]), [6, 3, 2]);
+        // But fractions take it first.
+        assert_eq!(
+            widths(vec![TrackSize::Auto, TrackSize::Fraction(1.0)], 10, &[3, 1]),
+            [3, 7]
+        );
+        assert_eq!(
+            widths(
+                vec![
+                    TrackSize::MinMax(2, Box::new(TrackSize::Cells(4))),
+                    TrackSize::Fraction(1.0)
+                ],
+                10,
+                &[1, 1]
+            ),
+            [4, 6]
+        );
+
+        // Items spanning several tracks do not size auto tracks, so the
+        // columns only make room for the narrow item and share the rest.
+        let container = GridContainer {
+            columns: vec![TrackSize::Auto, TrackSize::Auto],
+            rows: vec![TrackSize::Cells(1), TrackSize::Cells(1)],
+            ..GridContainer::default()
+        };
+        let wide = (
+            GridItem {
+                column: GridPlacement::spanning(0, 2),
+                ..GridItem::default()
+            },
+            Vec2::new(9, 1),
+        );
+        assert_eq!(
+            boxes(&area(4, 2), &container, &[wide, auto(3, 1)]),
+            [[0, 0, 4, 1], [0, 1, 4, 1]]
+        );
+    }
+
+    #[test]
+    fn named_areas() {
+        let container = GridContainer {
+            columns: vec![TrackSize::Cells(3), TrackSize::Fraction(1.0)],
+            rows: vec![
+                TrackSize::Cells(1),
+                TrackSize::Fraction(1.0),
+                TrackSize::Cells(1),
+            ],
+            areas: vec![
+                "head head".to_string(),
+                "side main".to_string(),
+                "foot foot".to_string(),
+            ],
+            ..GridContainer::default()
+        };
+        let children = [named("main"), named("head"), named("side"), named("foot")];
+        assert_eq!(
+            boxes(&area(10, 8), &container, &children),
+            [[3, 1, 7, 6], [0, 0, 10, 1], [0, 1, 3, 6], [0, 7, 10, 1]]
+        );
+
+        // Areas win over placements; unknown ones are placed automatically.
+        let areas = GridContainer {
+            areas: vec!["a .".to_string(), ". b".to_string()],
+            columns: vec![TrackSize::Cells(2), TrackSize::Cells(2)],
+            rows: vec![TrackSize::Cells(1), TrackSize::Cells(1)],
+            ..GridContainer::default()
+        };
+        let mut b = named("b");
+        b.0.column = GridPlacement::at(0);
+        b.0.row = GridPlacement::at(0);
+        assert_eq!(
+            boxes(&area(4, 2), &areas, &[b, named("missing"), named("a")]),
+            [[2, 1, 2, 1], [2, 0, 2, 1], [0, 0, 2, 1]]
+        );
+    }
+
+    #[test]
+    fn spans_past_the_last_track() {
+        let container = GridContainer {
+            columns: vec![TrackSize::Cells(3), TrackSize::Cells(3)],
+            rows: vec![TrackSize::Cells(1)],
+            ..GridContainer::default()
+        };
+        // The missing tracks are added as auto tracks, which take the leftover space.
+        let children = [at(GridPlacement::spanning(1, 3), GridPlacement::at(0))];
+        assert_eq!(boxes(&area(10, 1), &container, &children), [[3, 0, 7, 1]]);
+
+        // Automatically placed items never span more columns than there are.
+        let wide = (
+            GridItem {
+                column: GridPlacement {
+                    start: None,
+                    span: 5,
+                },
+                ..GridItem::default()
+            },
+            Vec2::new(1, 1),
+        );
+        assert_eq!(boxes(&area(10, 1), &container, &[wide]), [[0, 0, 6, 1]]);
+
+        // Rows past the template are added too, sized to their content.
+        let children = [
+            at(GridPlacement::at(0), GridPlacement::spanning(0, 3)),
+            auto(1, 1),
+        ];
+        assert_eq!(
+            boxes(&area(6, 5), &container, &children),
+            [[0, 0, 3, 5], [3, 0, 3, 1]]
+        );
+    }
+}
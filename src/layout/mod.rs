@@ -0,0 +1,42 @@
+pub mod flex;
+pub mod grid;
+pub mod split;
+pub mod units;
+
+/// How a box positions its children, like CSS `display`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Display {
+    /// Children are drawn at their own `position` and `size`.
+    #[default]
+    Block,
+    Flex(flex::FlexContainer),
+    Grid(grid::GridContainer),
+}
+
+/// Splits `total` cells in proportion to `weights`, handing the cells lost to
+/// rounding to the largest remainders so the parts always add up to `total`.
+pub fn distribute(total: usize, weights: &[f32]) -> Vec<usize> {
+    let sum = weights.iter().sum::<f32>();
+    if weights.is_empty() || sum <= 0.0 {
+        return vec![0; weights.len()];
+    }
+
+    let exact = weights
+        .iter()
+        .map(|w| total as f32 * w / sum)
+        .collect::<Vec<_>>();
+    let mut parts = exact.iter().map(|e| e.floor() as usize).collect::<Vec<_>>();
+
+    let mut order = (0..weights.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| {
+        let (ra, rb) = (exact[a] - exact[a].floor(), exact[b] - exact[b].floor());
+        rb.total_cmp(&ra).then(a.cmp(&b))
+    });
+
+    let remaining = total.saturating_sub(parts.iter().sum());
+    for &i in order.iter().cycle().take(remaining) {
+        parts[i] += 1;
+    }
+
+    parts
+}
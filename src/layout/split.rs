@@ -0,0 +1,414 @@
+use crate::common::{Rect, Vec2};
+use crate::layout::distribute;
+
+/// Smallest share of a split either side can be resized down to.
+const MIN_RATIO: f32 = 0.05;
+
+/// How a split lays out its two sides, named after the flex directions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SplitDirection {
+    /// Side by side, with a vertical divider between them.
+    Row,
+    /// One above the other, with a horizontal divider between them.
+    Column,
+}
+
+/// A tree of panes filling an area. Each leaf holds the id of a pane; each
+/// split gives `ratio` of its area, less the divider, to its first side.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Split {
+    Pane(usize),
+    Split {
+        direction: SplitDirection,
+        ratio: f32,
+        first: Box<Split>,
+        second: Box<Split>,
+    },
+}
+
+/// A line of cells between two sides of a split.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divider {
+    pub position: Vec2<i16>,
+    pub length: usize,
+    /// The direction of the split it divides. Row splits have vertical dividers.
+    pub direction: SplitDirection,
+}
+
+impl Split {
+    /// Ids of the panes in the tree, from the top left.
+    pub fn panes(&self) -> Vec<usize> {
+        match self {
+            Split::Pane(id) => vec![*id],
+            Split::Split { first, second, .. } => {
+                let mut panes = first.panes();
+                panes.extend(second.panes());
+                panes
+            }
+        }
+    }
+
+    /// Replaces the pane `id` with a split holding it and `new` after it, in
+    /// equal halves. Returns false when there is no such pane.
+    pub fn split(&mut self, id: usize, direction: SplitDirection, new: usize) -> bool {
+        match self {
+            Split::Pane(pane) if *pane == id => {
+                *self = Split::Split {
+                    direction,
+                    ratio: 0.5,
+                    first: Box::new(Split::Pane(id)),
+                    second: Box::new(Split::Pane(new)),
+                };
+                true
+            }
+            Split::Pane(_) => false,
+            Split::Split { first, second, .. } => {
+                first.split(id, direction, new) || second.split(id, direction, new)
+            }
+        }
+    }
+
+    /// Removes the pane `id`, giving its room to the other side of its split.
+    /// The last pane cannot be removed.
+    pub fn remove(&mut self, id: usize) -> bool {
+        let Split::Split { first, second, .. } = self else {
+            return false;
+        };
+
+        if **first == Split::Pane(id) {
+            *self = std::mem::replace(second, Split::Pane(0));
+            true
+        } else if **second == Split::Pane(id) {
+            *self = std::mem::replace(first, Split::Pane(0));
+            true
+        } else {
+            first.remove(id) || second.remove(id)
+        }
+    }
+
+    /// Where each pane goes in `area` and the dividers drawn between them.
+    pub fn layout(&self, area: &Rect) -> (Vec<(usize, Rect)>, Vec<Divider>) {
+        let mut panes = Vec::new();
+        let mut dividers = Vec::new();
+        self.place(area, &mut panes, &mut dividers);
+        (panes, dividers)
+    }
+
+    fn place(&self, area: &Rect, panes: &mut Vec<(usize, Rect)>, dividers: &mut Vec<Divider>) {
+        match self {
+            Split::Pane(id) => panes.push((*id, area.clone())),
+            Split::Split {
+                direction,
+                ratio,
+                first,
+                second,
+            } => {
+                let (first_area, divider, second_area) = divide(area, *direction, *ratio);
+                first.place(&first_area, panes, dividers);
+                dividers.push(divider);
+                second.place(&second_area, panes, dividers);
+            }
+        }
+    }
+
+    /// Moves the divider of the innermost split around `id` that goes in
+    /// `direction` by `cells`, towards the end of the area when positive.
+    /// Returns false when the pane is in no such split.
+    pub fn resize(
+        &mut self,
+        id: usize,
+        direction: SplitDirection,
+        cells: isize,
+        area: &Rect,
+    ) -> bool {
+        let Split::Split {
+            direction: split_direction,
+            ratio,
+            first,
+            second,
+        } = self
+        else {
+            return false;
+        };
+
+        let (first_area, _, second_area) = divide(area, *split_direction, *ratio);
+        let (inner, inner_area) = match first.panes().contains(&id) {
+            true => (first, first_area.clone()),
+            false => (second, second_area),
+        };
+        if inner.resize(id, direction, cells, &inner_area) {
+            return true;
+        }
+        if *split_direction != direction || !self.panes().contains(&id) {
+            return false;
+        }
+
+        let Split::Split { ratio, .. } = self else {
+            return false;
+        };
+        let (before, total) = match direction {
+            SplitDirection::Row => (first_area.size.x, area.size.x),
+            SplitDirection::Column => (first_area.size.y, area.size.y),
+        };
+        let room = total.saturating_sub(1).max(1) as f32;
+        *ratio = ((before as isize + cells) as f32 / room).clamp(MIN_RATIO, 1.0 - MIN_RATIO);
+        true
+    }
+}
+
+/// Splits `area` into the first side, the divider and the second side.
+fn divide(area: &Rect, direction: SplitDirection, ratio: f32) -> (Rect, Divider, Rect) {
+    let total = match direction {
+        SplitDirection::Row => area.size.x,
+        SplitDirection::Column => area.size.y,
+    };
+    let parts = distribute(total.saturating_sub(1), &[ratio, 1.0 - ratio]);
+    let (before, after) = (parts[0], parts[1]);
+    let position = &area.position;
+
+    match direction {
+        SplitDirection::Row => (
+            Rect {
+                position: position.clone(),
+                size: Vec2::new(before, area.size.y),
+            },
+            Divider {
+                position: Vec2::new(position.x + before as i16, position.y),
+                length: area.size.y,
+                direction,
+            },
+            Rect {
+                position: Vec2::new(position.x + before as i16 + 1, position.y),
+                size: Vec2::new(after, area.size.y),
+            },
+        ),
+        SplitDirection::Column => (
+            Rect {
+                position: position.clone(),
+                size: Vec2::new(area.size.x, before),
+            },
+            Divider {
+                position: Vec2::new(position.x, position.y + before as i16),
+                length: area.size.x,
+                direction,
+            },
+            Rect {
+                position: Vec2::new(position.x, position.y + before as i16 + 1),
+                size: Vec2::new(area.size.x, after),
+            },
+        ),
+    }
+}
+
+/// The pane next to `from` in the direction of `step`, such as (1, 0) for the
+/// one to the right. Of the panes on that side overlapping it, the closest wins.
+pub fn neighbour(panes: &[(usize, Rect)], from: usize, step: (i16, i16)) -> Option<usize> {
+    let (_, area) = panes.iter().find(|(id, _)| *id == from)?;
+    let span = |start: i16, length: usize| (start, start + length as i16);
+    let overlaps = |a: (i16, i16), b: (i16, i16)| a.0 < b.1 && b.0 < a.1;
+
+    let (from_x, from_y) = (
+        span(area.position.x, area.size.x),
+        span(area.position.y, area.size.y),
+    );
+
+    panes
+        .iter()
+        .filter(|(id, _)| *id != from)
+        .filter_map(|(id, rect)| {
+            let (x, y) = (
+                span(rect.position.x, rect.size.x),
+                span(rect.position.y, rect.size.y),
+            );
+            let distance = match step {
+                (1, 0) if overlaps(y, from_y) => x.0 - from_x.1,
+                (-1, 0) if overlaps(y, from_y) => from_x.0 - x.1,
+                (0, 1) if overlaps(x, from_x) => y.0 - from_y.1,
+                (0, -1) if overlaps(x, from_x) => from_y.0 - y.1,
+                _ => return None,
+            };
+            (distance >= 0).then_some((distance, *id))
+        })
+        .min()
+        .map(|(_, id)| id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area(width: usize, height: usize) -> Rect {
+        Rect {
+            position: Vec2::new(0, 0),
+            size: Vec2::new(width, height),
+        }
+    }
+
+    /// Each pane as `(id, [x, y, width, height])`.
+    fn boxes(split: &Split, area: &Rect) -> Vec<(usize, [i64; 4])> {
+        split
+            .layout(area)
+            .0
+            .iter()
+            .map(|(id, rect)| {
+                let rect = [
+                    rect.position.x as i64,
+                    rect.position.y as i64,
+                    rect.size.x as i64,
+                    rect.size.y as i64,
+                ];
+                (*id, rect)
+            })
+            .collect()
+    }
+
+    fn halves(direction: SplitDirection) -> Split {
+        let mut split = Split::Pane(1);
+        assert!(split.split(1, direction, 2));
+        split
+    }
+
+    #[test]
+    fn splits_by_ratio() {
+        let split = halves(SplitDirection::Row);
+        let (_, dividers) = split.layout(&area(21, 5));
+        assert_eq!(
+            boxes(&split, &area(21, 5)),
+            [(1, [0, 0, 10, 5]), (2, [11, 0, 10, 5])]
+        );
+        assert_eq!(
+            dividers,
+            [Divider {
+                position: Vec2::new(10, 0),
+                length: 5,
+                direction: SplitDirection::Row,
+            }]
+        );
+
+        let uneven = Split::Split {
+            direction: SplitDirection::Column,
+            ratio: 0.3,
+            first: Box::new(Split::Pane(1)),
+            second: Box::new(Split::Pane(2)),
+        };
+        let placed = Rect {
+            position: Vec2::new(4, 2),
+            size: Vec2::new(6, 11),
+        };
+        assert_eq!(
+            boxes(&uneven, &placed),
+            [(1, [4, 2, 6, 3]), (2, [4, 6, 6, 7])]
+        );
+        assert_eq!(uneven.layout(&placed).1[0].position, Vec2::new(4, 5));
+    }
+
+    #[test]
+    fn nests() {
+        let mut split = halves(SplitDirection::Row);
+        assert!(split.split(2, SplitDirection::Column, 3));
+        assert!(!split.split(9, SplitDirection::Column, 4));
+        assert_eq!(split.panes(), [1, 2, 3]);
+
+        let (panes, dividers) = split.layout(&area(21, 11));
+        assert_eq!(
+            boxes(&split, &area(21, 11)),
+            [
+                (1, [0, 0, 10, 11]),
+                (2, [11, 0, 10, 5]),
+                (3, [11, 6, 10, 5])
+            ]
+        );
+        let dividers = dividers
+            .iter()
+            .map(|divider| (divider.position.clone(), divider.length, divider.direction))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dividers,
+            [
+                (Vec2::new(10, 0), 11, SplitDirection::Row),
+                (Vec2::new(11, 5), 10, SplitDirection::Column)
+            ]
+        );
+
+        assert_eq!(neighbour(&panes, 1, (1, 0)), Some(2));
+        assert_eq!(neighbour(&panes, 3, (0, -1)), Some(2));
+        assert_eq!(neighbour(&panes, 3, (-1, 0)), Some(1));
+        assert_eq!(neighbour(&panes, 1, (-1, 0)), None);
+
+        // The other side of the removed pane takes its room.
+        assert!(split.remove(2));
+        assert_eq!(
+            boxes(&split, &area(21, 11)),
+            [(1, [0, 0, 10, 11]), (3, [11, 0, 10, 11])]
+        );
+        assert!(split.remove(1));
+        assert!(!split.remove(3));
+        assert_eq!(split, Split::Pane(3));
+    }
+
+    #[test]
+    fn resizes() {
+        let area = area(21, 5);
+        let mut split = halves(SplitDirection::Row);
+        assert!(split.resize(1, SplitDirection::Row, 3, &area));
+        assert_eq!(
+            boxes(&split, &area),
+            [(1, [0, 0, 13, 5]), (2, [14, 0, 7, 5])]
+        );
+        assert!(!split.resize(1, SplitDirection::Column, 3, &area));
+        assert!(!Split::Pane(1).resize(1, SplitDirection::Row, 3, &area));
+
+        // Neither side can be resized away entirely.
+        assert!(split.resize(2, SplitDirection::Row, -100, &area));
+        assert_eq!(boxes(&split, &area)[0].1[2], 1);
+        assert!(split.resize(1, SplitDirection::Row, 100, &area));
+        assert_eq!(boxes(&split, &area)[1].1[2], 1);
+    }
+
+    #[test]
+    fn resizes_the_innermost_split() {
+        let area = area(21, 11);
+        let mut split = halves(SplitDirection::Row);
+        split.split(2, SplitDirection::Column, 3);
+
+        assert!(split.resize(3, SplitDirection::Column, -2, &area));
+        assert_eq!(
+            boxes(&split, &area)[1..],
+            [(2, [11, 0, 10, 3]), (3, [11, 4, 10, 7])]
+        );
+        // No column split holds pane 1, but the row split around it moves.
+        assert!(split.resize(3, SplitDirection::Row, -2, &area));
+        assert_eq!(
+            boxes(&split, &area),
+            [(1, [0, 0, 8, 11]), (2, [9, 0, 12, 3]), (3, [9, 4, 12, 7])]
+        );
+        assert!(!split.resize(1, SplitDirection::Column, 1, &area));
+    }
+
+    #[test]
+    fn zero_sized_areas() {
+        let mut row = halves(SplitDirection::Row);
+        assert_eq!(
+            boxes(&row, &area(0, 3)),
+            [(1, [0, 0, 0, 3]), (2, [1, 0, 0, 3])]
+        );
+        assert_eq!(
+            boxes(&row, &area(1, 3)),
+            [(1, [0, 0, 0, 3]), (2, [1, 0, 0, 3])]
+        );
+        assert!(row.resize(1, SplitDirection::Row, 5, &area(0, 3)));
+        assert_eq!(
+            boxes(&row, &area(21, 3)),
+            [(1, [0, 0, 19, 3]), (2, [20, 0, 1, 3])]
+        );
+
+        let column = halves(SplitDirection::Column);
+        let (panes, dividers) = column.layout(&area(4, 0));
+        assert!(panes.iter().all(|(_, rect)| rect.size == Vec2::new(4, 0)));
+        assert_eq!(dividers[0].length, 4);
+        assert_eq!(
+            boxes(&column, &area(0, 5)),
+            [(1, [0, 0, 0, 2]), (2, [0, 3, 0, 2])]
+        );
+    }
+}
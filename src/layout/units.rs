@@ -0,0 +1,317 @@
+use crate::common::{Rect, Vec2};
+
+/// A length measured against the box's parent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Length {
+    Cells(usize),
+    /// Percent of the parent's content area, from 0.0 to 100.0.
+    Percent(f32),
+    /// Whatever the parent has left. As a size, this is the parent's content
+    /// area minus the box's offsets; as an offset, it pushes the box against
+    /// the opposite edge.
+    Fill,
+}
+
+impl Length {
+    fn resolve(&self, parent: usize) -> usize {
+        match self {
+            Length::Cells(cells) => *cells,
+            Length::Percent(percent) => (parent as f32 * percent / 100.0).round().max(0.0) as usize,
+            Length::Fill => parent,
+        }
+    }
+}
+
+/// Relative sizes, constraints and anchors of a box, like CSS `width`,
+/// `min-width`, `max-width` and `left`/`right`/`top`/`bottom` on a positioned
+/// element. Anything left unset falls back to the box's absolute `position` and `size`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Sizing {
+    pub width: Option<Length>,
+    pub height: Option<Length>,
+
+    pub min_width: Option<usize>,
+    pub max_width: Option<usize>,
+    pub min_height: Option<usize>,
+    pub max_height: Option<usize>,
+
+    /// Offsets from the edges of the parent's content area.
+    pub left: Option<Length>,
+    pub right: Option<Length>,
+    pub top: Option<Length>,
+    pub bottom: Option<Length>,
+}
+
+struct Axis {
+    start: Option<Length>,
+    end: Option<Length>,
+    length: Option<Length>,
+    min: Option<usize>,
+    max: Option<usize>,
+}
+
+fn clamp(length: usize, min: Option<usize>, max: Option<usize>) -> usize {
+    let length = max.map_or(length, |max| length.min(max));
+    min.map_or(length, |min| length.max(min))
+}
+
+impl Axis {
+    fn offset(&self, edge: Option<Length>, parent: usize) -> usize {
+        match edge {
+            Some(Length::Fill) | None => 0,
+            Some(length) => length.resolve(parent),
+        }
+    }
+
+    fn length(&self, parent: usize, fallback: usize) -> usize {
+        let insets = self.offset(self.start, parent) + self.offset(self.end, parent);
+
+        let length = match (self.length, self.start, self.end) {
+            (Some(Length::Fill), _, _) => parent.saturating_sub(insets),
+            (Some(length), _, _) => length.resolve(parent),
+            // Anchored to both edges, the box spans the space between them.
+            (None, Some(start), Some(end)) if start != Length::Fill && end != Length::Fill => {
+                parent.saturating_sub(insets)
+            }
+            (None, _, _) => fallback,
+        };
+
+        clamp(length, self.min, self.max)
+    }
+
+    fn resolve(&self, parent_start: i16, parent: usize, fallback: (i16, usize)) -> (i16, usize) {
+        let length = self.length(parent, fallback.1);
+        let far = |inset: usize| parent_start + parent.saturating_sub(inset + length) as i16;
+
+        let start = match (self.start, self.end) {
+            (Some(Length::Fill), end) => far(self.offset(end, parent)),
+            (Some(start), _) => parent_start + start.resolve(parent) as i16,
+            (None, Some(Length::Fill)) => parent_start,
+            (None, Some(end)) => far(end.resolve(parent)),
+            (None, None) => fallback.0,
+        };
+
+        (start, length)
+    }
+}
+
+impl Sizing {
+    fn horizontal(&self) -> Axis {
+        Axis {
+            start: self.left,
+            end: self.right,
+            length: self.width,
+            min: self.min_width,
+            max: self.max_width,
+        }
+    }
+
+    fn vertical(&self) -> Axis {
+        Axis {
+            start: self.top,
+            end: self.bottom,
+            length: self.height,
+            min: self.min_height,
+            max: self.max_height,
+        }
+    }
+
+    /// Where a box goes inside `parent`, given its own absolute `position` and `size`.
+    pub fn resolve(&self, parent: &Rect, position: &Vec2<i16>, size: &Vec2) -> Rect {
+        let (x, width) =
+            self.horizontal()
+                .resolve(parent.position.x, parent.size.x, (position.x, size.x));
+        let (y, height) =
+            self.vertical()
+                .resolve(parent.position.y, parent.size.y, (position.y, size.y));
+
+        Rect {
+            position: Vec2::new(x, y),
+            size: Vec2::new(width, height),
+        }
+    }
+
+    /// The size of a box inside a parent area of `parent`, ignoring anchors.
+    /// Used for children of flex and grid containers, which are positioned by the layout.
+    pub fn resolve_size(&self, parent: &Vec2, size: &Vec2) -> Vec2 {
+        Vec2::new(
+            self.horizontal().length(parent.x, size.x),
+            self.vertical().length(parent.y, size.y),
+        )
+    }
+
+    /// Applies the min and max constraints to `size`.
+    pub fn clamp(&self, size: &Vec2) -> Vec2 {
+        Vec2::new(
+            clamp(size.x, self.min_width, self.max_width),
+            clamp(size.y, self.min_height, self.max_height),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 20 by 10 parent whose content area starts at (2, 1).
+    fn parent() -> Rect {
+        Rect {
+            position: Vec2::new(2, 1),
+            size: Vec2::new(20, 10),
+        }
+    }
+
+    /// Where a box with `sizing` goes across the parent, as `(x, width)`.
+    fn across(sizing: Sizing) -> (i16, usize) {
+        let rect = sizing.resolve(&parent(), &Vec2::new(5, 5), &Vec2::new(4, 4));
+        (rect.position.x, rect.size.x)
+    }
+
+    #[test]
+    fn rounds_percentages() {
+        let width = |percent: f32| {
+            across(Sizing {
+                width: Some(Length::Percent(percent)),
+                ..Sizing::default()
+            })
+            .1
+        };
+
+        assert_eq!(width(50.0), 10);
+        assert_eq!(width(33.3), 7);
+        assert_eq!(width(12.5), 3);
+        assert_eq!(width(12.4), 2);
+        assert_eq!(width(0.0), 0);
+        assert_eq!(width(150.0), 30);
+        assert_eq!(width(-20.0), 0);
+
+        // Heights are measured against the parent's height.
+        let sizing = Sizing {
+            height: Some(Length::Percent(25.0)),
+            ..Sizing::default()
+        };
+        let size = sizing.resolve_size(&Vec2::new(20, 10), &Vec2::new(4, 4));
+        assert_eq!((size.x, size.y), (4, 3));
+    }
+
+    #[test]
+    fn falls_back_to_the_absolute_position() {
+        let rect = Sizing::default().resolve(&parent(), &Vec2::new(-3, 40), &Vec2::new(4, 6));
+        assert_eq!(rect.position, Vec2::new(-3, 40));
+        assert_eq!(rect.size, Vec2::new(4, 6));
+    }
+
+    #[test]
+    fn anchors() {
+        let cells = |cells| Some(Length::Cells(cells));
+
+        // Anchored to both edges, the box spans the space between them.
+        let both = Sizing {
+            left: cells(0),
+            right: cells(0),
+            ..Sizing::default()
+        };
+        assert_eq!(across(both), (2, 20));
+        let inset = Sizing {
+            left: cells(3),
+            right: cells(5),
+            ..Sizing::default()
+        };
+        assert_eq!(across(inset), (5, 12));
+
+        let right = Sizing {
+            right: cells(0),
+            width: cells(5),
+            ..Sizing::default()
+        };
+        assert_eq!(across(right), (17, 5));
+        let pushed = Sizing {
+            left: Some(Length::Fill),
+            right: cells(1),
+            width: cells(5),
+            ..Sizing::default()
+        };
+        assert_eq!(across(pushed), (16, 5));
+        let far_edge = Sizing {
+            left: Some(Length::Fill),
+            ..Sizing::default()
+        };
+        assert_eq!(across(far_edge), (18, 4));
+        let near_edge = Sizing {
+            right: Some(Length::Fill),
+            ..Sizing::default()
+        };
+        assert_eq!(across(near_edge), (2, 4));
+
+        let filled = Sizing {
+            top: cells(0),
+            bottom: cells(2),
+            height: Some(Length::Fill),
+            ..Sizing::default()
+        };
+        let rect = filled.resolve(&parent(), &Vec2::new(0, 0), &Vec2::new(1, 1));
+        assert_eq!((rect.position.y, rect.size.y), (1, 8));
+    }
+
+    #[test]
+    fn negative_and_oversized_offsets() {
+        let cells = |cells| Some(Length::Cells(cells));
+
+        // Negative percentages count as no offset at all.
+        let negative = Sizing {
+            left: Some(Length::Percent(-50.0)),
+            width: Some(Length::Percent(-10.0)),
+            min_width: Some(3),
+            ..Sizing::default()
+        };
+        assert_eq!(across(negative), (2, 3));
+
+        // Boxes may be pushed past the far edge, but never past the near one.
+        let past_far = Sizing {
+            left: cells(30),
+            width: cells(5),
+            ..Sizing::default()
+        };
+        assert_eq!(across(past_far), (32, 5));
+        let past_near = Sizing {
+            right: cells(30),
+            width: cells(5),
+            ..Sizing::default()
+        };
+        assert_eq!(across(past_near), (2, 5));
+        let overlapping = Sizing {
+            left: cells(15),
+            right: cells(15),
+            ..Sizing::default()
+        };
+        assert_eq!(across(overlapping), (17, 0));
+        let filled = Sizing {
+            left: Some(Length::Percent(125.0)),
+            width: Some(Length::Fill),
+            ..Sizing::default()
+        };
+        assert_eq!(across(filled), (27, 0));
+    }
+
+    #[test]
+    fn constraints() {
+        let sizing = Sizing {
+            width: Some(Length::Fill),
+            max_width: Some(12),
+            min_height: Some(5),
+            max_height: Some(8),
+            ..Sizing::default()
+        };
+        assert_eq!(across(sizing.clone()), (5, 12));
+        assert_eq!(sizing.clamp(&Vec2::new(30, 2)), Vec2::new(12, 5));
+        assert_eq!(sizing.clamp(&Vec2::new(3, 9)), Vec2::new(3, 8));
+
+        // The minimum wins when the two disagree.
+        let backwards = Sizing {
+            min_width: Some(6),
+            max_width: Some(4),
+            ..Sizing::default()
+        };
+        assert_eq!(across(backwards), (5, 6));
+    }
+}
@@ -0,0 +1,9 @@
+pub mod browser;
+pub mod common;
+pub mod config;
+pub mod draw;
+pub mod html;
+pub mod layout;
+pub mod net;
+pub mod paths;
+pub mod window;
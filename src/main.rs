@@ -1,83 +1,1338 @@
 extern crate termion;
 
+use std::collections::HashMap;
 use std::io::{Write, stdin};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::time::Duration;
+use std::{fs, thread};
+use termion::event::{Event, Key, MouseButton, MouseEvent};
 use termion::input::TermRead;
 
-mod common;
-mod draw;
-mod window;
+use hyperline::browser::bookmarks::{self, Bookmark, Bookmarks};
+use hyperline::browser::fetch::Fetcher;
+use hyperline::browser::find::FindOptions;
+use hyperline::browser::form::{Control, ControlKind};
+use hyperline::browser::hints::{self, Hint, Target};
+use hyperline::browser::history::{History, Visit};
+use hyperline::browser::input::{InputEvent, LineInput};
+use hyperline::browser::session::Session;
+use hyperline::common::{self, Rect};
+use hyperline::config::Config;
+use hyperline::config::keys::Action;
+use hyperline::draw::border::BorderFlags;
+use hyperline::draw::content::Padding;
+use hyperline::draw::text::{Span, StyledLine, TextAttributes, TextStyle};
+use hyperline::draw::theme::{Role, THEMES, Theme};
+use hyperline::layout::split::SplitDirection;
+use hyperline::net::cache::HttpCache;
+use hyperline::net::cookies::CookieJar;
+use hyperline::net::date;
+use hyperline::paths;
+use hyperline::window::{self, Container};
 
-use draw::border::BorderFlags;
-use window::Container;
+/// How many visited pages are offered at most when typing a URL.
+const MAX_COMPLETIONS: usize = 8;
 
-fn main() {
-    let stdin = stdin();
+/// What keys are currently doing.
+enum Mode {
+    Browse,
+    /// Typing a URL to open, with the visited pages it could be.
+    Open {
+        input: LineInput,
+        /// Whether to open it in a new tab.
+        new_tab: bool,
+        /// What was typed, for going back to after choosing a completion.
+        typed: String,
+        completions: Vec<Visit>,
+        /// The completion chosen with the arrow keys, which is in the input.
+        selected: Option<usize>,
+    },
+    /// Typing the tags to bookmark the current page with.
+    Bookmark(LineInput),
+    /// Typing into the form control with this index.
+    Edit(usize, LineInput),
+    /// Choosing from the `<select>` with this index. The second number is
+    /// the highlighted option.
+    Choose(usize, usize),
+    /// Typing the label of a link or control to use, with what has been typed so far.
+    Hint(Vec<Hint>, String),
+    /// Typing what to find on the page, which is searched as it changes.
+    Find {
+        input: LineInput,
+        options: FindOptions,
+        /// Where the page was scrolled to before, for going back on Esc.
+        origin: usize,
+        error: Option<String>,
+    },
+}
+
+impl Mode {
+    /// The URL prompt, filled with `text`.
+    fn open(text: impl Into<String>, new_tab: bool) -> Mode {
+        let input = LineInput::new(text);
+        Mode::Open {
+            typed: input.text.clone(),
+            input,
+            new_tab,
+            completions: Vec::new(),
+            selected: None,
+        }
+    }
+}
+
+/// What the event loop waits for.
+enum Input {
+    Terminal(std::io::Result<Event>),
+    /// The terminal changed to this size.
+    Resized(common::Vec2),
+    /// The config file was saved.
+    ConfigChanged,
+}
+
+/// The size of the terminal in cells.
+fn terminal_size() -> std::io::Result<common::Vec2> {
+    termion::terminal_size().map(|(x, y)| common::Vec2::new(x as usize, y as usize))
+}
+
+/// Sends `Input::Resized` whenever the terminal changes size, checking ten
+/// times a second, until the event loop is gone.
+fn watch_size(mut last: common::Vec2, sender: Sender<Input>) {
+    loop {
+        thread::sleep(Duration::from_millis(100));
+        let Ok(current) = terminal_size() else {
+            continue;
+        };
+        if current != last {
+            last = current.clone();
+            if sender.send(Input::Resized(current)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Sends `Input::ConfigChanged` whenever `file` is modified, checking once a
+/// second, until the event loop is gone.
+fn watch_config(file: PathBuf, sender: Sender<Input>) {
+    let modified = |file: &PathBuf| fs::metadata(file).and_then(|m| m.modified()).ok();
+    let mut last = modified(&file);
+
+    loop {
+        thread::sleep(Duration::from_secs(1));
+        let current = modified(&file);
+        if current != last {
+            last = current;
+            if sender.send(Input::ConfigChanged).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Reads the config again after it was saved, keeping the old one if the
+/// new one has errors. Returns what to tell the user. The homepage, cache
+/// size and features are only read at startup, so they stay as they were,
+/// and the user is told to restart for changes to them.
+fn reload_config(file: &Path, config: &mut Config, window: &mut Container) -> String {
+    match Config::load(file) {
+        Ok(reloaded) => {
+            let changed = [
+                ("homepage", reloaded.homepage != config.homepage),
+                ("cache_size", reloaded.cache_size != config.cache_size),
+                ("features", reloaded.features != config.features),
+            ]
+            .into_iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+
+            *config = Config {
+                homepage: config.homepage.clone(),
+                cache_size: config.cache_size,
+                features: config.features.clone(),
+                ..reloaded
+            };
+            window.tab_style = config.tab_border.clone();
+            window.divider_style = config.divider_border.clone();
+            window.set_theme(config.theme.clone());
+
+            match changed[..] {
+                [] => "Reloaded the config.".to_string(),
+                [name] => format!("Reloaded the config. Restart hyperline to apply {}.", name),
+                [ref names @ .., last] => format!(
+                    "Reloaded the config. Restart hyperline to apply {} and {}.",
+                    names.join(", "),
+                    last
+                ),
+            }
+        }
+        // Each problem is on its own line, but they have to fit on the status line.
+        Err(error) => error.replacen(":\n  ", ": ", 1).replace("\n  ", "; "),
+    }
+}
+
+/// The label of the URL prompt.
+fn open_label(new_tab: bool) -> &'static str {
+    match new_tab {
+        true => "Open in a new tab: ",
+        false => "Open: ",
+    }
+}
+
+/// The label of the find prompt, showing which options are turned on.
+fn find_label(options: &FindOptions) -> String {
+    let mut label = "Find".to_string();
+    if options.case_sensitive {
+        label.push_str(" [Aa]");
+    }
+    if options.regex {
+        label.push_str(" [.*]");
+    }
+    label + ": "
+}
+
+/// Where the search on the page is, such as "Match 2 of 5".
+fn find_status(session: &Session) -> String {
+    match session.search() {
+        Some(search) if !search.matches.is_empty() => format!(
+            "Match {} of {}",
+            search.current.map_or(0, |current| current + 1),
+            search.matches.len()
+        ),
+        Some(search) => format!("No matches for '{}'.", search.query),
+        None => "Nothing to find: press / to search.".to_string(),
+    }
+}
+
+/// The text of a prompt, with the cursor drawn in reverse video.
+fn prompt_line(label: &str, input: &LineInput) -> StyledLine {
+    let cursor = TextStyle {
+        attributes: TextAttributes::REVERSE,
+        ..Default::default()
+    };
+
+    let before = input.text.chars().take(input.cursor).collect::<String>();
+    let mut after = input.text.chars().skip(input.cursor);
+    let under = after.next().map_or(" ".to_string(), |c| c.to_string());
+
+    StyledLine::new(vec![
+        Span::plain(label),
+        Span::plain(before),
+        Span::new(under, cursor),
+        Span::plain(after.collect::<String>()),
+    ])
+}
+
+/// The text of a form control being edited, over as many lines as it has,
+/// with the cursor drawn in reverse video.
+fn editor_lines(input: &LineInput, masked: bool) -> Vec<StyledLine> {
+    let cursor = TextStyle {
+        attributes: TextAttributes::REVERSE,
+        ..Default::default()
+    };
+
+    let mut lines = Vec::new();
+    let mut spans = Vec::new();
+    let mut text = String::new();
+
+    for (i, c) in input.text.chars().enumerate() {
+        let c = if masked && c != '\n' { '*' } else { c };
+        if i == input.cursor {
+            spans.push(Span::plain(std::mem::take(&mut text)));
+            spans.push(Span::new(if c == '\n' { ' ' } else { c }, cursor.clone()));
+        } else if c != '\n' {
+            text.push(c);
+        }
+
+        if c == '\n' {
+            spans.push(Span::plain(std::mem::take(&mut text)));
+            lines.push(StyledLine::new(std::mem::take(&mut spans)));
+        }
+    }
 
-    let size = termion::terminal_size().unwrap();
+    spans.push(Span::plain(text));
+    if input.cursor >= input.text.chars().count() {
+        spans.push(Span::new(" ", cursor));
+    }
+    lines.push(StyledLine::new(spans));
+    lines
+}
+
+/// The options of a `<select>`, with the highlighted one in the selection color.
+/// Only as many as fit in `rows` are listed, keeping the highlighted one in view.
+fn option_lines(control: &Control, highlighted: usize, rows: usize) -> Vec<StyledLine> {
+    let multiple = matches!(control.kind, ControlKind::Select { multiple: true });
+    let first = (highlighted + 1).saturating_sub(rows.max(1));
+
+    control
+        .options
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(rows.max(1))
+        .map(|(i, option)| {
+            let mark = match (multiple, option.selected) {
+                (true, true) => "[x] ",
+                (true, false) => "[ ] ",
+                (false, true) => "* ",
+                (false, false) => "  ",
+            };
+            let style = TextStyle {
+                background_role: (i == highlighted).then_some(Role::Selection),
+                ..Default::default()
+            };
+            Span::new(format!("{}{}", mark, option.label), style).into()
+        })
+        .collect()
+}
+
+/// Draws a bordered box in the middle of the page, over what is there.
+fn draw_popup(window: &mut Container, config: &Config, title: &str, mut lines: Vec<StyledLine>) {
+    let heading = TextStyle {
+        attributes: TextAttributes::BOLD,
+        ..Default::default()
+    };
+    lines.insert(0, Span::new(title, heading).into());
+
+    let width = lines
+        .iter()
+        .map(StyledLine::width)
+        .max()
+        .unwrap_or(0)
+        .max(30)
+        + 4;
+    let scene = window.scene_area().size;
+    let width = width.min(scene.x);
+    let height = (lines.len() + 2).min(scene.y.saturating_sub(1));
+
+    window.draw_box(window::DivOptions {
+        id: Some("popup".to_string()),
+
+        position: common::Vec2::new(
+            ((scene.x - width) / 2) as i16,
+            (scene.y.saturating_sub(1 + height) / 2) as i16,
+        ),
+        size: common::Vec2::new(width, height),
+
+        border_options: BorderFlags::ALL,
+        border_style: config.popup_border.clone(),
+        padding: Padding::symmetric(0, 1),
+
+        content: Some(lines),
 
-    let mut window = Container::new(common::Vec2::new(size.0 as usize, size.1 as usize));
+        ..Default::default()
+    });
+}
+
+/// Draws the pages offered for the URL prompt in a box just above it, lined
+/// up with the text typed, with the chosen one in the selection color.
+fn draw_completions(
+    window: &mut Container,
+    config: &Config,
+    completions: &[Visit],
+    selected: Option<usize>,
+    left: usize,
+) {
+    let dim = TextStyle {
+        attributes: TextAttributes::DIM,
+        ..Default::default()
+    };
+
+    let page = page_area(window);
+    let rows = completions.len().min(page.size.y.saturating_sub(2));
+    if rows == 0 {
+        return;
+    }
+    let left = left.saturating_sub(1).min(page.size.x.saturating_sub(12));
+    let room = page.size.x - left;
+
+    let lines = completions
+        .iter()
+        .take(rows)
+        .enumerate()
+        .map(|(i, visit)| {
+            let mut spans = match visit.title.is_empty() {
+                true => vec![Span::plain(visit.url.clone())],
+                false => vec![
+                    Span::plain(format!("{}  ", visit.title)),
+                    Span::new(visit.url.clone(), dim.clone()),
+                ],
+            };
+            if selected == Some(i) {
+                for span in &mut spans {
+                    span.style.background_role = Some(Role::Selection);
+                }
+            }
+            // Long titles and URLs are cut off at the edge of the box.
+            let line = StyledLine::new(spans);
+            StyledLine::from_chars(line.chars().take(room.saturating_sub(4)))
+        })
+        .collect::<Vec<_>>();
+
+    let width = (lines.iter().map(StyledLine::width).max().unwrap_or(0) + 4).min(room);
+    let height = rows + 2;
 
     window.draw_box(window::DivOptions {
-        id: Some("block_border_box".to_string()),
+        id: Some("completions".to_string()),
+
+        position: common::Vec2::new(left as i16, (page.size.y - height) as i16),
+        size: common::Vec2::new(width, height),
+
+        border_options: BorderFlags::ALL,
+        border_style: config.popup_border.clone(),
+        padding: Padding::symmetric(0, 1),
 
+        content: Some(lines),
+
+        ..Default::default()
+    });
+}
+
+/// How a form control is named in the box that edits it.
+fn control_title(control: &Control) -> String {
+    match control.name.is_empty() {
+        true => "Edit".to_string(),
+        false => control.name.clone(),
+    }
+}
+
+/// Draws the labels of the hints that start with what has been typed, each
+/// in a small box over the start of its target.
+fn draw_hints(window: &mut Container, hints: &[Hint], typed: &str) {
+    let typed_style = TextStyle {
+        attributes: TextAttributes::DIM,
+        ..Default::default()
+    };
+    let rest_style = TextStyle {
+        attributes: TextAttributes::BOLD,
+        ..Default::default()
+    };
+
+    // Hints are found on screen, but boxes are placed in the scene of the pane.
+    let origin = window.scene_area().position;
+
+    for hint in hints.iter().filter(|hint| hint.label.starts_with(typed)) {
+        let (done, rest) = hint.label.split_at(typed.len());
+        let width = hint.label.chars().count();
+        let x = hint.position.x.min(window.size.x.saturating_sub(width));
+
+        window.draw_box(window::DivOptions {
+            position: common::Vec2::new(x as i16 - origin.x, hint.position.y as i16 - origin.y),
+            size: common::Vec2::new(width, 1),
+
+            background_role: Some(Role::Selection),
+
+            content: Some(vec![StyledLine::new(vec![
+                Span::new(done, typed_style.clone()),
+                Span::new(rest, rest_style.clone()),
+            ])]),
+
+            ..Default::default()
+        });
+    }
+}
+
+/// Where pages are shown in the scene of a pane: everything above its status line.
+fn page_area(window: &Container) -> Rect {
+    let scene = window.scene_area();
+    Rect {
         position: common::Vec2::new(0, 0),
-        size: common::Vec2::new(size.0 as usize, size.1 as usize),
-
-        border_options: BorderFlags::TOP
-            | BorderFlags::RIGHT
-            | BorderFlags::BOTTOM
-            | BorderFlags::LEFT,
-        border_style: draw::border::BorderStyle::Rounded,
-
-        border_color: Some(common::Color::RED),
-        background_color: Some(common::Color::BLACK),
-        text_color: Some(common::Color::BLUE),
-
-        content: Some(vec![
-            "Hello, World!".to_string(),
-            "Press 'q' to exit.".to_string(),
-        ]),
+        size: common::Vec2::new(scene.size.x, scene.size.y.saturating_sub(1)),
+    }
+}
+
+/// Where pages are shown on screen, for looking at what was drawn there.
+fn page_on_screen(window: &Container) -> Rect {
+    Rect {
+        position: window.scene_area().position,
+        ..page_area(window)
+    }
+}
+
+/// What a tab is called on the tab bar: the title of its page, or its URL.
+fn tab_title(session: &Session) -> String {
+    match session.document() {
+        Some(document) if !document.title.is_empty() => document.title.clone(),
+        _ => session.url().map(|url| url.to_string()).unwrap_or_default(),
+    }
+}
+
+/// Opens `url` in a new tab after the current one, switching to it when
+/// `select` is set.
+fn open_tab(
+    window: &mut Container,
+    sessions: &mut HashMap<usize, Session>,
+    fetcher: &mut Fetcher,
+    url: &str,
+    select: bool,
+) -> Result<(), String> {
+    let index = window.open_tab("");
+    if select {
+        window.select_tab(index);
+    }
+
+    // The page is laid out for the pane it is shown in.
+    let pane = window.focused_pane_in(index).unwrap_or_default();
+    let session = sessions.entry(pane).or_default();
+    session.set_viewport(page_area(window).size);
+    session.open(fetcher, url)
+}
+
+/// Draws the status line at the bottom of the pane being drawn to. Only the
+/// focused pane's stands out, in the theme's colors swapped around.
+fn draw_status(window: &mut Container, status: StyledLine, focused: bool) {
+    let page = page_area(window);
+    let (background, text) = match focused {
+        true => (Role::Text, Some(Role::Background)),
+        false => (Role::Border, None),
+    };
+
+    window.draw_box(window::DivOptions {
+        id: Some("status".to_string()),
+
+        position: common::Vec2::new(0, page.size.y as i16),
+        size: common::Vec2::new(page.size.x, 1),
+
+        background_role: Some(background),
+        text_role: text,
+
+        content: Some(vec![status]),
+
+        ..Default::default()
     });
+}
+
+fn draw(
+    window: &mut Container,
+    sessions: &mut HashMap<usize, Session>,
+    mode: &Mode,
+    message: &Option<String>,
+    config: &Config,
+    history: &History,
+) {
+    for index in 0..window.tab_count() {
+        let title = window
+            .focused_pane_in(index)
+            .and_then(|pane| sessions.get(&pane))
+            .map(tab_title)
+            .unwrap_or_default();
+        if window.tab_title(index) != Some(title.as_str()) {
+            window.set_tab_title(index, title);
+        }
+    }
+
+    // Panes other than the focused one show their page and where it is.
+    let focused = window.focused_pane();
+    for (pane, _) in window.panes() {
+        if pane == focused {
+            continue;
+        }
+        let session = sessions.entry(pane).or_default();
+        window.draw_to(pane);
+        window.clear().unwrap();
+        let page = page_area(window);
+        session.draw(window, &page, history);
+        draw_status(window, session.status().into(), false);
+    }
+
+    window.draw_to(focused);
+    let session = sessions.entry(focused).or_default();
+
+    window.clear().unwrap();
+
+    let page = page_area(window);
+    session.draw(window, &page, history);
+
+    match mode {
+        Mode::Edit(index, input) => {
+            if let Some(control) = session.control(*index) {
+                let masked = control.kind == ControlKind::Password;
+                draw_popup(
+                    window,
+                    config,
+                    &control_title(control),
+                    editor_lines(input, masked),
+                );
+            }
+        }
+        Mode::Choose(index, highlighted) => {
+            if let Some(control) = session.control(*index) {
+                let rows = window.scene_area().size.y.saturating_sub(4);
+                let lines = option_lines(control, *highlighted, rows);
+                draw_popup(window, config, &control_title(control), lines);
+            }
+        }
+        Mode::Hint(hints, typed) => draw_hints(window, hints, typed),
+        Mode::Open {
+            new_tab,
+            completions,
+            selected,
+            ..
+        } => draw_completions(
+            window,
+            config,
+            completions,
+            *selected,
+            open_label(*new_tab).chars().count(),
+        ),
+        Mode::Browse | Mode::Bookmark(_) | Mode::Find { .. } => {}
+    }
+
+    let status = match (mode, message) {
+        (Mode::Open { input, new_tab, .. }, _) => prompt_line(open_label(*new_tab), input),
+        (Mode::Bookmark(input), _) => prompt_line("Bookmark with tags: ", input),
+        (Mode::Hint(_, typed), _) => format!("Follow: {}", typed).into(),
+        (
+            Mode::Find {
+                input,
+                options,
+                error,
+                ..
+            },
+            _,
+        ) => {
+            let mut line = prompt_line(&find_label(options), input);
+            let note = match (error, session.search()) {
+                (Some(error), _) => format!("  {}", error),
+                (None, Some(search)) if search.matches.is_empty() => "  No matches".to_string(),
+                (None, Some(search)) if search.matches.len() == 1 => "  1 match".to_string(),
+                (None, Some(search)) => format!("  {} matches", search.matches.len()),
+                (None, None) => String::new(),
+            };
+            line.spans.push(Span::plain(note));
+            line
+        }
+        (Mode::Edit(_, input), _) if input.multiline => "Ctrl-d: done  Esc: cancel".into(),
+        (Mode::Edit(..), _) => "Enter: submit  Tab: next field  Esc: cancel".into(),
+        (Mode::Choose(index, _), _) => match session.control(*index).map(|c| &c.kind) {
+            Some(ControlKind::Select { multiple: true }) => {
+                "Space: toggle  Enter: done  Esc: close".into()
+            }
+            _ => "Enter: choose  Esc: close".into(),
+        },
+        (Mode::Browse, Some(message)) => message.as_str().into(),
+        (Mode::Browse, None) => session.status().into(),
+    };
+    draw_status(window, status, true);
+
+    window.render();
+}
+
+/// Uses the form control at `index`, as clicking it does. Returns what
+/// keys should do next, such as typing into a text field.
+fn activate(session: &mut Session, fetcher: &mut Fetcher, index: usize) -> Result<Mode, String> {
+    let Some(control) = session.control(index).filter(|control| !control.disabled) else {
+        return Ok(Mode::Browse);
+    };
+
+    match &control.kind {
+        ControlKind::Text | ControlKind::Password | ControlKind::File => {
+            return Ok(Mode::Edit(index, LineInput::new(control.value.clone())));
+        }
+        ControlKind::TextArea => {
+            return Ok(Mode::Edit(
+                index,
+                LineInput::multiline(control.value.clone()),
+            ));
+        }
+        ControlKind::Select { .. } => {
+            let selected = control.options.iter().position(|option| option.selected);
+            return Ok(Mode::Choose(index, selected.unwrap_or(0)));
+        }
+        ControlKind::Checkbox | ControlKind::Radio => session.toggle(index),
+        ControlKind::Submit | ControlKind::Image => session.submit(fetcher, index)?,
+        ControlKind::Reset => session.reset_form(index),
+        ControlKind::Hidden | ControlKind::Button => {}
+    }
+
+    Ok(Mode::Browse)
+}
+
+/// The text field after or before the one at `index` in the same form.
+fn next_field(session: &Session, index: usize, forward: bool) -> Option<usize> {
+    let controls = &session.document()?.controls;
+    let form = controls.get(index)?.form;
+    let fields = controls
+        .iter()
+        .enumerate()
+        .filter(|(_, control)| {
+            control.form == form && control.visible && control.is_editable() && !control.disabled
+        })
+        .map(|(i, _)| i);
+
+    match forward {
+        true => fields.clone().find(|&i| i > index).or(fields.min()),
+        false => fields.clone().rfind(|&i| i < index).or(fields.max()),
+    }
+}
+
+fn usage(error: &str) -> ! {
+    eprintln!("{}", error);
+    eprintln!(
+        "Usage: hyperline [--insecure] [--ca-file PATH]... [--offline] [--cache-size MB] [URL]\n       \
+         hyperline [--import-bookmarks PATH] [--export-bookmarks PATH]"
+    );
+    std::process::exit(2);
+}
+
+fn main() {
+    let stdin = stdin();
+
+    let mut fetcher = Fetcher::new();
+    // The session shown in each pane, by pane id.
+    let mut sessions = HashMap::from([(0, Session::new())]);
 
-    window
-        .draw_box_under(
-            &"block_border_box".to_string(),
-            window::DivOptions {
-                id: Some("block_border_box_under".to_string()),
-
-                position: common::Vec2::new(10, 10),
-                size: common::Vec2::new(16, 8),
-
-                border_options: BorderFlags::TOP
-                    | BorderFlags::RIGHT
-                    | BorderFlags::BOTTOM
-                    | BorderFlags::LEFT,
-                border_style: draw::border::BorderStyle::Rounded,
-
-                border_color: Some(common::Color::GREEN),
-                background_color: Some(common::Color::BLACK),
-                text_color: Some(common::Color::WHITE),
-
-                content: Some(vec![
-                    "This is an under box.".to_string(),
-                    "It is under the main box.".to_string(),
-                ]),
+    // Problems with the config are shown before the screen is taken over.
+    let config_file = paths::config_dir().map(|dir| dir.join("config.toml"));
+    let mut config = match &config_file {
+        Some(file) => Config::load(file).unwrap_or_else(|error| {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }),
+        None => Config::default(),
+    };
+
+    let mut start = None;
+    let (mut offline, mut cache_size) = (false, None);
+    let (mut import, mut export) = (None, None);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--insecure" => fetcher.client.tls.insecure = true,
+            "--offline" => offline = true,
+            "--cache-size" => match args.next().and_then(|size| size.parse::<u64>().ok()) {
+                Some(megabytes) => cache_size = Some(megabytes * 1024 * 1024),
+                None => usage("--cache-size needs a number of megabytes."),
+            },
+            "--import-bookmarks" => match args.next() {
+                Some(path) => import = Some(path),
+                None => usage("--import-bookmarks needs a path."),
+            },
+            "--export-bookmarks" => match args.next() {
+                Some(path) => export = Some(path),
+                None => usage("--export-bookmarks needs a path."),
             },
-        )
-        .unwrap();
+            "--ca-file" => match args.next() {
+                Some(path) => fetcher.client.tls.ca_files.push(path.into()),
+                None => usage("--ca-file needs a path."),
+            },
+            flag if flag.starts_with("--") => usage(&format!("Unknown option {}.", flag)),
+            url => start = Some(url.to_string()),
+        }
+    }
+
+    fetcher.keys = config.keys.clone();
+    let features = &config.features;
+    if let Some(dir) = paths::cache_dir().filter(|_| features.cache) {
+        fetcher.client.cache = HttpCache::open(dir.join("http"));
+    }
+    fetcher.client.cache.offline = offline;
+    if let Some(size) = cache_size.or(config.cache_size) {
+        fetcher.client.cache.max_size = size;
+    }
+
+    let mut load_error = None;
+    if let Some(dir) = paths::data_dir() {
+        if features.cookies {
+            match CookieJar::open(dir.join("cookies.txt")) {
+                Ok(jar) => fetcher.client.cookies = jar,
+                Err(error) => load_error = Some(error),
+            }
+        }
+        match Bookmarks::open(dir.join("bookmarks.txt")) {
+            Ok(bookmarks) => fetcher.bookmarks = bookmarks,
+            Err(error) => load_error = Some(error),
+        }
+        if features.history {
+            match History::open(dir.join("history.txt")) {
+                Ok(history) => fetcher.history = history,
+                Err(error) => load_error = Some(error),
+            }
+        }
+    }
+
+    // Bookmarks are imported and exported without opening the browser.
+    if import.is_some() || export.is_some() {
+        if let Some(path) = import {
+            let imported = std::fs::read_to_string(&path)
+                .map_err(|error| format!("Could not read {}: {}", path, error))
+                .and_then(|text| fetcher.bookmarks.import_html(&text));
+            match imported {
+                Ok(count) => println!("Imported {} bookmarks from {}.", count, path),
+                Err(error) => {
+                    eprintln!("{}", error);
+                    std::process::exit(1);
+                }
+            }
+        }
+        if let Some(path) = export {
+            match std::fs::write(&path, fetcher.bookmarks.export_html()) {
+                Ok(()) => println!(
+                    "Exported {} bookmarks to {}.",
+                    fetcher.bookmarks.bookmarks.len(),
+                    path
+                ),
+                Err(error) => {
+                    eprintln!("Could not write {}: {}", path, error);
+                    std::process::exit(1);
+                }
+            }
+        }
+        return;
+    }
+
+    let size = match terminal_size() {
+        Ok(size) if termion::is_tty(&std::io::stdin()) && termion::is_tty(&std::io::stdout()) => {
+            size
+        }
+        _ => {
+            eprintln!("hyperline runs in a terminal, and needs one for input and output.");
+            std::process::exit(1);
+        }
+    };
+
+    let mut window = Container::new(size.clone());
+    window.tab_style = config.tab_border.clone();
+    window.divider_style = config.divider_border.clone();
+    window.set_theme(config.theme.clone());
+
+    let start = start.unwrap_or(config.homepage.clone());
+    let mut message = sessions
+        .entry(0)
+        .or_default()
+        .open(&mut fetcher, &start)
+        .err()
+        .or(load_error);
+    let mut mode = Mode::Browse;
+    let mut find_options = FindOptions::default();
 
     write!(window.stdout, "{}", termion::cursor::Hide).unwrap();
 
-    window.render();
+    draw(
+        &mut window,
+        &mut sessions,
+        &mode,
+        &message,
+        &config,
+        &fetcher.history,
+    );
 
-    for k in stdin.keys() {
-        window.render();
+    // Keys, resizes and changes to the config all wake the event loop.
+    let (sender, inputs) = mpsc::channel();
+    let keys = sender.clone();
+    thread::spawn(move || {
+        for event in stdin.events() {
+            if keys.send(Input::Terminal(event)).is_err() {
+                return;
+            }
+        }
+        let closed = std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "input closed");
+        let _ = keys.send(Input::Terminal(Err(closed)));
+    });
+    let resizes = sender.clone();
+    thread::spawn(move || watch_size(size, resizes));
+    if let Some(file) = config_file.clone() {
+        thread::spawn(move || watch_config(file, sender));
+    }
+
+    // Why the loop stopped, if it was not the user quitting.
+    let mut failure = None;
+    for input in inputs {
+        // Visits are saved together every so often, not on every page load.
+        fetcher.history.save_if_due(date::now());
+
+        let event = match input {
+            Input::Terminal(Ok(event)) => event,
+            Input::Terminal(Err(error)) => {
+                failure = Some(error);
+                break;
+            }
+            Input::Resized(size) => {
+                window.resize(size);
+                draw(
+                    &mut window,
+                    &mut sessions,
+                    &mode,
+                    &message,
+                    &config,
+                    &fetcher.history,
+                );
+                continue;
+            }
+            Input::ConfigChanged => {
+                if let Some(file) = &config_file {
+                    message = Some(reload_config(file, &mut config, &mut window));
+                    fetcher.keys = config.keys.clone();
+                }
+                draw(
+                    &mut window,
+                    &mut sessions,
+                    &mode,
+                    &message,
+                    &config,
+                    &fetcher.history,
+                );
+                continue;
+            }
+        };
+
+        let key = match event {
+            Event::Key(key) => key,
+            Event::Mouse(MouseEvent::Press(button, x, y)) if matches!(mode, Mode::Browse) => {
+                // Mouse coordinates start at 1.
+                let position = common::Vec2::new(x as usize - 1, y as usize - 1);
+                message = None;
+
+                if let Some(index) = window.tab_at(&position) {
+                    window.select_tab(index);
+                    draw(
+                        &mut window,
+                        &mut sessions,
+                        &mode,
+                        &message,
+                        &config,
+                        &fetcher.history,
+                    );
+                    continue;
+                }
+
+                // Clicking a pane focuses it, as well as using what is there.
+                if let Some(pane) = window.pane_at(&position)
+                    && button == MouseButton::Left
+                {
+                    window.focus_pane(pane);
+                }
+                let pane = window.pane_at(&position).unwrap_or(window.focused_pane());
+                let session = sessions.entry(pane).or_default();
+
+                match button {
+                    // A middle click opens a link in a new tab, behind this one.
+                    MouseButton::Middle => {
+                        if let Some(target) = window.link_at(&position)
+                            && let Some(url) = session.url().and_then(|url| url.join(target).ok())
+                        {
+                            let url = url.to_string();
+                            message =
+                                open_tab(&mut window, &mut sessions, &mut fetcher, &url, false)
+                                    .err();
+                        }
+                    }
+                    MouseButton::Left => {
+                        if let Some(index) = window.widget_at(&position) {
+                            match activate(session, &mut fetcher, index) {
+                                Ok(next) => mode = next,
+                                Err(error) => message = Some(error),
+                            }
+                        } else if let Some(target) = window.link_at(&position).map(str::to_string) {
+                            message = session.follow(&mut fetcher, &target).err();
+                        }
+                    }
+                    MouseButton::WheelUp => session.scroll_by(-3),
+                    MouseButton::WheelDown => session.scroll_by(3),
+                    _ => {}
+                }
+
+                draw(
+                    &mut window,
+                    &mut sessions,
+                    &mode,
+                    &message,
+                    &config,
+                    &fetcher.history,
+                );
+                continue;
+            }
+            _ => continue,
+        };
+
+        let session = sessions.entry(window.focused_pane()).or_default();
+        match &mut mode {
+            Mode::Open {
+                input,
+                new_tab,
+                typed,
+                completions,
+                selected,
+            } => {
+                // The arrow keys go through the completions, then back to what was typed.
+                let step = match key {
+                    Key::Down | Key::Char('\t') => Some(1),
+                    Key::Up | Key::BackTab => Some(-1),
+                    _ => None,
+                };
+
+                let event = match step {
+                    Some(_) if completions.is_empty() => InputEvent::Ignored,
+                    Some(step) => {
+                        let count = completions.len() as isize + 1;
+                        let position = selected.map_or(count - 1, |i| i as isize);
+                        let next = (position + step).rem_euclid(count) as usize;
+                        *selected = (next < completions.len()).then_some(next);
+                        *input = LineInput::new(match *selected {
+                            Some(i) => completions[i].url.clone(),
+                            None => typed.clone(),
+                        });
+                        InputEvent::Ignored
+                    }
+                    None => input.handle_key(key),
+                };
 
-        match k.unwrap() {
-            termion::event::Key::Char('q') => break,
-            _ => {}
+                match event {
+                    InputEvent::Submitted => {
+                        message = match new_tab {
+                            true => open_tab(
+                                &mut window,
+                                &mut sessions,
+                                &mut fetcher,
+                                &input.text,
+                                true,
+                            )
+                            .err(),
+                            false => session.open(&mut fetcher, &input.text).err(),
+                        };
+                        mode = Mode::Browse;
+                    }
+                    InputEvent::Cancelled => mode = Mode::Browse,
+                    InputEvent::Edited if config.features.history => {
+                        *typed = input.text.clone();
+                        *completions = fetcher
+                            .history
+                            .complete(typed, date::now(), MAX_COMPLETIONS)
+                            .into_iter()
+                            .cloned()
+                            .collect();
+                        *selected = None;
+                    }
+                    InputEvent::Edited => *typed = input.text.clone(),
+                    InputEvent::Ignored => {}
+                }
+            }
+            Mode::Bookmark(input) => match input.handle_key(key) {
+                InputEvent::Submitted => {
+                    if let Some(url) = session.url() {
+                        let bookmark = Bookmark {
+                            url: url.to_string(),
+                            title: session
+                                .document()
+                                .map_or(String::new(), |d| d.title.clone()),
+                            tags: bookmarks::parse_tags(&input.text),
+                            added: date::now(),
+                        };
+                        message = Some(match fetcher.bookmarks.add(bookmark) {
+                            Ok(()) => "Bookmarked.".to_string(),
+                            Err(error) => error,
+                        });
+                    }
+                    mode = Mode::Browse;
+                }
+                InputEvent::Cancelled => mode = Mode::Browse,
+                InputEvent::Edited | InputEvent::Ignored => {}
+            },
+            Mode::Edit(index, input) => {
+                let index = *index;
+                let forward = match key {
+                    Key::Char('\t') => Some(true),
+                    Key::BackTab => Some(false),
+                    _ => None,
+                };
+
+                if let Some(forward) = forward {
+                    session.set_value(index, input.text.clone());
+                    mode = match next_field(session, index, forward) {
+                        Some(next) => activate(session, &mut fetcher, next).unwrap_or(Mode::Browse),
+                        None => Mode::Browse,
+                    };
+                } else {
+                    match input.handle_key(key) {
+                        InputEvent::Submitted => {
+                            let multiline = input.multiline;
+                            session.set_value(index, input.text.clone());
+                            mode = Mode::Browse;
+                            // Enter in a text field submits its form, as in other browsers.
+                            if !multiline
+                                && session.control(index).is_some_and(|c| c.form.is_some())
+                            {
+                                message = session.submit(&mut fetcher, index).err();
+                            }
+                        }
+                        InputEvent::Cancelled => mode = Mode::Browse,
+                        InputEvent::Edited | InputEvent::Ignored => {}
+                    }
+                }
+            }
+            Mode::Choose(index, highlighted) => {
+                let index = *index;
+                let count = session.control(index).map_or(0, |c| c.options.len());
+                let multiple = session
+                    .control(index)
+                    .is_some_and(|c| matches!(c.kind, ControlKind::Select { multiple: true }));
+
+                match key {
+                    Key::Char('j') | Key::Down => {
+                        *highlighted = (*highlighted + 1).min(count.saturating_sub(1))
+                    }
+                    Key::Char('k') | Key::Up => *highlighted = highlighted.saturating_sub(1),
+                    Key::Char(' ') => session.select(index, *highlighted),
+                    Key::Char('\n') => {
+                        if !multiple {
+                            session.select(index, *highlighted);
+                        }
+                        mode = Mode::Browse;
+                    }
+                    Key::Esc | Key::Ctrl('c') | Key::Char('q') => mode = Mode::Browse,
+                    _ => {}
+                }
+            }
+            Mode::Hint(hints, typed) => match key {
+                Key::Char(c) => {
+                    typed.push(c);
+                    let mut matching = hints.iter().filter(|hint| hint.label.starts_with(&*typed));
+
+                    match (matching.next(), matching.next()) {
+                        (None, _) => {
+                            message = Some(format!("No hint starts with '{}'.", typed));
+                            mode = Mode::Browse;
+                        }
+                        (Some(hint), None) => {
+                            let target = hint.target.clone();
+                            mode = Mode::Browse;
+                            match target {
+                                Target::Link(link) => {
+                                    message = session.follow(&mut fetcher, &link).err();
+                                }
+                                Target::Widget(index) => {
+                                    match activate(session, &mut fetcher, index) {
+                                        Ok(next) => mode = next,
+                                        Err(error) => message = Some(error),
+                                    }
+                                }
+                            }
+                        }
+                        (Some(_), Some(_)) => {}
+                    }
+                }
+                Key::Backspace => {
+                    typed.pop();
+                }
+                Key::Esc | Key::Ctrl('c') => mode = Mode::Browse,
+                _ => {}
+            },
+            Mode::Find {
+                input,
+                options,
+                origin,
+                error,
+            } => {
+                let event = match key {
+                    Key::Alt('c') => {
+                        options.case_sensitive = !options.case_sensitive;
+                        InputEvent::Edited
+                    }
+                    Key::Alt('r') => {
+                        options.regex = !options.regex;
+                        InputEvent::Edited
+                    }
+                    key => input.handle_key(key),
+                };
+                find_options = *options;
+
+                match event {
+                    InputEvent::Edited => {
+                        *error = session.find(&input.text, *options, *origin).err();
+                    }
+                    // An unfinished regular expression is left as it was.
+                    InputEvent::Submitted if error.is_some() => {}
+                    InputEvent::Submitted => {
+                        if session.search().is_some() {
+                            message = Some(find_status(session));
+                        }
+                        mode = Mode::Browse;
+                    }
+                    InputEvent::Cancelled => {
+                        session.clear_find();
+                        session.scroll_to(*origin);
+                        mode = Mode::Browse;
+                    }
+                    InputEvent::Ignored => {}
+                }
+            }
+            Mode::Browse => {
+                message = None;
+
+                match config.keys.action(&key) {
+                    Some(Action::Quit) => break,
+                    Some(Action::Hints) => {
+                        let hints = hints::collect(&window.buffer, &page_on_screen(&window));
+                        match hints.is_empty() {
+                            true => message = Some("No links or controls on screen.".to_string()),
+                            false => mode = Mode::Hint(hints, String::new()),
+                        }
+                    }
+                    Some(Action::Find) => {
+                        session.clear_find();
+                        mode = Mode::Find {
+                            input: LineInput::default(),
+                            options: find_options,
+                            origin: session.scroll(),
+                            error: None,
+                        }
+                    }
+                    Some(action @ (Action::FindNext | Action::FindPrevious)) => {
+                        session.find_next(action == Action::FindNext);
+                        message = Some(find_status(session));
+                    }
+                    Some(Action::ClearFind) => session.clear_find(),
+                    Some(Action::Open) => mode = Mode::open("", false),
+                    Some(Action::EditUrl) => {
+                        let current = session.url().map(|url| url.to_string());
+                        mode = Mode::open(current.unwrap_or_default(), false);
+                    }
+                    Some(Action::OpenInTab) => mode = Mode::open("", true),
+                    Some(Action::Bookmark) => match session.url() {
+                        // Bookmarking a page again edits its tags.
+                        Some(url) => {
+                            let tags = fetcher
+                                .bookmarks
+                                .get(&url.to_string())
+                                .map(|bookmark| bookmark.tags.join(", "));
+                            mode = Mode::Bookmark(LineInput::new(tags.unwrap_or_default()));
+                        }
+                        None => message = Some("There is no page to bookmark.".to_string()),
+                    },
+                    Some(Action::Bookmarks) => {
+                        message = session.open(&mut fetcher, "about:bookmarks").err()
+                    }
+                    // Closes the pane, or the tab when it is the only pane.
+                    Some(Action::ClosePane) if window.panes().len() > 1 => {
+                        message = window.close_pane(window.focused_pane()).err();
+                    }
+                    Some(Action::ClosePane | Action::CloseTab) => {
+                        message = window.close_tab(window.active_tab()).err();
+                    }
+                    Some(action @ (Action::SplitRow | Action::SplitColumn)) => {
+                        let direction = match action == Action::SplitRow {
+                            true => SplitDirection::Row,
+                            false => SplitDirection::Column,
+                        };
+                        // The new pane starts out as a copy of this one.
+                        let copy = session.clone();
+                        let pane = window.split_pane(direction);
+                        sessions.insert(pane, copy);
+                    }
+                    Some(
+                        action @ (Action::FocusLeft
+                        | Action::FocusDown
+                        | Action::FocusUp
+                        | Action::FocusRight),
+                    ) => {
+                        let step = match action {
+                            Action::FocusLeft => (-1, 0),
+                            Action::FocusDown => (0, 1),
+                            Action::FocusUp => (0, -1),
+                            _ => (1, 0),
+                        };
+                        if !window.focus_neighbour(step) {
+                            message = Some("No pane that way.".to_string());
+                        }
+                    }
+                    // Cells are about twice as tall as they are wide, so
+                    // dividers move further sideways.
+                    Some(
+                        action @ (Action::ResizeLeft
+                        | Action::ResizeDown
+                        | Action::ResizeUp
+                        | Action::ResizeRight),
+                    ) => {
+                        let (direction, cells) = match action {
+                            Action::ResizeLeft => (SplitDirection::Row, -2),
+                            Action::ResizeRight => (SplitDirection::Row, 2),
+                            Action::ResizeUp => (SplitDirection::Column, -1),
+                            _ => (SplitDirection::Column, 1),
+                        };
+                        if !window.resize_pane(direction, cells) {
+                            message = Some("No divider to move that way.".to_string());
+                        }
+                    }
+                    Some(action @ (Action::NextTab | Action::PreviousTab)) => {
+                        let count = window.tab_count();
+                        let step = if action == Action::NextTab {
+                            1
+                        } else {
+                            count - 1
+                        };
+                        window.select_tab((window.active_tab() + step) % count);
+                    }
+                    Some(Action::NextTheme) => {
+                        let current = THEMES.iter().position(|name| *name == window.theme.name);
+                        let name = THEMES[current.map_or(0, |i| (i + 1) % THEMES.len())];
+                        // The configured theme comes back with its own colors.
+                        let theme = match config.theme.name == name {
+                            true => config.theme.clone(),
+                            false => Theme::built_in(name).unwrap_or_default(),
+                        };
+                        window.set_theme(theme);
+                        message = Some(format!("Theme: {}", name));
+                    }
+                    Some(action @ (Action::MoveTabRight | Action::MoveTabLeft)) => {
+                        let from = window.active_tab();
+                        let to = match action == Action::MoveTabRight {
+                            true => (from + 1).min(window.tab_count() - 1),
+                            false => from.saturating_sub(1),
+                        };
+                        window.move_tab(from, to);
+                    }
+                    Some(Action::Back) if !session.go_back(&mut fetcher) => {
+                        message = Some("No previous page.".to_string());
+                    }
+                    Some(Action::Forward) if !session.go_forward(&mut fetcher) => {
+                        message = Some("No next page.".to_string());
+                    }
+                    Some(Action::Back | Action::Forward) => {}
+                    Some(Action::Reload) => session.reload(&mut fetcher),
+                    Some(Action::ScrollDown) => session.scroll_by(1),
+                    Some(Action::ScrollUp) => session.scroll_by(-1),
+                    Some(Action::PageDown) => session.scroll_pages(1),
+                    Some(Action::PageUp) => session.scroll_pages(-1),
+                    Some(Action::Top) => session.scroll_to(0),
+                    Some(Action::Bottom) => session.scroll_to(usize::MAX),
+                    // 1 to 8 go to that tab, and 9 to the last one.
+                    None => {
+                        if let Key::Char(digit @ '1'..='9') = key {
+                            let count = window.tab_count();
+                            match digit.to_digit(10).unwrap_or(1) as usize {
+                                9 => window.select_tab(count - 1),
+                                n if n <= count => window.select_tab(n - 1),
+                                _ => message = Some(format!("There are only {} tabs.", count)),
+                            }
+                        }
+                    }
+                }
+            }
         }
+
+        // Forget the sessions of panes that were closed.
+        let panes = window.pane_ids();
+        sessions.retain(|pane, _| panes.contains(pane));
+
+        draw(
+            &mut window,
+            &mut sessions,
+            &mode,
+            &message,
+            &config,
+            &fetcher.history,
+        );
     }
 
     write!(window.stdout, "{}", termion::cursor::Show).unwrap();
+    let _ = fetcher.history.flush();
+
+    // The terminal leaves raw mode when the window is dropped, so errors are
+    // only printed after that.
+    if let Some(error) = failure {
+        drop(window);
+        eprintln!("Could not read from the terminal: {}", error);
+        std::process::exit(1);
+    }
 }
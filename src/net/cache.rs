@@ -0,0 +1,670 @@
+use std::fs::{self, DirBuilder, File, Permissions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::browser::url::Url;
+use crate::net::date;
+use crate::net::http::{Headers, Response};
+
+/// Statuses that may be stored, as long as the response allows it.
+const CACHEABLE: &[u16] = &[200, 203, 204, 300, 301, 308, 404, 410];
+
+/// Headers that describe the body as it was sent, not as it is stored, and
+/// cookies, which are kept in the cookie jar and nowhere else.
+const NOT_STORED: &[&str] = &[
+    "Connection",
+    "Content-Encoding",
+    "Content-Length",
+    "Keep-Alive",
+    "Set-Cookie",
+    "Set-Cookie2",
+    "Transfer-Encoding",
+];
+
+/// The extension of entries still being written.
+const PARTIAL: &str = "partial";
+
+/// Responses without an explicit lifetime stay fresh for a tenth of their
+/// age when received, but no longer than this many seconds.
+const MAX_HEURISTIC_LIFETIME: u64 = 7 * 24 * 60 * 60;
+
+/// The directives of the `Cache-Control` headers, lowercased, with their values.
+fn directives(headers: &Headers) -> Vec<(String, Option<String>)> {
+    headers
+        .get_all("Cache-Control")
+        .flat_map(|value| value.split(','))
+        .filter(|directive| !directive.trim().is_empty())
+        .map(|directive| match directive.split_once('=') {
+            Some((name, value)) => (
+                name.trim().to_ascii_lowercase(),
+                Some(value.trim().trim_matches('"').to_string()),
+            ),
+            None => (directive.trim().to_ascii_lowercase(), None),
+        })
+        .collect()
+}
+
+fn has_directive(headers: &Headers, name: &str) -> bool {
+    directives(headers).iter().any(|(key, _)| key == name)
+}
+
+fn max_age(headers: &Headers) -> Option<u64> {
+    directives(headers)
+        .into_iter()
+        .find(|(key, _)| key == "max-age")
+        .and_then(|(_, value)| value?.parse().ok())
+}
+
+/// Whether a request asks for stored responses to be checked with the
+/// server first, as a reload does.
+pub fn wants_revalidation(request_headers: &Headers) -> bool {
+    has_directive(request_headers, "no-cache")
+        || max_age(request_headers) == Some(0)
+        || request_headers
+            .get("Pragma")
+            .is_some_and(|pragma| pragma.eq_ignore_ascii_case("no-cache"))
+}
+
+/// Whether a response to a GET request may be stored at all.
+pub fn is_storable(request_headers: &Headers, response: &Response) -> bool {
+    let vary = response
+        .headers
+        .get_all("Vary")
+        .flat_map(|value| value.split(','));
+    // Only the encoding can vary, and hyperline always asks for the same ones.
+    let varies = vary
+        .map(str::trim)
+        .any(|name| !name.is_empty() && !name.eq_ignore_ascii_case("Accept-Encoding"));
+
+    CACHEABLE.contains(&response.status)
+        && !varies
+        && !has_directive(request_headers, "no-store")
+        && !has_directive(&response.headers, "no-store")
+}
+
+/// A response kept on disk, and when it was received.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub response: Response,
+    /// Seconds since the Unix epoch.
+    pub stored: u64,
+}
+
+impl Entry {
+    /// How long after it was sent the response may be used without asking
+    /// the server again.
+    pub fn lifetime(&self) -> u64 {
+        let headers = &self.response.headers;
+        if has_directive(headers, "no-cache") {
+            return 0;
+        }
+        if let Some(max_age) = max_age(headers) {
+            return max_age;
+        }
+
+        let sent = headers
+            .get("Date")
+            .and_then(date::parse)
+            .unwrap_or(self.stored);
+        if let Some(expires) = headers.get("Expires") {
+            // Invalid dates, such as `0`, mean the response has already expired.
+            return date::parse(expires).map_or(0, |expires| expires.saturating_sub(sent));
+        }
+        if let Some(modified) = headers.get("Last-Modified").and_then(date::parse) {
+            return (sent.saturating_sub(modified) / 10).min(MAX_HEURISTIC_LIFETIME);
+        }
+
+        0
+    }
+
+    /// How old the response is, counting the time it spent in other caches.
+    pub fn age(&self, now: u64) -> u64 {
+        let age = self
+            .response
+            .headers
+            .get("Age")
+            .and_then(|age| age.trim().parse::<u64>().ok())
+            .unwrap_or(0);
+        age + now.saturating_sub(self.stored)
+    }
+
+    pub fn is_fresh(&self, now: u64) -> bool {
+        self.age(now) < self.lifetime()
+    }
+
+    /// The headers that ask the server to send the response only if it has
+    /// changed. Empty when the response has no validators.
+    pub fn conditions(&self) -> Headers {
+        let mut conditions = Headers::default();
+        if let Some(etag) = self.response.headers.get("ETag") {
+            conditions.insert("If-None-Match", etag);
+        }
+        if let Some(modified) = self.response.headers.get("Last-Modified") {
+            conditions.insert("If-Modified-Since", modified);
+        }
+        conditions
+    }
+
+    /// Takes in the headers of a `304 Not Modified` response, which replace
+    /// the stored ones, and restarts the entry's age.
+    pub fn refresh(&mut self, headers: &Headers, now: u64) {
+        for (name, value) in &headers.0 {
+            if NOT_STORED
+                .iter()
+                .any(|skip| skip.eq_ignore_ascii_case(name))
+            {
+                continue;
+            }
+            self.response
+                .headers
+                .0
+                .retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+            self.response.headers.insert(name.clone(), value.clone());
+        }
+        self.stored = now;
+    }
+}
+
+/// A cache of HTTP responses, one file per URL. When it grows past its size
+/// limit, the entries used least recently are removed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpCache {
+    /// Where entries are kept. Without a directory, nothing is cached.
+    pub dir: Option<PathBuf>,
+    /// The most bytes the entries may take up together.
+    pub max_size: u64,
+    /// Serve every stored response, however old, and never use the network.
+    pub offline: bool,
+}
+
+impl Default for HttpCache {
+    fn default() -> Self {
+        HttpCache {
+            dir: None,
+            max_size: 100 * 1024 * 1024,
+            offline: false,
+        }
+    }
+}
+
+/// The file name of a URL's entry: its 64-bit FNV-1a hash in hex.
+fn file_name(url: &Url) -> String {
+    let hash = url
+        .to_string()
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    format!("{:016x}", hash)
+}
+
+impl HttpCache {
+    /// A cache that stores nothing.
+    pub fn new() -> Self {
+        HttpCache::default()
+    }
+
+    /// A cache kept in `dir`, which is created when the first entry is stored.
+    pub fn open(dir: PathBuf) -> Self {
+        HttpCache {
+            dir: Some(dir),
+            ..HttpCache::default()
+        }
+    }
+
+    fn path(&self, url: &Url) -> Option<PathBuf> {
+        Some(self.dir.as_ref()?.join(file_name(url)))
+    }
+
+    /// The stored response for `url`, if there is one. Looking an entry up
+    /// counts as using it.
+    pub fn get(&self, url: &Url) -> Option<Entry> {
+        let path = self.path(url)?;
+        let file = File::options().read(true).write(true).open(&path).ok()?;
+        let _ = file.set_modified(SystemTime::now());
+
+        let mut reader = BufReader::new(file);
+        let mut line = || {
+            let mut line = String::new();
+            reader.read_line(&mut line).ok()?;
+            Some(line.trim_end_matches(['\r', '\n']).to_string())
+        };
+
+        // Entries for different URLs could share a file name.
+        if line()? != url.to_string() {
+            return None;
+        }
+        let status_line = line()?;
+        let (status, reason) = status_line.split_once(' ').unwrap_or((&status_line, ""));
+        let status = status.parse().ok()?;
+        let reason = reason.to_string();
+        let stored = line()?.parse().ok()?;
+
+        let mut headers = Headers::default();
+        loop {
+            let header = line()?;
+            if header.is_empty() {
+                break;
+            }
+            let (name, value) = header.split_once(": ")?;
+            headers.insert(name, value);
+        }
+
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body).ok()?;
+
+        Some(Entry {
+            response: Response {
+                url: url.clone(),
+                status,
+                reason,
+                headers,
+                body,
+            },
+            stored,
+        })
+    }
+
+    /// Stores `entry` under `url`, then evicts entries if the cache is too big.
+    pub fn insert(&self, url: &Url, entry: &Entry) -> Result<(), String> {
+        let (Some(dir), Some(path)) = (&self.dir, self.path(url)) else {
+            return Ok(());
+        };
+
+        let response = &entry.response;
+        let mut data = format!(
+            "{}\n{} {}\n{}\n",
+            url, response.status, response.reason, entry.stored
+        );
+        for (name, value) in &response.headers.0 {
+            if !NOT_STORED
+                .iter()
+                .any(|skip| skip.eq_ignore_ascii_case(name))
+            {
+                data.push_str(&format!("{}: {}\n", name, value));
+            }
+        }
+        data.push('\n');
+
+        let mut data = data.into_bytes();
+        data.extend_from_slice(&response.body);
+        if data.len() as u64 > self.max_size {
+            return Ok(());
+        }
+
+        let error = |error: std::io::Error| format!("Could not write to the cache: {}", error);
+        // Pages can be private, so only the user may read them.
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .map_err(error)?;
+        // Written under another name first, so a crash never leaves half an entry.
+        let partial = path.with_extension(PARTIAL);
+        File::options()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&partial)
+            .and_then(|mut file| {
+                file.set_permissions(Permissions::from_mode(0o600))?;
+                file.write_all(&data)
+            })
+            .map_err(error)?;
+        fs::rename(&partial, &path).map_err(error)?;
+
+        self.evict()
+    }
+
+    pub fn remove(&self, url: &Url) {
+        if let Some(path) = self.path(url) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// Removes every entry.
+    pub fn clear(&self) -> Result<(), String> {
+        for (path, _, _) in self.entries() {
+            fs::remove_file(&path)
+                .map_err(|error| format!("Could not remove {}: {}", path.display(), error))?;
+        }
+        Ok(())
+    }
+
+    /// The number of entries and the bytes they take up.
+    pub fn usage(&self) -> (usize, u64) {
+        let entries = self.entries();
+        (entries.len(), entries.iter().map(|(_, size, _)| size).sum())
+    }
+
+    /// Every entry file, with its size and when it was last used. Entries
+    /// left half written by a crash are not counted.
+    fn entries(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let Some(Ok(dir)) = self.dir.as_ref().map(fs::read_dir) else {
+            return Vec::new();
+        };
+
+        dir.filter_map(|entry| {
+            let entry = entry.ok()?;
+            if entry.path().extension().is_some_and(|ext| ext == PARTIAL) {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            let used = metadata.modified().ok()?;
+            metadata
+                .is_file()
+                .then(|| (entry.path(), metadata.len(), used))
+        })
+        .collect()
+    }
+
+    /// Removes the least recently used entries until the cache fits in `max_size`.
+    fn evict(&self) -> Result<(), String> {
+        let mut entries = self.entries();
+        let mut size = entries.iter().map(|(_, size, _)| size).sum::<u64>();
+        if size <= self.max_size {
+            return Ok(());
+        }
+
+        entries.sort_by_key(|(_, _, used)| *used);
+        for (path, length, _) in entries {
+            if size <= self.max_size {
+                break;
+            }
+            fs::remove_file(&path)
+                .map_err(|error| format!("Could not remove {}: {}", path.display(), error))?;
+            size -= length;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+    use std::time::Duration;
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn url(text: &str) -> Url {
+        Url::parse(text).unwrap()
+    }
+
+    fn headers(pairs: &[(&str, &str)]) -> Headers {
+        let mut headers = Headers::default();
+        for (name, value) in pairs {
+            headers.insert(*name, *value);
+        }
+        headers
+    }
+
+    fn entry(pairs: &[(&str, &str)], stored: u64) -> Entry {
+        Entry {
+            response: Response {
+                url: url("http://example.com/"),
+                status: 200,
+                reason: "OK".to_string(),
+                headers: headers(pairs),
+                body: b"hello".to_vec(),
+            },
+            stored,
+        }
+    }
+
+    fn cache(name: &str) -> HttpCache {
+        let dir = env::temp_dir().join(format!("hyperline-cache-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        HttpCache::open(dir)
+    }
+
+    #[test]
+    fn lifetime_from_headers() {
+        let date = date::format(NOW);
+        let lifetime = |pairs: &[(&str, &str)]| entry(pairs, NOW).lifetime();
+
+        assert_eq!(lifetime(&[("Cache-Control", "public, max-age=60")]), 60);
+        assert_eq!(lifetime(&[("Cache-Control", "no-cache, max-age=60")]), 0);
+        assert_eq!(
+            lifetime(&[("Date", &date), ("Expires", &date::format(NOW + 300))]),
+            300
+        );
+        // max-age wins over Expires.
+        assert_eq!(
+            lifetime(&[
+                ("Cache-Control", "max-age=10"),
+                ("Expires", &date::format(NOW + 300))
+            ]),
+            10
+        );
+        assert_eq!(lifetime(&[("Expires", "0")]), 0);
+        assert_eq!(lifetime(&[("Expires", &date::format(NOW - 300))]), 0);
+        assert_eq!(lifetime(&[]), 0);
+    }
+
+    #[test]
+    fn heuristic_lifetime() {
+        let date = date::format(NOW);
+        let modified = |ago: u64| {
+            entry(
+                &[("Date", &date), ("Last-Modified", &date::format(NOW - ago))],
+                NOW,
+            )
+            .lifetime()
+        };
+
+        assert_eq!(modified(1000), 100);
+        assert_eq!(modified(0), 0);
+        assert_eq!(modified(365 * 24 * 60 * 60), MAX_HEURISTIC_LIFETIME);
+        // Without a Date, the time it was stored stands in for it.
+        let entry = entry(&[("Last-Modified", &date::format(NOW - 1000))], NOW);
+        assert_eq!(entry.lifetime(), 100);
+    }
+
+    #[test]
+    fn freshness() {
+        let fresh = entry(&[("Cache-Control", "max-age=60")], NOW);
+        assert_eq!(fresh.age(NOW + 10), 10);
+        assert!(fresh.is_fresh(NOW));
+        assert!(fresh.is_fresh(NOW + 59));
+        assert!(!fresh.is_fresh(NOW + 60));
+
+        // Time spent in other caches counts too.
+        let aged = entry(&[("Cache-Control", "max-age=60"), ("Age", "50")], NOW);
+        assert_eq!(aged.age(NOW + 10), 60);
+        assert!(!aged.is_fresh(NOW + 10));
+
+        assert!(!entry(&[], NOW).is_fresh(NOW));
+    }
+
+    #[test]
+    fn conditions() {
+        let modified = date::format(NOW - 1000);
+        let both = entry(&[("ETag", "\"v1\""), ("Last-Modified", &modified)], NOW);
+        let conditions = both.conditions();
+        assert_eq!(conditions.get("If-None-Match"), Some("\"v1\""));
+        assert_eq!(conditions.get("If-Modified-Since"), Some(modified.as_str()));
+
+        let etag = entry(&[("ETag", "W/\"v2\"")], NOW).conditions();
+        assert_eq!(etag.0, vec![("If-None-Match".into(), "W/\"v2\"".into())]);
+
+        assert!(
+            entry(&[("Cache-Control", "max-age=60")], NOW)
+                .conditions()
+                .0
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn refresh_on_not_modified() {
+        let mut entry = entry(
+            &[
+                ("Content-Type", "text/plain"),
+                ("ETag", "\"v1\""),
+                ("Cache-Control", "max-age=60"),
+            ],
+            NOW,
+        );
+        let not_modified = headers(&[
+            ("etag", "\"v2\""),
+            ("Cache-Control", "max-age=120"),
+            ("Content-Length", "0"),
+            ("Set-Cookie", "id=1"),
+        ]);
+        entry.refresh(&not_modified, NOW + 100);
+
+        let stored = &entry.response.headers;
+        assert_eq!(stored.get("Content-Type"), Some("text/plain"));
+        assert_eq!(stored.get_all("ETag").collect::<Vec<_>>(), ["\"v2\""]);
+        assert_eq!(stored.get("Content-Length"), None);
+        assert_eq!(stored.get("Set-Cookie"), None);
+        assert_eq!(entry.response.body, b"hello");
+        assert_eq!(entry.stored, NOW + 100);
+        assert!(entry.is_fresh(NOW + 200));
+    }
+
+    #[test]
+    fn revalidation_and_storability() {
+        assert!(wants_revalidation(&headers(&[(
+            "Cache-Control",
+            "no-cache"
+        )])));
+        assert!(wants_revalidation(&headers(&[(
+            "Cache-Control",
+            "max-age=0"
+        )])));
+        assert!(wants_revalidation(&headers(&[("Pragma", "no-cache")])));
+        assert!(!wants_revalidation(&headers(&[(
+            "Cache-Control",
+            "max-age=5"
+        )])));
+
+        let storable = |request: &[(&str, &str)], response: &[(&str, &str)], status| {
+            let mut entry = entry(response, NOW);
+            entry.response.status = status;
+            is_storable(&headers(request), &entry.response)
+        };
+        assert!(storable(&[], &[], 200));
+        assert!(storable(&[], &[("Vary", "Accept-Encoding")], 404));
+        assert!(!storable(&[], &[], 500));
+        assert!(!storable(&[], &[("Vary", "Accept-Encoding, Cookie")], 200));
+        assert!(!storable(&[], &[("Cache-Control", "no-store")], 200));
+        assert!(!storable(&[("Cache-Control", "no-store")], &[], 200));
+    }
+
+    #[test]
+    fn stores_privately_without_cookies() {
+        let cache = cache("store");
+        let url = url("http://example.com/page");
+        let entry = entry(
+            &[
+                ("Content-Type", "text/plain"),
+                ("Set-Cookie", "id=1"),
+                ("set-cookie2", "id=2"),
+                ("Transfer-Encoding", "chunked"),
+            ],
+            NOW,
+        );
+        cache.insert(&url, &entry).unwrap();
+
+        let dir = cache.dir.as_ref().unwrap();
+        let path = dir.join(file_name(&url));
+        let mode = |path: &PathBuf| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(mode(dir), 0o700);
+
+        let stored = cache.get(&url).unwrap();
+        assert_eq!(stored.stored, NOW);
+        assert_eq!(stored.response.status, 200);
+        assert_eq!(stored.response.reason, "OK");
+        assert_eq!(stored.response.body, b"hello");
+        assert_eq!(
+            stored.response.headers.0,
+            vec![("Content-Type".into(), "text/plain".into())]
+        );
+        assert_eq!(
+            cache.get(&Url::parse("http://example.com/other").unwrap()),
+            None
+        );
+
+        cache.remove(&url);
+        assert_eq!(cache.get(&url), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = cache("evict");
+        let (a, b, c) = (
+            url("http://example.com/a"),
+            url("http://example.com/b"),
+            url("http://example.com/c"),
+        );
+        let entry = entry(&[], NOW);
+        cache.insert(&a, &entry).unwrap();
+        let size = cache.usage().1;
+        cache.max_size = 2 * size;
+        cache.insert(&b, &entry).unwrap();
+        assert_eq!(cache.usage(), (2, 2 * size));
+
+        let dir = cache.dir.clone().unwrap();
+        let used = |url: &Url, ago: u64| {
+            let file = File::options()
+                .write(true)
+                .open(dir.join(file_name(url)))
+                .unwrap();
+            file.set_modified(SystemTime::now() - Duration::from_secs(ago))
+                .unwrap();
+        };
+        used(&a, 200);
+        used(&b, 100);
+        // Looking `a` up makes `b` the least recently used.
+        assert!(cache.get(&a).is_some());
+
+        cache.insert(&c, &entry).unwrap();
+        assert_eq!(cache.usage(), (2, 2 * size));
+        assert!(cache.get(&a).is_some());
+        assert_eq!(cache.get(&b), None);
+        assert!(cache.get(&c).is_some());
+
+        // Entries bigger than the whole cache are not stored at all.
+        cache.max_size = size - 1;
+        cache.insert(&b, &entry).unwrap();
+        assert_eq!(cache.get(&b), None);
+
+        cache.clear().unwrap();
+        assert_eq!(cache.usage(), (0, 0));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn ignores_partial_entries() {
+        let mut cache = cache("partial");
+        let url = url("http://example.com/");
+        cache.insert(&url, &entry(&[], NOW)).unwrap();
+        let (count, size) = cache.usage();
+        assert_eq!(count, 1);
+
+        let dir = cache.dir.clone().unwrap();
+        let partial = dir.join(file_name(&url)).with_extension(PARTIAL);
+        fs::write(&partial, vec![0; 4096]).unwrap();
+        assert_eq!(cache.usage(), (1, size));
+
+        // Neither counted towards the limit nor evicted in place of an entry.
+        cache.max_size = size;
+        cache.insert(&url, &entry(&[], NOW + 1)).unwrap();
+        assert_eq!(cache.get(&url).unwrap().stored, NOW + 1);
+        assert!(!partial.exists());
+
+        fs::write(&partial, b"left over").unwrap();
+        cache.clear().unwrap();
+        assert_eq!(cache.usage(), (0, 0));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
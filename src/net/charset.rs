@@ -0,0 +1,121 @@
+/// Characters 0x80 to 0x9F of windows-1252, where it differs from ISO-8859-1.
+/// Unassigned bytes map to the replacement character.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{fffd}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{fffd}', 'Ž',
+    '\u{fffd}', '\u{fffd}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{fffd}',
+    'ž', 'Ÿ',
+];
+
+/// The `charset` parameter of a `Content-Type` header value.
+pub fn from_content_type(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (key, value) = parameter.split_once('=')?;
+        (key.trim().eq_ignore_ascii_case("charset"))
+            .then(|| value.trim().trim_matches(['"', '\'']).to_ascii_lowercase())
+    })
+}
+
+/// The charset an HTML document declares in a `<meta>` tag near its start.
+pub fn from_html(bytes: &[u8]) -> Option<String> {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(1024)]).to_ascii_lowercase();
+
+    let start = head.find("charset=")? + "charset=".len();
+    let value = head[start..].trim_start_matches(['"', '\'']);
+    let end = value
+        .find(|c: char| !(c.is_ascii_alphanumeric() || "-_:.".contains(c)))
+        .unwrap_or(value.len());
+
+    (end > 0).then(|| value[..end].to_string())
+}
+
+/// Decodes `bytes` in `charset`, honouring a byte order mark. Unknown
+/// charsets are decoded as UTF-8.
+pub fn decode(bytes: &[u8], charset: Option<&str>) -> String {
+    if let Some(rest) = bytes.strip_prefix(b"\xef\xbb\xbf") {
+        return String::from_utf8_lossy(rest).into_owned();
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xff\xfe") {
+        return decode_utf16(rest, u16::from_le_bytes);
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xfe\xff") {
+        return decode_utf16(rest, u16::from_be_bytes);
+    }
+
+    match charset.unwrap_or("utf-8") {
+        "iso-8859-1" | "latin1" | "l1" | "us-ascii" | "ascii" => {
+            bytes.iter().map(|&byte| byte as char).collect()
+        }
+        "windows-1252" | "cp1252" | "x-cp1252" => bytes
+            .iter()
+            .map(|&byte| match byte {
+                0x80..=0x9f => WINDOWS_1252[(byte - 0x80) as usize],
+                byte => byte as char,
+            })
+            .collect(),
+        "utf-16le" => decode_utf16(bytes, u16::from_le_bytes),
+        "utf-16be" | "utf-16" => decode_utf16(bytes, u16::from_be_bytes),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> String {
+    let units = bytes
+        .chunks_exact(2)
+        .map(|pair| unit([pair[0], pair[1]]))
+        .collect::<Vec<_>>();
+    String::from_utf16_lossy(&units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_charset_parameter() {
+        assert_eq!(
+            from_content_type("text/html; charset=UTF-8").as_deref(),
+            Some("utf-8")
+        );
+        assert_eq!(
+            from_content_type("text/plain;format=flowed; Charset=\"ISO-8859-1\"").as_deref(),
+            Some("iso-8859-1")
+        );
+        assert_eq!(from_content_type("text/html"), None);
+    }
+
+    #[test]
+    fn finds_meta_charsets() {
+        assert_eq!(
+            from_html(b"<meta charset=\"Shift_JIS\">").as_deref(),
+            Some("shift_jis")
+        );
+        assert_eq!(
+            from_html(b"<meta http-equiv=Content-Type content='text/html; charset=windows-1252'>")
+                .as_deref(),
+            Some("windows-1252")
+        );
+        assert_eq!(from_html(b"<p>charset=</p>"), None);
+        let late = [&[b' '; 1024][..], b"<meta charset=latin1>"].concat();
+        assert_eq!(from_html(&late), None);
+    }
+
+    #[test]
+    fn decodes() {
+        assert_eq!(decode("café".as_bytes(), None), "café");
+        assert_eq!(decode(b"caf\xe9", Some("iso-8859-1")), "café");
+        assert_eq!(decode(b"\x80\x81\x9f", Some("windows-1252")), "€\u{fffd}Ÿ");
+        assert_eq!(decode(b"caf\xe9", Some("klingon")), "caf\u{fffd}");
+        assert_eq!(decode(b"h\x00i\x00", Some("utf-16le")), "hi");
+        assert_eq!(decode(b"\x00h\x00i", Some("utf-16be")), "hi");
+    }
+
+    #[test]
+    fn byte_order_marks_win() {
+        assert_eq!(
+            decode(b"\xef\xbb\xbfcaf\xc3\xa9", Some("iso-8859-1")),
+            "café"
+        );
+        assert_eq!(decode(b"\xff\xfeh\x00i\x00", None), "hi");
+        assert_eq!(decode(b"\xfe\xff\x00h\x00i", Some("windows-1252")), "hi");
+    }
+}
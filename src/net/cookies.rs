@@ -0,0 +1,649 @@
+use std::fs::{self, File, Permissions};
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
+
+use crate::browser::url::Url;
+use crate::net::date;
+
+/// Like the cookie `SameSite` attribute. Cookies without it are treated as `Lax`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    #[default]
+    Lax,
+    None,
+}
+
+impl SameSite {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+
+    fn parse(value: &str) -> Option<SameSite> {
+        match value.to_ascii_lowercase().as_str() {
+            "strict" => Some(SameSite::Strict),
+            "lax" => Some(SameSite::Lax),
+            "none" => Some(SameSite::None),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// Lowercase, without a leading dot.
+    pub domain: String,
+    /// Sent only to `domain` itself, not its subdomains, because the cookie
+    /// had no `Domain` attribute.
+    pub host_only: bool,
+    pub path: String,
+    /// Seconds since the Unix epoch. Cookies without an expiry last until
+    /// hyperline exits.
+    pub expires: Option<u64>,
+    pub secure: bool,
+    pub http_only: bool,
+    pub same_site: SameSite,
+}
+
+impl Cookie {
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    fn domain_matches(&self, host: &str) -> bool {
+        host == self.domain
+            || (!self.host_only
+                && host.ends_with(&self.domain)
+                && host[..host.len() - self.domain.len()].ends_with('.'))
+    }
+
+    fn path_matches(&self, path: &str) -> bool {
+        path == self.path
+            || (path.starts_with(&self.path)
+                && (self.path.ends_with('/') || path[self.path.len()..].starts_with('/')))
+    }
+
+    fn same_key(&self, other: &Cookie) -> bool {
+        self.name == other.name && self.domain == other.domain && self.path == other.path
+    }
+}
+
+/// The directory part of a request path, used when a cookie has no `Path`.
+fn default_path(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(end) => path[..end].to_string(),
+    }
+}
+
+/// Domains under which anyone can register a name, besides top-level
+/// domains. This is a small part of the public suffix list, with the suffixes
+/// most often met.
+const PUBLIC_SUFFIXES: &[&str] = &[
+    "ac.jp",
+    "ac.uk",
+    "ac.nz",
+    "co.il",
+    "co.in",
+    "co.jp",
+    "co.kr",
+    "co.nz",
+    "co.uk",
+    "co.za",
+    "com.ar",
+    "com.au",
+    "com.br",
+    "com.cn",
+    "com.hk",
+    "com.mx",
+    "com.my",
+    "com.sg",
+    "com.tr",
+    "com.tw",
+    "com.ua",
+    "edu.au",
+    "edu.cn",
+    "gov.au",
+    "gov.br",
+    "gov.cn",
+    "gov.in",
+    "gov.uk",
+    "govt.nz",
+    "ltd.uk",
+    "me.uk",
+    "ne.jp",
+    "net.au",
+    "net.br",
+    "net.cn",
+    "net.in",
+    "net.nz",
+    "net.uk",
+    "nhs.uk",
+    "or.jp",
+    "or.kr",
+    "org.au",
+    "org.br",
+    "org.cn",
+    "org.in",
+    "org.nz",
+    "org.uk",
+    "org.za",
+    "plc.uk",
+    "sch.uk",
+    "appspot.com",
+    "azurewebsites.net",
+    "blogspot.com",
+    "cloudfront.net",
+    "github.io",
+    "gitlab.io",
+    "herokuapp.com",
+    "netlify.app",
+    "pages.dev",
+    "vercel.app",
+    "workers.dev",
+];
+
+fn is_public_suffix(domain: &str) -> bool {
+    !domain.contains('.') || PUBLIC_SUFFIXES.contains(&domain)
+}
+
+/// The site of a host, used to tell same-site requests apart: its public
+/// suffix and the label before it.
+fn site(host: &str) -> &str {
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        return host;
+    }
+
+    host.rmatch_indices('.')
+        .map(|(index, _)| &host[index + 1..])
+        .find(|suffix| !is_public_suffix(suffix))
+        .unwrap_or(host)
+}
+
+/// Parses a `Set-Cookie` header received from `url`. Returns `None` for
+/// malformed cookies and ones `url` may not set.
+pub fn parse_set_cookie(header: &str, url: &Url, now: u64) -> Option<Cookie> {
+    let host = url.host.as_deref().unwrap_or("");
+    // Control characters, tabs among them, have no place in a cookie and
+    // would break the file cookies are saved in.
+    if header.chars().any(char::is_control) {
+        return None;
+    }
+    let mut parts = header.split(';');
+
+    let (name, value) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let mut cookie = Cookie {
+        name: name.to_string(),
+        value: value.trim().trim_matches('"').to_string(),
+        domain: host.to_string(),
+        host_only: true,
+        path: default_path(&url.path),
+        expires: None,
+        secure: false,
+        http_only: false,
+        same_site: SameSite::default(),
+    };
+    let mut max_age = None;
+
+    for attribute in parts {
+        let (key, value) = match attribute.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => (attribute.trim(), ""),
+        };
+
+        match key.to_ascii_lowercase().as_str() {
+            "domain" if !value.is_empty() => {
+                let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                // A cookie may only be set for the host itself or a parent
+                // domain, and not for a whole public suffix such as co.uk.
+                let allowed = host == domain
+                    || (host.ends_with(&domain)
+                        && host[..host.len() - domain.len()].ends_with('.'));
+                if !allowed || is_public_suffix(&domain) && domain != host {
+                    return None;
+                }
+                // A host that is itself a public suffix keeps its cookies to itself.
+                if !is_public_suffix(&domain) {
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+            }
+            "path" if value.starts_with('/') => cookie.path = value.to_string(),
+            "expires" => cookie.expires = date::parse(value).or(cookie.expires),
+            "max-age" => max_age = value.parse::<i64>().ok(),
+            "secure" => cookie.secure = true,
+            "httponly" => cookie.http_only = true,
+            "samesite" => cookie.same_site = SameSite::parse(value).unwrap_or_default(),
+            _ => {}
+        }
+    }
+
+    // Max-Age takes precedence over Expires.
+    if let Some(max_age) = max_age {
+        cookie.expires = Some(now.saturating_add_signed(max_age.max(-(now as i64))));
+    }
+
+    if cookie.secure && url.scheme != "https" {
+        return None;
+    }
+    if cookie.same_site == SameSite::None && !cookie.secure {
+        return None;
+    }
+
+    Some(cookie)
+}
+
+/// Cookies received from servers, optionally saved to a file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CookieJar {
+    pub cookies: Vec<Cookie>,
+    /// Where cookies with an expiry are saved whenever they change.
+    pub file: Option<PathBuf>,
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        CookieJar::default()
+    }
+
+    /// Loads the cookies saved in `file`, which is kept up to date from then on.
+    /// A missing file is an empty jar.
+    pub fn open(file: PathBuf) -> Result<Self, String> {
+        let mut jar = CookieJar {
+            cookies: Vec::new(),
+            file: Some(file.clone()),
+        };
+
+        let text = match fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(jar),
+            Err(error) => return Err(format!("Could not read {}: {}", file.display(), error)),
+        };
+
+        let now = date::now();
+        for line in text.lines().filter(|line| !line.starts_with('#')) {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let [
+                domain,
+                host_only,
+                path,
+                secure,
+                http_only,
+                same_site,
+                expires,
+                name,
+                value,
+            ] = fields[..]
+            else {
+                continue;
+            };
+
+            let cookie = Cookie {
+                name: name.to_string(),
+                value: value.to_string(),
+                domain: domain.to_string(),
+                host_only: host_only == "TRUE",
+                path: path.to_string(),
+                expires: expires.parse().ok(),
+                secure: secure == "TRUE",
+                http_only: http_only == "TRUE",
+                same_site: SameSite::parse(same_site).unwrap_or_default(),
+            };
+            if !cookie.is_expired(now) {
+                jar.cookies.push(cookie);
+            }
+        }
+
+        Ok(jar)
+    }
+
+    /// Writes the cookies that outlive the session to the jar's file, which
+    /// only the user may read.
+    pub fn save(&self) -> Result<(), String> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+
+        let flag = |value: bool| if value { "TRUE" } else { "FALSE" };
+        let mut text = String::from(
+            "# hyperline cookies: domain, host only, path, secure, HTTP only, SameSite, expires, name, value\n",
+        );
+        for cookie in &self.cookies {
+            let Some(expires) = cookie.expires else {
+                continue;
+            };
+            text.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                cookie.domain,
+                flag(cookie.host_only),
+                cookie.path,
+                flag(cookie.secure),
+                flag(cookie.http_only),
+                cookie.same_site.as_str(),
+                expires,
+                cookie.name,
+                cookie.value
+            ));
+        }
+
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+        }
+        File::options()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(file)
+            .and_then(|mut opened| {
+                // A file made before cookies were kept private may be readable by others.
+                opened.set_permissions(Permissions::from_mode(0o600))?;
+                opened.write_all(text.as_bytes())
+            })
+            .map_err(|error| format!("Could not save cookies: {}", error))
+    }
+
+    /// Adds or replaces a cookie. A cookie that has already expired deletes
+    /// the one it replaces.
+    pub fn insert(&mut self, cookie: Cookie, now: u64) {
+        self.cookies.retain(|existing| !existing.same_key(&cookie));
+        if !cookie.is_expired(now) {
+            self.cookies.push(cookie);
+        }
+    }
+
+    /// Stores the cookies set by a response from `url`.
+    pub fn store<'a>(&mut self, url: &Url, set_cookies: impl Iterator<Item = &'a str>, now: u64) {
+        let mut changed = false;
+        for header in set_cookies {
+            if let Some(cookie) = parse_set_cookie(header, url, now) {
+                self.insert(cookie, now);
+                changed = true;
+            }
+        }
+
+        if changed {
+            // Saving is best effort; the cookies still work for this session.
+            let _ = self.save();
+        }
+    }
+
+    /// The `Cookie` header to send to `url`. `initiator` is the page that
+    /// caused the request, or `None` when the user asked for it directly.
+    pub fn header(
+        &mut self,
+        url: &Url,
+        initiator: Option<&Url>,
+        top_level_get: bool,
+        now: u64,
+    ) -> Option<String> {
+        self.cookies.retain(|cookie| !cookie.is_expired(now));
+
+        let host = url.host.as_deref().unwrap_or("");
+        let cross_site = initiator
+            .is_some_and(|initiator| site(initiator.host.as_deref().unwrap_or("")) != site(host));
+
+        let mut matching = self
+            .cookies
+            .iter()
+            .filter(|cookie| cookie.domain_matches(host) && cookie.path_matches(&url.path))
+            .filter(|cookie| !cookie.secure || url.scheme == "https")
+            .filter(|cookie| match (cross_site, cookie.same_site) {
+                (false, _) | (true, SameSite::None) => true,
+                (true, SameSite::Lax) => top_level_get,
+                (true, SameSite::Strict) => false,
+            })
+            .collect::<Vec<_>>();
+
+        if matching.is_empty() {
+            return None;
+        }
+
+        // Cookies with longer paths are sent first.
+        matching.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+        Some(
+            matching
+                .iter()
+                .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+                .collect::<Vec<_>>()
+                .join("; "),
+        )
+    }
+
+    /// Removes the cookie called `name` set for `domain` and `path`.
+    pub fn remove(&mut self, domain: &str, path: &str, name: &str) {
+        self.cookies.retain(|cookie| {
+            !(cookie.domain == domain && cookie.path == path && cookie.name == name)
+        });
+        let _ = self.save();
+    }
+
+    /// Removes every cookie, or only those for `domain`.
+    pub fn clear(&mut self, domain: Option<&str>) {
+        self.cookies
+            .retain(|cookie| domain.is_some_and(|domain| cookie.domain != domain));
+        let _ = self.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn url(text: &str) -> Url {
+        Url::parse(text).unwrap()
+    }
+
+    fn set(header: &str, from: &str) -> Option<Cookie> {
+        parse_set_cookie(header, &url(from), NOW)
+    }
+
+    fn filled(headers: &[(&str, &str)]) -> CookieJar {
+        let mut jar = CookieJar::new();
+        for (header, from) in headers {
+            jar.store(&url(from), [*header].into_iter(), NOW);
+        }
+        jar
+    }
+
+    #[test]
+    fn parses_attributes() {
+        let cookie = set(
+            "id=\"a1\"; Domain=.Example.com; Path=/docs; Max-Age=60; Secure; HttpOnly; \
+             SameSite=Strict; Expires=Wed, 21 Oct 2015 07:28:00 GMT",
+            "https://www.example.com/docs/page",
+        )
+        .unwrap();
+
+        assert_eq!(cookie.name, "id");
+        assert_eq!(cookie.value, "a1");
+        assert_eq!(cookie.domain, "example.com");
+        assert!(!cookie.host_only);
+        assert_eq!(cookie.path, "/docs");
+        assert_eq!(cookie.expires, Some(NOW + 60));
+        assert!(cookie.secure && cookie.http_only);
+        assert_eq!(cookie.same_site, SameSite::Strict);
+    }
+
+    #[test]
+    fn defaults_to_the_host_and_directory() {
+        let cookie = set("a=1", "http://example.com/one/two").unwrap();
+        assert_eq!(cookie.domain, "example.com");
+        assert!(cookie.host_only);
+        assert_eq!(cookie.path, "/one");
+        assert_eq!(cookie.expires, None);
+        assert_eq!(cookie.same_site, SameSite::Lax);
+
+        assert_eq!(set("a=1", "http://example.com/").unwrap().path, "/");
+        assert_eq!(
+            set("a=1; Path=relative", "http://example.com/x/y")
+                .unwrap()
+                .path,
+            "/x"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_and_misplaced_cookies() {
+        assert_eq!(set("no equals sign", "http://example.com/"), None);
+        assert_eq!(set("=value", "http://example.com/"), None);
+        assert_eq!(set("a=1; Secure", "http://example.com/"), None);
+        assert_eq!(set("a=1; SameSite=None", "https://example.com/"), None);
+        assert!(set("a=1; SameSite=None; Secure", "https://example.com/").is_some());
+
+        assert_eq!(set("a=1; Domain=other.com", "http://example.com/"), None);
+        assert_eq!(set("a=1; Domain=ample.com", "http://example.com/"), None);
+        assert_eq!(
+            set("a=1; Domain=www.example.com", "http://example.com/"),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_public_suffix_domains() {
+        assert_eq!(set("a=1; Domain=com", "http://example.com/"), None);
+        assert_eq!(set("a=1; Domain=co.uk", "http://shop.co.uk/"), None);
+        assert_eq!(
+            set("a=1; Domain=.github.io", "https://someone.github.io/"),
+            None
+        );
+
+        let cookie = set("a=1; Domain=shop.co.uk", "http://www.shop.co.uk/").unwrap();
+        assert_eq!(cookie.domain, "shop.co.uk");
+
+        // A host may still set a cookie for itself, just not for its subdomains.
+        let cookie = set("a=1; Domain=localhost", "http://localhost/").unwrap();
+        assert!(cookie.host_only);
+    }
+
+    #[test]
+    fn rejects_control_characters() {
+        assert_eq!(set("a=one\ttwo", "http://example.com/"), None);
+        assert_eq!(set("a\t=1", "http://example.com/"), None);
+        assert_eq!(set("a=1; Path=/x\ty", "http://example.com/"), None);
+        assert_eq!(set("a=\u{1b}[31m", "http://example.com/"), None);
+    }
+
+    #[test]
+    fn sends_matching_cookies_longest_path_first() {
+        let mut jar = filled(&[
+            ("root=1", "http://example.com/"),
+            ("deep=2; Path=/a/b", "http://example.com/"),
+            ("shared=3; Domain=example.com", "http://www.example.com/"),
+            ("secure=4; Secure", "https://example.com/"),
+            ("other=5", "http://other.com/"),
+        ]);
+
+        let mut header = |target: &str| jar.header(&url(target), None, true, NOW);
+        assert_eq!(
+            header("http://example.com/a/b/c").unwrap(),
+            "deep=2; root=1; shared=3"
+        );
+        assert_eq!(
+            header("http://example.com/a/bc").unwrap(),
+            "root=1; shared=3"
+        );
+        assert_eq!(
+            header("https://example.com/").unwrap(),
+            "root=1; shared=3; secure=4"
+        );
+        assert_eq!(header("http://sub.example.com/").unwrap(), "shared=3");
+        assert_eq!(header("http://notexample.com/"), None);
+    }
+
+    #[test]
+    fn same_site_limits_cross_site_requests() {
+        let mut jar = filled(&[
+            ("lax=1", "https://example.com/"),
+            ("strict=2; SameSite=Strict", "https://example.com/"),
+            ("none=3; SameSite=None; Secure", "https://example.com/"),
+        ]);
+        let target = url("https://example.com/");
+        let mut header = |initiator: &str, top_level_get| {
+            jar.header(&target, Some(&url(initiator)), top_level_get, NOW)
+        };
+
+        let all = Some("lax=1; strict=2; none=3".to_string());
+        assert_eq!(header("https://www.example.com/", false), all);
+        assert_eq!(
+            header("https://evil.com/", true),
+            Some("lax=1; none=3".into())
+        );
+        assert_eq!(header("https://evil.com/", false), Some("none=3".into()));
+        // Sites under different owners of the same public suffix are not the same site.
+        assert_eq!(site("a.example.co.uk"), "example.co.uk");
+        assert_eq!(site("example.co.uk"), "example.co.uk");
+        assert_eq!(site("someone.github.io"), "someone.github.io");
+        assert_eq!(site("127.0.0.1"), "127.0.0.1");
+    }
+
+    #[test]
+    fn expired_cookies_delete_and_are_dropped() {
+        let mut jar = filled(&[("a=1; Max-Age=10", "http://example.com/")]);
+        let target = url("http://example.com/");
+        assert_eq!(jar.header(&target, None, true, NOW + 5), Some("a=1".into()));
+        assert_eq!(jar.header(&target, None, true, NOW + 10), None);
+        assert!(jar.cookies.is_empty());
+
+        let mut jar = filled(&[
+            ("a=1", "http://example.com/"),
+            ("a=gone; Max-Age=0", "http://example.com/"),
+        ]);
+        assert_eq!(jar.header(&target, None, true, NOW), None);
+    }
+
+    #[test]
+    fn saves_privately_and_loads_again() {
+        let dir = env::temp_dir().join(format!("hyperline-cookies-{}", process::id()));
+        let file = dir.join("cookies.txt");
+        let _ = fs::remove_dir_all(&dir);
+
+        let expires = date::now() + 3600;
+        let mut jar = CookieJar::open(file.clone()).unwrap();
+        assert!(jar.cookies.is_empty());
+        let headers = [
+            format!(
+                "kept=1; Path=/p; Expires={}; HttpOnly",
+                date::format(expires)
+            ),
+            "session=2".to_string(),
+        ];
+        jar.store(
+            &url("http://example.com/"),
+            headers.iter().map(String::as_str),
+            NOW,
+        );
+
+        let mode = fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let loaded = CookieJar::open(file.clone()).unwrap();
+        assert_eq!(loaded.cookies.len(), 1);
+        assert_eq!(loaded.cookies[0], jar.cookies[0]);
+
+        // Files saved before cookies were private are tightened too.
+        fs::set_permissions(&file, Permissions::from_mode(0o644)).unwrap();
+        loaded.save().unwrap();
+        let mode = fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
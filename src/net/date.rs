@@ -0,0 +1,141 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+
+/// Seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+/// Parses the date formats found in HTTP headers and cookies, such as
+/// `Sun, 06 Nov 1994 08:49:37 GMT`, `Sunday, 06-Nov-94 08:49:37 GMT` and
+/// `Sun Nov  6 08:49:37 1994`. Returns seconds since the Unix epoch.
+pub fn parse(date: &str) -> Option<u64> {
+    let (mut day, mut month, mut year, mut time) = (None, None, None, None);
+
+    for token in date.split(|c: char| c.is_whitespace() || c == ',' || c == '-') {
+        if token.is_empty() {
+            continue;
+        }
+
+        let lower = token.to_ascii_lowercase();
+        if time.is_none() && token.contains(':') {
+            let mut parts = token.split(':').map(|part| part.parse::<u64>().ok());
+            let (h, m, s) = (
+                parts.next()??,
+                parts.next()??,
+                parts.next().flatten().unwrap_or(0),
+            );
+            time = Some(h * 3600 + m * 60 + s);
+        } else if month.is_none()
+            && let Some(index) = MONTHS.iter().position(|name| lower.starts_with(name))
+        {
+            month = Some(index as u32 + 1);
+        } else if let Ok(number) = token.parse::<i64>() {
+            if day.is_none() && (1..=31).contains(&number) && token.len() <= 2 {
+                day = Some(number as u32);
+            } else if year.is_none() {
+                // Two-digit years, as in RFC 850 dates and old cookies.
+                year = Some(match number {
+                    0..=69 => number + 2000,
+                    70..=99 => number + 1900,
+                    _ => number,
+                });
+            }
+        }
+    }
+
+    let days = days_from_civil(year?, month?, day?);
+    let seconds = days * 86400 + time.unwrap_or(0) as i64;
+    u64::try_from(seconds).ok()
+}
+
+/// Formats a time as an HTTP date, like `Sun, 06 Nov 1994 08:49:37 GMT`.
+pub fn format(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64;
+    let time = seconds % 86400;
+    let (year, month, day) = civil_from_days(days);
+
+    let month = MONTHS[month as usize - 1];
+    let month = format!("{}{}", month[..1].to_ascii_uppercase(), &month[1..]);
+
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[(days % 7) as usize],
+        day,
+        month,
+        year,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sun, 06 Nov 1994 08:49:37 GMT, the example date of the HTTP RFCs.
+    const EXAMPLE: u64 = 784111777;
+
+    #[test]
+    fn parses_http_date_formats() {
+        assert_eq!(parse("Sun, 06 Nov 1994 08:49:37 GMT"), Some(EXAMPLE));
+        assert_eq!(parse("Sunday, 06-Nov-94 08:49:37 GMT"), Some(EXAMPLE));
+        assert_eq!(parse("Sun Nov  6 08:49:37 1994"), Some(EXAMPLE));
+        assert_eq!(parse("sun, 6 NOVEMBER 1994 8:49:37"), Some(EXAMPLE));
+        assert_eq!(parse("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0));
+        assert_eq!(parse("Wed, 01 Jan 2020"), Some(1577836800));
+        assert_eq!(parse("Tue, 29 Feb 2000 12:00:00 GMT"), Some(951825600));
+        assert_eq!(parse("01-Jan-30 00:00:00 GMT"), Some(1893456000));
+    }
+
+    #[test]
+    fn rejects_incomplete_dates() {
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("Sun, 06 1994 08:49:37 GMT"), None);
+        assert_eq!(parse("06 Nov 08:49:37"), None);
+        assert_eq!(parse("Sun, 06 Nov 1994 08:xx:37 GMT"), None);
+        assert_eq!(parse("Wed, 31 Dec 1969 23:59:59 GMT"), None);
+    }
+
+    #[test]
+    fn formats_and_reads_back() {
+        assert_eq!(format(EXAMPLE), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(format(0), "Thu, 01 Jan 1970 00:00:00 GMT");
+        for seconds in [0, EXAMPLE, 951825600, 4102444799, 253402300799] {
+            assert_eq!(parse(&format(seconds)), Some(seconds));
+        }
+    }
+}
@@ -0,0 +1,985 @@
+use std::fmt;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use rustls::ClientConfig;
+
+use crate::browser::url::Url;
+use crate::net::cache::{self, Entry, HttpCache};
+use crate::net::charset;
+use crate::net::cookies::CookieJar;
+use crate::net::date;
+use crate::net::tls::{self, TlsOptions};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Method {
+    #[default]
+    Get,
+    Post,
+    Head,
+}
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Head => "HEAD",
+        }
+    }
+}
+
+/// Header fields in the order they were sent or received. Names compare
+/// case-insensitively.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Headers(pub Vec<(String, String)>);
+
+impl Headers {
+    /// The first value of the header `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.0
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.0.push((name.into(), value.into()));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Request {
+    pub method: Method,
+    pub url: Url,
+    /// Sent along with the headers the client adds itself, such as `Host`.
+    pub headers: Headers,
+    pub body: Vec<u8>,
+    /// The page that caused the request, such as the one a link was on. `None`
+    /// when the user asked for the URL directly.
+    pub initiator: Option<Url>,
+}
+
+impl Request {
+    pub fn get(url: Url) -> Self {
+        Request {
+            method: Method::Get,
+            url,
+            headers: Headers::default(),
+            body: Vec::new(),
+            initiator: None,
+        }
+    }
+
+    pub fn post(url: Url, content_type: &str, body: Vec<u8>) -> Self {
+        let mut headers = Headers::default();
+        headers.insert("Content-Type", content_type);
+
+        Request {
+            method: Method::Post,
+            url,
+            headers,
+            body,
+            initiator: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Response {
+    /// Where the response came from, after following redirects.
+    pub url: Url,
+    pub status: u16,
+    pub reason: String,
+    pub headers: Headers,
+    /// The body with any content encoding already undone.
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The media type without parameters, like `text/html`.
+    pub fn mime_type(&self) -> String {
+        self.headers
+            .get("Content-Type")
+            .and_then(|value| value.split(';').next())
+            .map(|mime| mime.trim().to_ascii_lowercase())
+            .unwrap_or_default()
+    }
+
+    /// The charset of the body, from the `Content-Type` header or, for HTML,
+    /// from the document itself.
+    pub fn charset(&self) -> Option<String> {
+        self.headers
+            .get("Content-Type")
+            .and_then(charset::from_content_type)
+            .or_else(|| {
+                (self.mime_type() == "text/html")
+                    .then(|| charset::from_html(&self.body))
+                    .flatten()
+            })
+    }
+
+    /// The body decoded as text in its charset.
+    pub fn text(&self) -> String {
+        charset::decode(&self.body, self.charset().as_deref())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HttpError {
+    UnsupportedScheme(String),
+    /// The host name could not be resolved or connected to.
+    Connect(String),
+    Timeout,
+    Io(String),
+    InvalidResponse(String),
+    TooManyRedirects,
+    /// The server's certificate could not be verified.
+    Certificate {
+        host: String,
+        reason: String,
+    },
+    /// The TLS connection could not be set up, for a reason other than the certificate.
+    Tls(String),
+    /// The cache is offline and has no response for the URL.
+    Offline,
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpError::UnsupportedScheme(scheme) => {
+                write!(f, "'{}' is not supported over HTTP.", scheme)
+            }
+            HttpError::Connect(error) => write!(f, "Could not connect: {}", error),
+            HttpError::Timeout => write!(f, "The server took too long to respond."),
+            HttpError::Io(error) => write!(f, "Connection error: {}", error),
+            HttpError::InvalidResponse(error) => write!(f, "Invalid response: {}", error),
+            HttpError::TooManyRedirects => write!(f, "Too many redirects."),
+            HttpError::Certificate { host, reason } => write!(
+                f,
+                "The certificate of {} could not be verified: {}.",
+                host, reason
+            ),
+            HttpError::Tls(error) => write!(f, "Secure connection failed: {}", error),
+            HttpError::Offline => write!(f, "Hyperline is offline and this page is not cached."),
+        }
+    }
+}
+
+impl From<std::io::Error> for HttpError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            ErrorKind::TimedOut | ErrorKind::WouldBlock => HttpError::Timeout,
+            _ => HttpError::Io(error.to_string()),
+        }
+    }
+}
+
+/// Anything a request can be sent over.
+pub trait Connection: Read + Write {}
+
+impl<T: Read + Write> Connection for T {}
+
+/// An HTTP/1.1 client. Each request uses a fresh connection.
+#[derive(Clone, Debug)]
+pub struct Client {
+    /// Limit for connecting and for each read or write.
+    pub timeout: Duration,
+    pub max_redirects: usize,
+    /// The most bytes a response body may have, both as sent and once decoded.
+    pub max_body_size: usize,
+    pub user_agent: String,
+    pub tls: TlsOptions,
+    pub cookies: CookieJar,
+    pub cache: HttpCache,
+    /// Built from `tls` on the first https request.
+    tls_config: OnceLock<Result<Arc<ClientConfig>, String>>,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client {
+            timeout: Duration::from_secs(30),
+            max_redirects: 10,
+            max_body_size: 64 << 20,
+            user_agent: format!("hyperline/{}", env!("CARGO_PKG_VERSION")),
+            tls: TlsOptions::default(),
+            cookies: CookieJar::new(),
+            cache: HttpCache::new(),
+            tls_config: OnceLock::new(),
+        }
+    }
+}
+
+impl Client {
+    pub fn new() -> Self {
+        Client::default()
+    }
+
+    pub fn with_tls(tls: TlsOptions) -> Self {
+        Client {
+            tls,
+            ..Client::default()
+        }
+    }
+
+    fn connect(&self, url: &Url) -> Result<Box<dyn Connection>, HttpError> {
+        if url.scheme != "http" && url.scheme != "https" {
+            return Err(HttpError::UnsupportedScheme(url.scheme.clone()));
+        }
+
+        let host = url.host.as_deref().unwrap_or_default();
+        let port = url.port_or_default().unwrap_or(80);
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        let addresses = (host, port)
+            .to_socket_addrs()
+            .map_err(|error| HttpError::Connect(format!("{}: {}", host, error)))?;
+
+        let mut last_error = HttpError::Connect(format!("{}: no addresses found", host));
+        for address in addresses {
+            match TcpStream::connect_timeout(&address, self.timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.timeout))?;
+                    stream.set_write_timeout(Some(self.timeout))?;
+
+                    if url.scheme == "http" {
+                        return Ok(Box::new(stream));
+                    }
+
+                    let config = self
+                        .tls_config
+                        .get_or_init(|| tls::config(&self.tls))
+                        .clone()
+                        .map_err(HttpError::Tls)?;
+                    return Ok(Box::new(tls::connect(config, host, stream)?));
+                }
+                Err(error) if error.kind() == ErrorKind::TimedOut => {
+                    last_error = HttpError::Timeout;
+                }
+                Err(error) => last_error = HttpError::Connect(error.to_string()),
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Sends one request, without following redirects.
+    fn exchange(&self, request: &Request, cookie: Option<String>) -> Result<Response, HttpError> {
+        let mut connection = self.connect(&request.url)?;
+
+        let host = match request.url.port {
+            Some(port) => format!("{}:{}", request.url.host.as_deref().unwrap_or(""), port),
+            None => request.url.host.clone().unwrap_or_default(),
+        };
+
+        let mut head = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\n",
+            request.method.as_str(),
+            request.url.request_target(),
+            host
+        );
+        let defaults = [
+            ("User-Agent", self.user_agent.as_str()),
+            ("Accept", "text/html,text/plain;q=0.9,*/*;q=0.8"),
+            ("Accept-Encoding", "gzip, deflate"),
+            ("Connection", "close"),
+        ];
+        for (name, value) in defaults {
+            if !request.headers.contains(name) {
+                head.push_str(&format!("{}: {}\r\n", name, value));
+            }
+        }
+        for (name, value) in &request.headers.0 {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if let Some(cookie) = cookie {
+            head.push_str(&format!("Cookie: {}\r\n", cookie));
+        }
+        if !request.body.is_empty() || request.method == Method::Post {
+            head.push_str(&format!("Content-Length: {}\r\n", request.body.len()));
+        }
+        head.push_str("\r\n");
+
+        connection.write_all(head.as_bytes())?;
+        connection.write_all(&request.body)?;
+        connection.flush()?;
+
+        read_response(BufReader::new(connection), request, self.max_body_size)
+    }
+
+    /// Sends one request through the cache, without following redirects.
+    /// Fresh stored responses are used as they are, and stale ones are
+    /// checked with the server when they have validators.
+    fn exchange_cached(&mut self, request: &Request) -> Result<Response, HttpError> {
+        let now = date::now();
+        let stored = match request.method {
+            Method::Get => self.cache.get(&request.url),
+            _ => None,
+        };
+
+        if let Some(entry) = &stored
+            && (self.cache.offline
+                || (entry.is_fresh(now) && !cache::wants_revalidation(&request.headers)))
+        {
+            return Ok(entry.response.clone());
+        }
+        if self.cache.offline {
+            return Err(HttpError::Offline);
+        }
+
+        let mut conditional = request.clone();
+        if let Some(entry) = &stored {
+            conditional.headers.0.extend(entry.conditions().0);
+        }
+
+        let cookie = self.cookies.header(
+            &request.url,
+            request.initiator.as_ref(),
+            request.method == Method::Get,
+            now,
+        );
+        let response = self.exchange(&conditional, cookie)?;
+        let now = date::now();
+        self.cookies
+            .store(&request.url, response.headers.get_all("Set-Cookie"), now);
+
+        if let (304, Some(mut entry)) = (response.status, stored) {
+            entry.refresh(&response.headers, now);
+            // Caching is best effort; the response is still good.
+            let _ = self.cache.insert(&request.url, &entry);
+            return Ok(entry.response);
+        }
+
+        match request.method {
+            Method::Get if cache::is_storable(&request.headers, &response) => {
+                let entry = Entry {
+                    response,
+                    stored: now,
+                };
+                let _ = self.cache.insert(&request.url, &entry);
+                return Ok(entry.response);
+            }
+            Method::Get | Method::Head => {}
+            // A successful POST may have changed what the URL shows.
+            Method::Post => self.cache.remove(&request.url),
+        }
+
+        Ok(response)
+    }
+
+    /// Sends `request`, following redirects.
+    pub fn send(&mut self, request: Request) -> Result<Response, HttpError> {
+        let mut request = request;
+
+        for _ in 0..=self.max_redirects {
+            let response = self.exchange_cached(&request)?;
+
+            let location = response.headers.get("Location");
+            let redirect = matches!(response.status, 301 | 302 | 303 | 307 | 308);
+            let (true, Some(location)) = (redirect, location) else {
+                return Ok(response);
+            };
+
+            request.url = request
+                .url
+                .join(location)
+                .map_err(HttpError::InvalidResponse)?;
+
+            // Like browsers, anything but 307 and 308 turns a POST into a GET.
+            if !matches!(response.status, 307 | 308) && request.method == Method::Post {
+                request.method = Method::Get;
+                request.body.clear();
+                request
+                    .headers
+                    .0
+                    .retain(|(name, _)| !name.eq_ignore_ascii_case("Content-Type"));
+            }
+        }
+
+        Err(HttpError::TooManyRedirects)
+    }
+
+    pub fn get(&mut self, url: Url) -> Result<Response, HttpError> {
+        self.send(Request::get(url))
+    }
+}
+
+/// The longest status or header line that is read.
+const MAX_LINE: u64 = 64 << 10;
+
+/// How many interim (1xx) responses may come before the final one.
+const MAX_INTERIM: usize = 16;
+
+fn too_large(limit: usize) -> HttpError {
+    HttpError::InvalidResponse(format!("the body is larger than {} bytes", limit))
+}
+
+fn closed_early() -> HttpError {
+    HttpError::InvalidResponse("the connection closed early".to_string())
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<String, HttpError> {
+    let mut line = Vec::new();
+    reader.take(MAX_LINE).read_until(b'\n', &mut line)?;
+    if line.is_empty() {
+        return Err(closed_early());
+    }
+    if line.last() != Some(&b'\n') && line.len() as u64 == MAX_LINE {
+        return Err(HttpError::InvalidResponse(format!(
+            "a line is longer than {} bytes",
+            MAX_LINE
+        )));
+    }
+    Ok(String::from_utf8_lossy(&line).trim_end().to_string())
+}
+
+/// Appends exactly `size` bytes to `body`, which may grow to at most `limit`
+/// bytes. The buffer grows as data arrives, not by what the server announced.
+fn read_exact_into(
+    reader: &mut impl Read,
+    body: &mut Vec<u8>,
+    size: usize,
+    limit: usize,
+) -> Result<(), HttpError> {
+    if size > limit.saturating_sub(body.len()) {
+        return Err(too_large(limit));
+    }
+    if reader.take(size as u64).read_to_end(body)? < size {
+        return Err(closed_early());
+    }
+    Ok(())
+}
+
+fn read_chunked(reader: &mut impl BufRead, limit: usize) -> Result<Vec<u8>, HttpError> {
+    let mut body = Vec::new();
+
+    loop {
+        let line = read_line(reader)?;
+        let size = line.split(';').next().unwrap_or("").trim();
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| HttpError::InvalidResponse(format!("bad chunk size '{}'", size)))?;
+
+        if size == 0 {
+            // Trailer fields are read and dropped.
+            while !read_line(reader)?.is_empty() {}
+            return Ok(body);
+        }
+
+        read_exact_into(reader, &mut body, size, limit)?;
+        read_line(reader)?;
+    }
+}
+
+fn decode_content(
+    body: Vec<u8>,
+    encoding: Option<&str>,
+    limit: usize,
+) -> Result<Vec<u8>, HttpError> {
+    if body.is_empty() {
+        return Ok(body);
+    }
+
+    // One byte more than the limit is enough to tell that it was exceeded.
+    let most = limit as u64 + 1;
+    let mut decoded = Vec::new();
+    let result = match encoding.map(|e| e.trim().to_ascii_lowercase()).as_deref() {
+        Some("gzip" | "x-gzip") => GzDecoder::new(&body[..])
+            .take(most)
+            .read_to_end(&mut decoded),
+        // Some servers send raw deflate data instead of the zlib format.
+        Some("deflate") => ZlibDecoder::new(&body[..])
+            .take(most)
+            .read_to_end(&mut decoded)
+            .or_else(|_| {
+                decoded.clear();
+                DeflateDecoder::new(&body[..])
+                    .take(most)
+                    .read_to_end(&mut decoded)
+            }),
+        _ => return Ok(body),
+    };
+
+    result.map_err(|error| HttpError::InvalidResponse(error.to_string()))?;
+    if decoded.len() > limit {
+        return Err(too_large(limit));
+    }
+    Ok(decoded)
+}
+
+/// Reads a status line and the header fields after it.
+fn read_head(reader: &mut impl BufRead) -> Result<(u16, String, Headers), HttpError> {
+    let status_line = read_line(reader)?;
+    let mut parts = status_line.splitn(3, ' ');
+
+    let version = parts.next().unwrap_or("");
+    if !version.starts_with("HTTP/") {
+        return Err(HttpError::InvalidResponse(format!(
+            "bad status line '{}'",
+            status_line
+        )));
+    }
+    let status = parts
+        .next()
+        .and_then(|status| status.parse::<u16>().ok())
+        .ok_or_else(|| HttpError::InvalidResponse(format!("bad status line '{}'", status_line)))?;
+    let reason = parts.next().unwrap_or("").to_string();
+
+    let mut headers = Headers::default();
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim(), value.trim());
+        }
+    }
+
+    Ok((status, reason, headers))
+}
+
+fn read_response(
+    mut reader: impl BufRead,
+    request: &Request,
+    limit: usize,
+) -> Result<Response, HttpError> {
+    // Interim responses are skipped; the final one follows.
+    let mut interim = 0;
+    let (status, reason, headers) = loop {
+        let head = read_head(&mut reader)?;
+        if !(100..200).contains(&head.0) {
+            break head;
+        }
+        interim += 1;
+        if interim > MAX_INTERIM {
+            return Err(HttpError::InvalidResponse(
+                "too many interim responses".to_string(),
+            ));
+        }
+    };
+
+    let has_body = request.method != Method::Head && status != 204 && status != 304;
+    let chunked = headers
+        .get("Transfer-Encoding")
+        .is_some_and(|encoding| encoding.to_ascii_lowercase().contains("chunked"));
+    let length = headers
+        .get("Content-Length")
+        .and_then(|length| length.trim().parse::<usize>().ok());
+
+    let body = match (has_body, chunked, length) {
+        (false, _, _) => Vec::new(),
+        (true, true, _) => read_chunked(&mut reader, limit)?,
+        (true, false, Some(length)) => {
+            let mut body = Vec::new();
+            read_exact_into(&mut reader, &mut body, length, limit)?;
+            body
+        }
+        (true, false, None) => {
+            let mut body = Vec::new();
+            match reader.take(limit as u64 + 1).read_to_end(&mut body) {
+                // Many servers close TLS connections without saying so first.
+                Err(error) if error.kind() == ErrorKind::UnexpectedEof => {}
+                result => {
+                    result?;
+                }
+            }
+            if body.len() > limit {
+                return Err(too_large(limit));
+            }
+            body
+        }
+    };
+
+    Ok(Response {
+        url: request.url.clone(),
+        status,
+        reason,
+        body: decode_content(body, headers.get("Content-Encoding"), limit)?,
+        headers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    use flate2::Compression;
+    use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+
+    use super::*;
+
+    /// A server on 127.0.0.1 that answers one connection with each of
+    /// `responses` in turn, and hands back the requests it was sent.
+    fn serve(responses: Vec<Vec<u8>>) -> (Url, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+
+                let mut request = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("Content-Length")
+                    {
+                        length = value.trim().parse().unwrap();
+                    }
+                    request.push_str(&line);
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                request.push_str(&String::from_utf8(body).unwrap());
+
+                reader.get_mut().write_all(&response).unwrap();
+                requests.push(request);
+            }
+            requests
+        });
+
+        (url, server)
+    }
+
+    fn response(head: &str, body: &[u8]) -> Vec<u8> {
+        let mut response = format!("HTTP/1.1 {}\r\n\r\n", head).into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    fn ok(body: &str) -> Vec<u8> {
+        response(
+            &format!("200 OK\r\nContent-Length: {}", body.len()),
+            body.as_bytes(),
+        )
+    }
+
+    fn redirect(status: &str, location: &str) -> Vec<u8> {
+        response(
+            &format!("{}\r\nLocation: {}\r\nContent-Length: 0", status, location),
+            b"",
+        )
+    }
+
+    /// Reads a response from `bytes`, as if they came from a server.
+    fn parse(bytes: &[u8], method: Method, limit: usize) -> Result<Response, HttpError> {
+        let request = Request {
+            method,
+            ..Request::get(Url::parse("http://example.com/").unwrap())
+        };
+        read_response(bytes, &request, limit)
+    }
+
+    #[test]
+    fn get() {
+        let (url, server) = serve(vec![response(
+            "200 OK\r\nContent-Type: text/plain\r\nContent-Length: 5",
+            b"hello",
+        )]);
+
+        let response = Client::new().get(url.join("/a/b?q=1").unwrap()).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.reason, "OK");
+        assert_eq!(response.mime_type(), "text/plain");
+        assert_eq!(response.text(), "hello");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /a/b?q=1 HTTP/1.1\r\n"));
+        let host = format!("Host: 127.0.0.1:{}\r\n", url.port.unwrap());
+        assert!(requests[0].contains(&host), "{}", requests[0]);
+    }
+
+    #[test]
+    fn encodes_the_request_line() {
+        let (url, server) = serve(vec![ok("found")]);
+
+        let response = Client::new()
+            .get(url.join("/a file/ü?q=a b").unwrap())
+            .unwrap();
+        assert_eq!(response.text(), "found");
+
+        let requests = server.join().unwrap();
+        assert!(
+            requests[0].starts_with("GET /a%20file/%C3%BC?q=a%20b HTTP/1.1\r\n"),
+            "{}",
+            requests[0]
+        );
+    }
+
+    #[test]
+    fn post() {
+        let (url, server) = serve(vec![ok("done")]);
+
+        let request = Request::post(
+            url,
+            "application/x-www-form-urlencoded",
+            b"a=1&b=2".to_vec(),
+        );
+        assert_eq!(Client::new().send(request).unwrap().text(), "done");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST / HTTP/1.1\r\n"));
+        assert!(requests[0].contains("Content-Type: application/x-www-form-urlencoded\r\n"));
+        assert!(requests[0].contains("Content-Length: 7\r\n"));
+        assert!(requests[0].ends_with("\r\n\r\na=1&b=2"));
+    }
+
+    #[test]
+    fn head_responses_have_no_body() {
+        // The length is that of the body a GET would get, which is not sent.
+        let (url, server) = serve(vec![response("200 OK\r\nContent-Length: 100", b"")]);
+
+        let request = Request {
+            method: Method::Head,
+            ..Request::get(url)
+        };
+        let response = Client::new().send(request).unwrap();
+        assert_eq!(response.headers.get("content-length"), Some("100"));
+        assert!(response.body.is_empty());
+
+        assert!(server.join().unwrap()[0].starts_with("HEAD / HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn follows_redirects() {
+        let (url, server) = serve(vec![
+            redirect("301 Moved Permanently", "/b"),
+            redirect("302 Found", "c?x=1"),
+            ok("there"),
+        ]);
+
+        let response = Client::new().get(url.join("/a").unwrap()).unwrap();
+        assert_eq!(response.text(), "there");
+        assert_eq!(response.url, url.join("/c?x=1").unwrap());
+
+        let requests = server.join().unwrap();
+        assert!(requests[1].starts_with("GET /b HTTP/1.1\r\n"));
+        assert!(requests[2].starts_with("GET /c?x=1 HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn see_other_turns_a_post_into_a_get() {
+        let (url, server) = serve(vec![redirect("303 See Other", "/done"), ok("")]);
+
+        let request = Request::post(url, "text/plain", b"body".to_vec());
+        Client::new().send(request).unwrap();
+
+        let requests = server.join().unwrap();
+        assert!(requests[1].starts_with("GET /done HTTP/1.1\r\n"));
+        assert!(!requests[1].contains("Content-Type"));
+        assert!(!requests[1].contains("Content-Length"));
+        assert!(!requests[1].contains("body"));
+    }
+
+    #[test]
+    fn temporary_redirect_keeps_the_post() {
+        let (url, server) = serve(vec![redirect("307 Temporary Redirect", "/again"), ok("")]);
+
+        let request = Request::post(url, "text/plain", b"body".to_vec());
+        Client::new().send(request).unwrap();
+
+        let requests = server.join().unwrap();
+        assert!(requests[1].starts_with("POST /again HTTP/1.1\r\n"));
+        assert!(requests[1].ends_with("\r\n\r\nbody"));
+    }
+
+    #[test]
+    fn stops_after_too_many_redirects() {
+        let (url, server) = serve(vec![redirect("302 Found", "/"); 2]);
+
+        let mut client = Client::new();
+        client.max_redirects = 1;
+        assert_eq!(client.get(url), Err(HttpError::TooManyRedirects));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn chunked_bodies() {
+        let (url, server) = serve(vec![response(
+            "200 OK\r\nTransfer-Encoding: chunked",
+            b"5;name=value\r\nhello\r\n6\r\n world\r\n0\r\nTrailer: yes\r\n\r\n",
+        )]);
+
+        assert_eq!(Client::new().get(url).unwrap().text(), "hello world");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn decodes_gzip_and_deflate() {
+        let text = "squeezed ".repeat(100);
+
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(text.as_bytes()).unwrap();
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(text.as_bytes()).unwrap();
+        let mut raw = DeflateEncoder::new(Vec::new(), Compression::default());
+        raw.write_all(text.as_bytes()).unwrap();
+
+        let bodies = [
+            ("gzip", gzip.finish().unwrap()),
+            ("deflate", zlib.finish().unwrap()),
+            ("deflate", raw.finish().unwrap()),
+        ];
+        let (url, server) = serve(
+            bodies
+                .iter()
+                .map(|(encoding, body)| {
+                    let head = format!(
+                        "200 OK\r\nContent-Encoding: {}\r\nContent-Length: {}",
+                        encoding,
+                        body.len()
+                    );
+                    response(&head, body)
+                })
+                .collect(),
+        );
+
+        let mut client = Client::new();
+        for _ in bodies {
+            assert_eq!(client.get(url.clone()).unwrap().text(), text);
+        }
+        assert!(server.join().unwrap()[0].contains("Accept-Encoding: gzip, deflate\r\n"));
+    }
+
+    #[test]
+    fn detects_the_charset() {
+        let typed = |content_type: &str, body: &[u8]| {
+            let head = format!(
+                "200 OK\r\nContent-Type: {}\r\nContent-Length: {}",
+                content_type,
+                body.len()
+            );
+            response(&head, body)
+        };
+        let (url, server) = serve(vec![
+            typed("text/plain; charset=ISO-8859-1", b"caf\xe9"),
+            typed("text/html", b"<meta charset=\"windows-1252\"><p>\x80 5</p>"),
+        ]);
+
+        let mut client = Client::new();
+        let response = client.get(url.clone()).unwrap();
+        assert_eq!(response.charset().as_deref(), Some("iso-8859-1"));
+        assert_eq!(response.text(), "café");
+
+        let response = client.get(url).unwrap();
+        assert_eq!(response.charset().as_deref(), Some("windows-1252"));
+        assert!(response.text().contains("<p>€ 5</p>"));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn times_out_on_a_silent_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            thread::sleep(Duration::from_millis(500));
+            drop(stream);
+        });
+
+        let mut client = Client::new();
+        client.timeout = Duration::from_millis(100);
+        assert_eq!(client.get(url), Err(HttpError::Timeout));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn skips_interim_responses() {
+        let bytes = b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 103 Early Hints\r\nLink: </a>\r\n\r\n\
+                      HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok";
+        let response = parse(bytes, Method::Get, 100).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"ok");
+
+        let endless = b"HTTP/1.1 100 Continue\r\n\r\n".repeat(1000);
+        assert!(matches!(
+            parse(&endless, Method::Get, 100),
+            Err(HttpError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn bodies_are_limited_in_size() {
+        let too_large = |result: Result<Response, HttpError>| {
+            result
+                == Err(HttpError::InvalidResponse(
+                    "the body is larger than 10 bytes".into(),
+                ))
+        };
+
+        // None of these may allocate what the server claims to send.
+        let announced = b"HTTP/1.1 200 OK\r\nContent-Length: 18446744073709551615\r\n\r\n";
+        assert!(too_large(parse(announced, Method::Get, 10)));
+        let chunk = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nffffffffffffffff\r\n";
+        assert!(too_large(parse(chunk, Method::Get, 10)));
+        let chunks = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+                       6\r\n123456\r\n6\r\n123456\r\n0\r\n\r\n";
+        assert!(too_large(parse(chunks, Method::Get, 10)));
+        let unframed = b"HTTP/1.1 200 OK\r\n\r\n0123456789A";
+        assert!(too_large(parse(unframed, Method::Get, 10)));
+
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::best());
+        gzip.write_all(&[0; 1 << 20]).unwrap();
+        let gzip = gzip.finish().unwrap();
+        let mut bomb = format!(
+            "HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n",
+            gzip.len()
+        )
+        .into_bytes();
+        bomb.extend_from_slice(&gzip);
+        assert!(parse(&bomb, Method::Get, 1 << 20).is_ok());
+        assert_eq!(
+            parse(&bomb, Method::Get, (1 << 20) - 1),
+            Err(HttpError::InvalidResponse(
+                "the body is larger than 1048575 bytes".into()
+            ))
+        );
+
+        let exact = b"HTTP/1.1 200 OK\r\n\r\n0123456789";
+        assert_eq!(parse(exact, Method::Get, 10).unwrap().body, b"0123456789");
+    }
+
+    #[test]
+    fn truncated_bodies_are_invalid() {
+        let bytes = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort";
+        assert_eq!(
+            parse(bytes, Method::Get, 100),
+            Err(HttpError::InvalidResponse(
+                "the connection closed early".into()
+            ))
+        );
+        assert!(matches!(
+            parse(b"SSH-2.0-OpenSSH\r\n\r\n", Method::Get, 100),
+            Err(HttpError::InvalidResponse(_))
+        ));
+    }
+}
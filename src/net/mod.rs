@@ -0,0 +1,6 @@
+pub mod cache;
+pub mod charset;
+pub mod cookies;
+pub mod date;
+pub mod http;
+pub mod tls;
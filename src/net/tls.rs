@@ -0,0 +1,352 @@
+use std::io::{self, ErrorKind};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{self, CryptoProvider};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{
+    CertificateError, ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore,
+    SignatureScheme, StreamOwned,
+};
+
+use crate::net::http::HttpError;
+
+/// How https servers are verified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TlsOptions {
+    /// Trust the certificate authorities installed on the system. When none
+    /// can be found, a built-in set is used instead.
+    pub system_roots: bool,
+    /// PEM files of extra certificate authorities to trust.
+    pub ca_files: Vec<PathBuf>,
+    /// Accept any certificate. Connections are still encrypted, but anyone
+    /// between hyperline and the server can read and change them.
+    pub insecure: bool,
+}
+
+impl Default for TlsOptions {
+    fn default() -> Self {
+        TlsOptions {
+            system_roots: true,
+            ca_files: Vec::new(),
+            insecure: false,
+        }
+    }
+}
+
+pub type TlsStream = StreamOwned<ClientConnection, TcpStream>;
+
+/// Accepts every certificate, for `TlsOptions::insecure`. Handshake
+/// signatures are still checked, so the connection is at least consistent.
+#[derive(Debug)]
+struct AcceptAnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn root_store(options: &TlsOptions) -> Result<RootCertStore, String> {
+    let mut roots = RootCertStore::empty();
+
+    if options.system_roots {
+        let system = rustls_native_certs::load_native_certs();
+        roots.add_parsable_certificates(system.certs);
+
+        if roots.is_empty() {
+            roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        }
+    }
+
+    for path in &options.ca_files {
+        let certificates = CertificateDer::pem_file_iter(path)
+            .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+            .map_err(|error| format!("Could not read CA file {}: {}", path.display(), error))?;
+
+        if certificates.is_empty() {
+            return Err(format!("No certificates found in {}.", path.display()));
+        }
+
+        let (_, ignored) = roots.add_parsable_certificates(certificates);
+        if ignored > 0 {
+            return Err(format!("Invalid certificates in {}.", path.display()));
+        }
+    }
+
+    Ok(roots)
+}
+
+/// Builds the client configuration shared by every https connection.
+pub fn config(options: &TlsOptions) -> Result<Arc<ClientConfig>, String> {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|error| error.to_string())?;
+
+    let config = if options.insecure {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate(provider)))
+            .with_no_client_auth()
+    } else {
+        builder
+            .with_root_certificates(root_store(options)?)
+            .with_no_client_auth()
+    };
+
+    Ok(Arc::new(config))
+}
+
+/// Why a certificate was rejected, in words.
+fn describe(error: &CertificateError) -> String {
+    match error {
+        CertificateError::UnknownIssuer => "it was issued by an unknown authority".to_string(),
+        CertificateError::Expired | CertificateError::ExpiredContext { .. } => {
+            "it has expired".to_string()
+        }
+        CertificateError::NotValidYet | CertificateError::NotValidYetContext { .. } => {
+            "it is not valid yet".to_string()
+        }
+        CertificateError::NotValidForName | CertificateError::NotValidForNameContext { .. } => {
+            "it belongs to a different host".to_string()
+        }
+        CertificateError::Revoked => "it has been revoked".to_string(),
+        CertificateError::BadSignature => "its signature is invalid".to_string(),
+        CertificateError::BadEncoding => "it is malformed".to_string(),
+        error => format!("{:?}", error),
+    }
+}
+
+fn tls_error(host: &str, error: io::Error) -> HttpError {
+    let rustls_error = error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<rustls::Error>());
+
+    match rustls_error {
+        Some(rustls::Error::InvalidCertificate(reason)) => HttpError::Certificate {
+            host: host.to_string(),
+            reason: describe(reason),
+        },
+        Some(error) => HttpError::Tls(error.to_string()),
+        None if error.kind() == ErrorKind::TimedOut || error.kind() == ErrorKind::WouldBlock => {
+            HttpError::Timeout
+        }
+        None => HttpError::Io(error.to_string()),
+    }
+}
+
+/// Wraps `stream` in TLS for `host` and completes the handshake, so
+/// certificate problems are reported before anything is sent.
+pub fn connect(
+    config: Arc<ClientConfig>,
+    host: &str,
+    stream: TcpStream,
+) -> Result<TlsStream, HttpError> {
+    let name = ServerName::try_from(host.to_string())
+        .map_err(|_| HttpError::Tls(format!("'{}' is not a valid server name.", host)))?;
+    let connection =
+        ClientConnection::new(config, name).map_err(|error| HttpError::Tls(error.to_string()))?;
+
+    let mut stream = StreamOwned::new(connection, stream);
+    while stream.conn.is_handshaking() {
+        stream
+            .conn
+            .complete_io(&mut stream.sock)
+            .map_err(|error| tls_error(host, error))?;
+    }
+
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::path::Path;
+    use std::thread::{self, JoinHandle};
+
+    use rustls::pki_types::PrivateKeyDer;
+    use rustls::{ServerConfig, ServerConnection};
+
+    use super::*;
+    use crate::browser::fetch::LoadError;
+    use crate::browser::url::Url;
+    use crate::net::http::Client;
+
+    /// A test file: `ca.pem` is a CA that signed `server.pem` for `localhost` only.
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/net/testdata")
+            .join(name)
+    }
+
+    /// An https server on 127.0.0.1 that answers one connection. The
+    /// handshake may fail when the client rejects the certificate.
+    fn serve() -> (u16, JoinHandle<()>) {
+        let chain = CertificateDer::pem_file_iter(fixture("server.pem"))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let key = PrivateKeyDer::from_pem_file(fixture("server-key.pem")).unwrap();
+        let config =
+            ServerConfig::builder_with_provider(Arc::new(crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_no_client_auth()
+                .with_single_cert(chain, key)
+                .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let server = thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            let connection = ServerConnection::new(Arc::new(config)).unwrap();
+            let mut reader = BufReader::new(StreamOwned::new(connection, socket));
+
+            let mut line = String::new();
+            while reader.read_line(&mut line).is_ok_and(|read| read > 0) && line != "\r\n" {
+                line.clear();
+            }
+            let _ = reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\nsecret");
+            reader.get_mut().conn.send_close_notify();
+            let _ = reader.get_mut().flush();
+        });
+
+        (port, server)
+    }
+
+    fn get(options: TlsOptions, host: &str) -> Result<String, HttpError> {
+        let (port, server) = serve();
+        let url = Url::parse(&format!("https://{}:{}/", host, port)).unwrap();
+        let result = Client::with_tls(options).get(url).map(|r| r.text());
+        server.join().unwrap();
+        result
+    }
+
+    fn trusting_the_test_ca() -> TlsOptions {
+        TlsOptions {
+            system_roots: false,
+            ca_files: vec![fixture("ca.pem")],
+            insecure: false,
+        }
+    }
+
+    #[test]
+    fn trusts_an_extra_certificate_authority() {
+        assert_eq!(
+            get(trusting_the_test_ca(), "localhost"),
+            Ok("secret".into())
+        );
+    }
+
+    #[test]
+    fn rejects_an_unknown_issuer() {
+        let options = TlsOptions {
+            system_roots: false,
+            ..TlsOptions::default()
+        };
+        let error = get(options, "localhost").unwrap_err();
+        assert_eq!(
+            error,
+            HttpError::Certificate {
+                host: "localhost".into(),
+                reason: "it was issued by an unknown authority".into(),
+            }
+        );
+
+        let page = LoadError::Http(error).to_string();
+        assert!(page.starts_with(
+            "The certificate of localhost could not be verified: \
+             it was issued by an unknown authority."
+        ));
+        assert!(page.contains("--ca-file"));
+        assert!(page.contains("--insecure"));
+    }
+
+    #[test]
+    fn rejects_a_certificate_for_another_host() {
+        assert_eq!(
+            get(trusting_the_test_ca(), "127.0.0.1"),
+            Err(HttpError::Certificate {
+                host: "127.0.0.1".into(),
+                reason: "it belongs to a different host".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn insecure_accepts_any_certificate() {
+        let options = TlsOptions {
+            system_roots: false,
+            ca_files: Vec::new(),
+            insecure: true,
+        };
+        assert_eq!(get(options, "127.0.0.1"), Ok("secret".into()));
+    }
+
+    #[test]
+    fn ca_files_must_hold_certificates() {
+        let options = TlsOptions {
+            ca_files: vec![fixture("server-key.pem")],
+            ..trusting_the_test_ca()
+        };
+        let error = config(&options).unwrap_err();
+        assert!(error.starts_with("No certificates found in "), "{}", error);
+
+        let options = TlsOptions {
+            ca_files: vec![fixture("missing.pem")],
+            ..trusting_the_test_ca()
+        };
+        assert!(
+            config(&options)
+                .unwrap_err()
+                .starts_with("Could not read CA file ")
+        );
+    }
+}
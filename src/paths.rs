@@ -0,0 +1,28 @@
+use std::env;
+use std::path::PathBuf;
+
+/// A directory from `variable`, or `fallback` under the home directory,
+/// following the XDG base directory specification.
+fn xdg(variable: &str, fallback: &str) -> Option<PathBuf> {
+    let base = env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(fallback)))?;
+
+    Some(base.join("hyperline"))
+}
+
+/// Where hyperline keeps its settings, such as `~/.config/hyperline`.
+pub fn config_dir() -> Option<PathBuf> {
+    xdg("XDG_CONFIG_HOME", ".config")
+}
+
+/// Where hyperline keeps what it records, such as cookies and history.
+pub fn data_dir() -> Option<PathBuf> {
+    xdg("XDG_DATA_HOME", ".local/share")
+}
+
+/// Where hyperline keeps files it can download again, such as cached pages.
+pub fn cache_dir() -> Option<PathBuf> {
+    xdg("XDG_CACHE_HOME", ".cache")
+}
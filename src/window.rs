@@ -1,18 +1,67 @@
+use std::collections::HashMap;
 use std::io::{Stdout, Write};
 
+use termion::input::MouseTerminal;
 use termion::raw::{IntoRawMode, RawTerminal};
 
-use crate::common::{self, Vec2, compile_buffer};
-use crate::draw::border;
+use crate::common::{self, Rect, Vec2, compile_buffer};
+use crate::draw::border::{self, Border};
 use crate::draw::boxes::{self, BoxChar, draw_box};
+use crate::draw::content::{HorizontalAlign, Padding, VerticalAlign};
+use crate::draw::gradient::Gradient;
+use crate::draw::shadow::Shadow;
+use crate::draw::text::{Span, StyledLine, TextAttributes, TextStyle};
+use crate::draw::theme::{Role, Theme};
+use crate::layout::split::{self, Divider, Split, SplitDirection};
+use crate::layout::units::Sizing;
+use crate::layout::{Display, flex, grid};
 
 pub struct Container {
     pub size: Vec2,
     pub buffer: Vec<Vec<BoxChar>>,
 
-    pub stdout: RawTerminal<Stdout>,
+    pub stdout: MouseTerminal<RawTerminal<Stdout>>,
 
-    objects: Vec<DivOptions>,
+    /// How the tab bar is drawn once there is more than one tab.
+    pub tab_style: border::BorderStyle,
+    /// How the dividers between split panes are drawn.
+    pub divider_style: border::BorderStyle,
+    /// The colors of roles, which boxes and text given roles are drawn in.
+    /// Everything is drawn again in the new colors when it changes.
+    pub theme: Theme,
+
+    tabs: Vec<Tab>,
+    active: usize,
+
+    /// The boxes drawn in each pane, by pane id. Each pane is its own scene.
+    scenes: HashMap<usize, Vec<Node>>,
+    /// The pane new boxes go into, one of the active tab's.
+    target: usize,
+    next_pane: usize,
+    /// Whether anything changed since the buffer was last drawn. Changes
+    /// only mark it, and the next `render` draws everything once.
+    dirty: bool,
+}
+
+/// A tab of the container: panes splitting its area between them. Only the
+/// active tab is drawn.
+struct Tab {
+    title: String,
+    panes: Split,
+    focused: usize,
+}
+
+/// Rows taken by the tab bar: the top of the tabs' borders and their titles.
+const TAB_BAR_HEIGHT: usize = 2;
+/// Widest a tab is drawn, border included.
+const MAX_TAB_WIDTH: usize = 24;
+/// Narrowest a tab is drawn before tabs start scrolling off the bar.
+const MIN_TAB_WIDTH: usize = 10;
+
+/// A box drawn in the container, kept so the scene can be laid out again.
+struct Node {
+    options: DivOptions,
+    parent: Option<String>,
 }
 
 #[derive(Clone)]
@@ -21,77 +70,741 @@ pub struct DivOptions {
 
     pub position: Vec2<i16>,
     pub size: Vec2,
+    /// Relative sizes and anchors, which take precedence over `position` and `size`.
+    pub sizing: Sizing,
 
     pub border_options: border::BorderFlags,
     pub border_style: border::BorderStyle,
+    pub border_sides: border::BorderSides,
 
     pub border_color: Option<common::Color>,
     pub background_color: Option<common::Color>,
     pub text_color: Option<common::Color>,
 
-    pub content: Option<Vec<String>>,
+    /// Colors from the theme, which take precedence over the ones above.
+    /// Whatever is left unset takes the theme's border, background and text
+    /// colors, or text readable on the background role.
+    pub border_role: Option<Role>,
+    pub background_role: Option<Role>,
+    pub text_role: Option<Role>,
+
+    pub background_gradient: Option<Gradient>,
+    pub border_gradient: Option<Gradient>,
+
+    pub shadow: Option<Shadow>,
+
+    pub padding: Padding,
+    pub text_align: HorizontalAlign,
+    pub vertical_align: VerticalAlign,
+
+    /// How children drawn under this box are positioned.
+    pub display: Display,
+    /// Used when the parent box is a flex container.
+    pub flex: flex::FlexItem,
+    /// Used when the parent box is a grid container.
+    pub grid: grid::GridItem,
+
+    pub content: Option<Vec<StyledLine>>,
+}
+
+impl Default for DivOptions {
+    fn default() -> Self {
+        DivOptions {
+            id: None,
+
+            position: Vec2::new(0, 0),
+            size: Vec2::new(0, 0),
+            sizing: Sizing::default(),
+
+            border_options: border::BorderFlags::NONE,
+            border_style: border::BorderStyle::Sharp,
+            border_sides: border::BorderSides::default(),
+
+            border_color: None,
+            background_color: None,
+            text_color: None,
+
+            border_role: None,
+            background_role: None,
+            text_role: None,
+
+            background_gradient: None,
+            border_gradient: None,
+
+            shadow: None,
+
+            padding: Padding::default(),
+            text_align: HorizontalAlign::default(),
+            vertical_align: VerticalAlign::default(),
+
+            display: Display::default(),
+            flex: flex::FlexItem::default(),
+            grid: grid::GridItem::default(),
+
+            content: None,
+        }
+    }
 }
 
 impl Container {
     pub fn new(size: Vec2) -> Self {
-        let stdout = std::io::stdout().into_raw_mode().unwrap();
+        let stdout = MouseTerminal::from(std::io::stdout().into_raw_mode().unwrap());
 
-        let buffer = vec![vec![BoxChar::default(); size.x as usize]; size.y as usize];
+        let buffer = vec![vec![BoxChar::default(); size.x]; size.y];
 
         Container {
             size,
             buffer,
             stdout,
-            objects: Vec::new(),
+            tab_style: border::BorderStyle::Rounded,
+            divider_style: border::BorderStyle::Sharp,
+            theme: Theme::default(),
+            tabs: vec![Tab {
+                title: String::new(),
+                panes: Split::Pane(0),
+                focused: 0,
+            }],
+            active: 0,
+            scenes: HashMap::from([(0, Vec::new())]),
+            target: 0,
+            next_pane: 1,
+            dirty: true,
         }
     }
 
-    fn draw_under_size(&mut self, size: &Vec2, div_options: DivOptions) -> () {
-        let option_data = div_options.clone();
+    fn objects(&self) -> &[Node] {
+        self.scenes.get(&self.target).map_or(&[], Vec::as_slice)
+    }
 
-        let options = boxes::BoxOptions {
-            screen_size: size,
+    /// Makes an empty scene for a new pane and returns the pane's id.
+    fn new_pane(&mut self) -> usize {
+        let id = self.next_pane;
+        self.next_pane += 1;
+        self.scenes.insert(id, Vec::new());
+        id
+    }
+
+    pub fn tab_count(&self) -> usize {
+        self.tabs.len()
+    }
+
+    /// Index of the tab whose panes are drawn.
+    pub fn active_tab(&self) -> usize {
+        self.active
+    }
+
+    pub fn tab_title(&self, index: usize) -> Option<&str> {
+        self.tabs.get(index).map(|tab| tab.title.as_str())
+    }
+
+    pub fn set_tab_title(&mut self, index: usize, title: impl Into<String>) {
+        if let Some(tab) = self.tabs.get_mut(index) {
+            tab.title = title.into();
+        }
+        self.dirty = true;
+    }
+
+    /// Adds an empty tab after the active one and returns its index. The
+    /// active tab does not change.
+    pub fn open_tab(&mut self, title: impl Into<String>) -> usize {
+        let index = self.active + 1;
+        let pane = self.new_pane();
+        self.tabs.insert(
+            index,
+            Tab {
+                title: title.into(),
+                panes: Split::Pane(pane),
+                focused: pane,
+            },
+        );
+        self.dirty = true;
+        index
+    }
 
-            position: option_data.position,
-            size: option_data.size,
+    /// Removes the tab at `index` with its panes. The last tab cannot be closed.
+    pub fn close_tab(&mut self, index: usize) -> Result<(), String> {
+        if index >= self.tabs.len() {
+            return Err(format!("No tab {}.", index + 1));
+        }
+        if self.tabs.len() == 1 {
+            return Err("The last tab cannot be closed.".to_string());
+        }
+
+        let tab = self.tabs.remove(index);
+        for pane in tab.panes.panes() {
+            self.scenes.remove(&pane);
+        }
+        if self.active > index || self.active == self.tabs.len() {
+            self.active -= 1;
+        }
+        self.target = self.tabs[self.active].focused;
+        self.dirty = true;
+        Ok(())
+    }
 
-            border_options: option_data.border_options,
-            border_style: option_data.border_style,
+    pub fn select_tab(&mut self, index: usize) {
+        if index < self.tabs.len() {
+            self.active = index;
+            self.target = self.tabs[index].focused;
+            self.dirty = true;
+        }
+    }
 
-            border_color: option_data.border_color,
-            background_color: option_data.background_color,
-            text_color: option_data.text_color,
+    /// Moves the tab at `from` to `to`, shifting the tabs in between.
+    pub fn move_tab(&mut self, from: usize, to: usize) {
+        if from >= self.tabs.len() || to >= self.tabs.len() {
+            return;
+        }
 
-            content: option_data.content,
+        let tab = self.tabs.remove(from);
+        self.tabs.insert(to, tab);
+        self.active = match self.active {
+            active if active == from => to,
+            active if from < active && active <= to => active - 1,
+            active if to <= active && active < from => active + 1,
+            active => active,
         };
+        self.dirty = true;
+    }
+
+    /// Rows at the top of the container taken by the tab bar, which is only
+    /// shown once there is more than one tab.
+    fn tab_bar_height(&self) -> usize {
+        match self.tabs.len() {
+            1 => 0,
+            _ => TAB_BAR_HEIGHT.min(self.size.y),
+        }
+    }
+
+    /// Where the active tab's panes go: everything below the tab bar.
+    fn tab_area(&self) -> Rect {
+        let bar = self.tab_bar_height();
+        Rect {
+            position: Vec2::new(0, bar as i16),
+            size: Vec2::new(self.size.x, self.size.y - bar),
+        }
+    }
+
+    /// Where the boxes being drawn are laid out: the area of the pane they go
+    /// into. Positions of boxes in the scene are relative to it.
+    pub fn scene_area(&self) -> Rect {
+        self.pane_area(self.target)
+            .unwrap_or_else(|| self.tab_area())
+    }
+
+    /// The active tab's panes and where each of them is on screen.
+    pub fn panes(&self) -> Vec<(usize, Rect)> {
+        self.tabs[self.active].panes.layout(&self.tab_area()).0
+    }
+
+    fn dividers(&self) -> Vec<Divider> {
+        self.tabs[self.active].panes.layout(&self.tab_area()).1
+    }
+
+    pub fn pane_area(&self, pane: usize) -> Option<Rect> {
+        self.panes()
+            .into_iter()
+            .find(|(id, _)| *id == pane)
+            .map(|(_, rect)| rect)
+    }
+
+    /// The pane of the active tab drawn at `position`.
+    pub fn pane_at(&self, position: &Vec2) -> Option<usize> {
+        self.panes()
+            .into_iter()
+            .find(|(_, rect)| contains(rect, position))
+            .map(|(id, _)| id)
+    }
 
-        if div_options.id.is_some() {
-            self.objects.push(div_options.clone());
+    /// Ids of the panes of every tab.
+    pub fn pane_ids(&self) -> Vec<usize> {
+        self.tabs.iter().flat_map(|tab| tab.panes.panes()).collect()
+    }
+
+    /// The pane keys go to in the active tab.
+    pub fn focused_pane(&self) -> usize {
+        self.tabs[self.active].focused
+    }
+
+    /// The pane keys go to in the tab at `index`, whether it is active or not.
+    pub fn focused_pane_in(&self, index: usize) -> Option<usize> {
+        self.tabs.get(index).map(|tab| tab.focused)
+    }
+
+    /// Gives the keys to `pane`, which new boxes are then drawn in. Panes of
+    /// other tabs than the active one are ignored.
+    pub fn focus_pane(&mut self, pane: usize) {
+        if self.tabs[self.active].panes.panes().contains(&pane) {
+            self.tabs[self.active].focused = pane;
+            self.target = pane;
+        }
+    }
+
+    /// Draws new boxes in `pane` of the active tab, without focusing it.
+    pub fn draw_to(&mut self, pane: usize) {
+        if self.tabs[self.active].panes.panes().contains(&pane) {
+            self.target = pane;
+        }
+    }
+
+    /// Splits the focused pane in two, focuses the new half and returns its id.
+    pub fn split_pane(&mut self, direction: SplitDirection) -> usize {
+        let pane = self.new_pane();
+        let tab = &mut self.tabs[self.active];
+        tab.panes.split(tab.focused, direction, pane);
+        self.focus_pane(pane);
+        self.dirty = true;
+        pane
+    }
+
+    /// Removes `pane` from the active tab, giving its room to its neighbour.
+    /// The last pane of a tab cannot be closed.
+    pub fn close_pane(&mut self, pane: usize) -> Result<(), String> {
+        let panes = self.panes();
+        if panes.len() == 1 {
+            return Err("The last pane of a tab cannot be closed.".to_string());
         }
 
+        let next = [(-1, 0), (0, -1), (1, 0), (0, 1)]
+            .into_iter()
+            .find_map(|step| split::neighbour(&panes, pane, step));
+        if !self.tabs[self.active].panes.remove(pane) {
+            return Err("No such pane.".to_string());
+        }
+        self.scenes.remove(&pane);
+
+        let tab = &self.tabs[self.active];
+        if tab.focused == pane || self.target == pane {
+            let first = tab.panes.panes()[0];
+            self.focus_pane(next.unwrap_or(first));
+        }
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Focuses the pane next to the focused one in the direction of `step`,
+    /// such as (-1, 0) for the one to the left. Returns false when there is none.
+    pub fn focus_neighbour(&mut self, step: (i16, i16)) -> bool {
+        match split::neighbour(&self.panes(), self.focused_pane(), step) {
+            Some(pane) => {
+                self.focus_pane(pane);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the divider next to the focused pane that runs across
+    /// `direction` by `cells`. Returns false when there is no such divider.
+    pub fn resize_pane(&mut self, direction: SplitDirection, cells: isize) -> bool {
+        let area = self.tab_area();
+        let tab = &mut self.tabs[self.active];
+        let resized = tab.panes.resize(tab.focused, direction, cells, &area);
+        self.dirty = true;
+        resized
+    }
+
+    /// Draws the dividers between the active tab's panes, joining them where
+    /// they meet.
+    fn draw_dividers(&mut self) {
+        let mut cells = HashMap::new();
+        for divider in self.dividers() {
+            for i in 0..divider.length as i16 {
+                let cell = match divider.direction {
+                    SplitDirection::Row => (divider.position.x, divider.position.y + i),
+                    SplitDirection::Column => (divider.position.x + i, divider.position.y),
+                };
+                cells.insert(cell, ());
+            }
+        }
+
+        for &(x, y) in cells.keys() {
+            let mut arms = border::BorderFlags::NONE;
+            for (flag, (dx, dy)) in [
+                (border::BorderFlags::TOP, (0, -1)),
+                (border::BorderFlags::BOTTOM, (0, 1)),
+                (border::BorderFlags::LEFT, (-1, 0)),
+                (border::BorderFlags::RIGHT, (1, 0)),
+            ] {
+                if cells.contains_key(&(x + dx, y + dy)) {
+                    arms |= flag;
+                }
+            }
+
+            let glyph = self.divider_style.junction(arms).to_string();
+            if let Some(cell) = self
+                .buffer
+                .get_mut(y.max(0) as usize)
+                .and_then(|row| row.get_mut(x.max(0) as usize))
+            {
+                *cell = BoxChar {
+                    content: glyph,
+                    fg: Some(self.theme.border.clone()),
+                    bg: Some(self.theme.background.clone()),
+                    ..Default::default()
+                };
+            }
+        }
+    }
+
+    /// Where each tab shown on the bar is drawn. When the tabs do not fit,
+    /// only a run of them around the active one is shown.
+    fn tab_rects(&self) -> Vec<(usize, Rect)> {
+        if self.tab_bar_height() == 0 {
+            return Vec::new();
+        }
+
+        let count = self.tabs.len();
+        let width = (self.size.x / count).clamp(MIN_TAB_WIDTH, MAX_TAB_WIDTH);
+        let shown = (self.size.x / width).clamp(1, count);
+        let first = (self.active + 1).saturating_sub(shown).min(count - shown);
+
+        (first..first + shown)
+            .enumerate()
+            .map(|(slot, index)| {
+                let rect = Rect {
+                    position: Vec2::new((slot * width) as i16, 0),
+                    size: Vec2::new(width.min(self.size.x), self.tab_bar_height()),
+                };
+                (index, rect)
+            })
+            .collect()
+    }
+
+    /// Draws the tab bar: each tab's title in a box open at the bottom, the
+    /// active one in bold and the others dimmed.
+    fn draw_tab_bar(&mut self) {
+        for (index, rect) in self.tab_rects() {
+            let title = match self.tabs[index].title.as_str() {
+                "" => "New tab",
+                title => title,
+            };
+            let room = rect.size.x.saturating_sub(4);
+            let title = match title.chars().count() > room {
+                true => {
+                    title
+                        .chars()
+                        .take(room.saturating_sub(1))
+                        .collect::<String>()
+                        + "…"
+                }
+                false => title.to_string(),
+            };
+            let style = match index == self.active {
+                true => TextStyle {
+                    attributes: TextAttributes::BOLD,
+                    role: Some(Role::Focus),
+                    ..Default::default()
+                },
+                false => TextStyle {
+                    attributes: TextAttributes::DIM,
+                    ..Default::default()
+                },
+            };
+
+            let options = DivOptions {
+                border_options: border::BorderFlags::TOP
+                    | border::BorderFlags::LEFT
+                    | border::BorderFlags::RIGHT,
+                border_style: self.tab_style.clone(),
+                padding: Padding::symmetric(0, 1),
+                content: Some(vec![Span::new(title, style).into()]),
+                ..Default::default()
+            };
+            let clip = self.size.clone();
+            self.draw_in(&rect, &clip, options);
+        }
+    }
+
+    /// The tab whose title is drawn at `position` on the tab bar.
+    pub fn tab_at(&self, position: &Vec2) -> Option<usize> {
+        self.tab_rects()
+            .into_iter()
+            .find(|(_, rect)| contains(rect, position))
+            .map(|(index, _)| index)
+    }
+
+    fn draw_in(&mut self, rect: &Rect, clip: &Vec2, div_options: DivOptions) {
+        let theme = &self.theme;
+        let color = |role: Option<Role>| role.map(|role| theme.color(role).clone());
+
+        let border_color = color(div_options.border_role)
+            .or(div_options.border_color)
+            .unwrap_or(theme.border.clone());
+        let background_color = color(div_options.background_role)
+            .or(div_options.background_color)
+            .unwrap_or(theme.background.clone());
+        let text_color = color(div_options.text_role)
+            .or(div_options.text_color)
+            .or_else(|| {
+                div_options
+                    .background_role
+                    .map(|role| theme.text_on(role).clone())
+            })
+            .unwrap_or(theme.text.clone());
+
+        let content = div_options.content.map(|lines| {
+            lines
+                .iter()
+                .map(|line| {
+                    let styles = line
+                        .chars()
+                        .map(|(c, style)| (c, self.theme.resolve(style)))
+                        .collect::<Vec<_>>();
+                    StyledLine::from_chars(styles.iter().map(|(c, style)| (*c, style)))
+                })
+                .collect()
+        });
+
+        let options = boxes::BoxOptions {
+            screen_size: clip,
+
+            position: rect.position.clone(),
+            size: rect.size.clone(),
+
+            border_options: div_options.border_options,
+            border_style: div_options.border_style,
+            border_sides: div_options.border_sides,
+
+            border_color: Some(border_color),
+            background_color: Some(background_color),
+            text_color: Some(text_color),
+
+            background_gradient: div_options.background_gradient,
+            border_gradient: div_options.border_gradient,
+
+            shadow: div_options.shadow,
+
+            padding: div_options.padding,
+            text_align: div_options.text_align,
+            vertical_align: div_options.vertical_align,
+
+            content,
+        };
+
         draw_box(&mut self.buffer, options);
     }
 
-    pub fn draw_box(&mut self, div_options: DivOptions) -> () {
-        let size = self.size.clone();
-        self.draw_under_size(&size, div_options);
+    fn find(objects: &[Node], id: &String) -> Option<usize> {
+        objects
+            .iter()
+            .position(|node| node.options.id.as_ref() == Some(id))
+    }
+
+    /// The area inside a box's border and padding, where its children are laid out.
+    fn content_rect(options: &DivOptions, rect: &Rect) -> Rect {
+        let border = Border::new(
+            options.border_options,
+            &options.border_style,
+            &options.border_color,
+            &options.border_sides,
+        );
+
+        let (origin, size) = boxes::content_area(&border, &options.padding, &rect.size);
+
+        Rect {
+            position: Vec2::new(
+                rect.position.x + origin.x as i16,
+                rect.position.y + origin.y as i16,
+            ),
+            size,
+        }
+    }
+
+    /// Resolves where every box of a scene goes, and the far edge it is
+    /// clipped to. The scene is laid out as if it filled the container, then
+    /// moved to its place at `scene`.
+    fn layout(objects: &[Node], scene: &Rect) -> Vec<(Rect, Vec2)> {
+        let mut placed: Vec<(Rect, Vec2)> = Vec::with_capacity(objects.len());
+
+        // A parent is the first box with its id, and comes before its children.
+        let mut ids: HashMap<&String, usize> = HashMap::new();
+        let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+        // Where each box comes among its siblings.
+        let mut slots = vec![0; objects.len()];
+        for (i, node) in objects.iter().enumerate() {
+            if let Some(parent) = node.parent.as_ref().and_then(|id| ids.get(id)) {
+                let siblings = children.entry(*parent).or_default();
+                slots[i] = siblings.len();
+                siblings.push(i);
+            }
+            if let Some(id) = &node.options.id {
+                ids.entry(id).or_insert(i);
+            }
+        }
+        // The places of each parent's children, worked out together the
+        // first time one of them is reached.
+        let mut laid_out: HashMap<usize, Vec<Rect>> = HashMap::new();
+
+        for (i, node) in objects.iter().enumerate() {
+            let options = &node.options;
+            let parent = node.parent.as_ref().and_then(|id| ids.get(id)).copied();
+
+            let Some(parent_index) = parent else {
+                let screen = Rect {
+                    position: Vec2::new(0, 0),
+                    size: scene.size.clone(),
+                };
+                let rect = options
+                    .sizing
+                    .resolve(&screen, &options.position, &options.size);
+
+                placed.push((rect, scene.size.clone()));
+                continue;
+            };
+
+            let (parent_rect, parent_clip) = &placed[parent_index];
+            let far_edge =
+                |start: i16, length: usize| (start as isize + length as isize).max(0) as usize;
+            let clip = Vec2::new(
+                far_edge(parent_rect.position.x, parent_rect.size.x).min(parent_clip.x),
+                far_edge(parent_rect.position.y, parent_rect.size.y).min(parent_clip.y),
+            );
+
+            let siblings = &children[&parent_index];
+            let rects = laid_out.entry(parent_index).or_insert_with(|| {
+                let parent_options = &objects[parent_index].options;
+                let area = Container::content_rect(parent_options, parent_rect);
+                let item_size =
+                    |n: &Node| n.options.sizing.resolve_size(&area.size, &n.options.size);
+
+                match &parent_options.display {
+                    Display::Block => siblings
+                        .iter()
+                        .map(|index| {
+                            let options = &objects[*index].options;
+                            options
+                                .sizing
+                                .resolve(&area, &options.position, &options.size)
+                        })
+                        .collect(),
+                    Display::Flex(container) => {
+                        let items = siblings
+                            .iter()
+                            .map(|index| {
+                                let n = &objects[*index];
+                                (n.options.flex, item_size(n))
+                            })
+                            .collect::<Vec<_>>();
+                        flex::layout(&area, container, &items)
+                    }
+                    Display::Grid(container) => {
+                        let items = siblings
+                            .iter()
+                            .map(|index| {
+                                let n = &objects[*index];
+                                (n.options.grid.clone(), item_size(n))
+                            })
+                            .collect::<Vec<_>>();
+                        grid::layout(&area, container, &items)
+                    }
+                }
+            });
+            let rect = rects[slots[i]].clone();
+            let rect = Rect {
+                size: options.sizing.clamp(&rect.size),
+                ..rect
+            };
+
+            placed.push((rect, clip));
+        }
+
+        let (left, top) = (scene.position.x, scene.position.y);
+        placed
+            .into_iter()
+            .map(|(rect, clip)| {
+                let position = Vec2::new(rect.position.x + left, rect.position.y + top);
+                let clip = Vec2::new(clip.x + left as usize, clip.y + top as usize);
+                (Rect { position, ..rect }, clip)
+            })
+            .collect()
+    }
+
+    /// Switches to `theme`, drawing everything again in its colors.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        self.dirty = true;
+    }
+
+    /// Clears the buffer and draws every box again, re-running layouts.
+    pub fn redraw(&mut self) {
+        self.dirty = false;
+        let blank = BoxChar {
+            fg: Some(self.theme.text.clone()),
+            bg: Some(self.theme.background.clone()),
+            ..Default::default()
+        };
+        self.buffer = vec![vec![blank; self.size.x]; self.size.y];
+
+        self.draw_tab_bar();
+        self.draw_dividers();
+
+        for (pane, area) in self.panes() {
+            let Some(objects) = self.scenes.get(&pane) else {
+                continue;
+            };
+            let placed = Container::layout(objects, &area);
+            let options = objects
+                .iter()
+                .map(|node| node.options.clone())
+                .collect::<Vec<_>>();
+
+            for ((rect, clip), div_options) in placed.iter().zip(options) {
+                self.draw_in(rect, clip, div_options);
+            }
+        }
+    }
+
+    /// Changes the size of the container and lays the scenes out again.
+    pub fn resize(&mut self, size: Vec2) {
+        self.size = size;
+        self.dirty = true;
+    }
+
+    pub fn draw_box(&mut self, div_options: DivOptions) {
+        self.scenes.entry(self.target).or_default().push(Node {
+            options: div_options,
+            parent: None,
+        });
+        self.dirty = true;
     }
 
     pub fn draw_box_under(&mut self, id: &String, div_options: DivOptions) -> Result<(), String> {
-        if let Some(res) = self.objects.iter().find(|x| x.id.as_ref() == Some(id)) {
-            let size = res.size.clone();
-            self.draw_under_size(&size, div_options);
+        if Container::find(self.objects(), id).is_some() {
+            self.scenes.entry(self.target).or_default().push(Node {
+                options: div_options,
+                parent: Some(id.clone()),
+            });
+            self.dirty = true;
             Ok(())
         } else {
             Err(format!("No object with id '{}' found.", id))
         }
     }
 
+    /// Target of the link drawn at `position` when last rendered, if there is one.
+    pub fn link_at(&self, position: &Vec2) -> Option<&str> {
+        self.buffer
+            .get(position.y)?
+            .get(position.x)?
+            .link
+            .as_deref()
+    }
+
+    /// The widget drawn at `position` when last rendered, if there is one.
+    pub fn widget_at(&self, position: &Vec2) -> Option<usize> {
+        self.buffer.get(position.y)?.get(position.x)?.widget
+    }
+
     fn make_render(&mut self) -> String {
         compile_buffer(&self.buffer)
     }
 
-    pub fn render(&mut self) -> () {
+    pub fn render(&mut self) {
+        if self.dirty {
+            self.redraw();
+        }
+
         write!(self.stdout, "{}", termion::clear::All).unwrap();
         write!(self.stdout, "{}", termion::cursor::Goto(1, 1)).unwrap();
 
@@ -101,8 +814,96 @@ impl Container {
         self.stdout.flush().unwrap();
     }
 
+    /// Removes every box from the scene of the pane being drawn to.
     pub fn clear(&mut self) -> Result<(), String> {
-        self.buffer = vec![vec![BoxChar::default(); self.size.x as usize]; self.size.y as usize];
+        self.scenes.entry(self.target).or_default().clear();
+        self.dirty = true;
         Ok(())
     }
 }
+
+fn contains(rect: &Rect, position: &Vec2) -> bool {
+    let (x, y) = (rect.position.x as usize, rect.position.y as usize);
+    (x..x + rect.size.x).contains(&position.x) && (y..y + rect.size.y).contains(&position.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: Option<&str>, parent: Option<&str>, options: DivOptions) -> Node {
+        Node {
+            options: DivOptions {
+                id: id.map(str::to_string),
+                ..options
+            },
+            parent: parent.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn children_of_a_flex_box_are_laid_out_together() {
+        let item = DivOptions {
+            size: Vec2::new(5, 1),
+            ..Default::default()
+        };
+        let objects = vec![
+            node(
+                Some("row"),
+                None,
+                DivOptions {
+                    position: Vec2::new(2, 1),
+                    size: Vec2::new(20, 3),
+                    display: Display::Flex(flex::FlexContainer {
+                        gap: 1,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            ),
+            node(None, Some("row"), item.clone()),
+            node(Some("middle"), Some("row"), item.clone()),
+            node(None, None, item.clone()),
+            node(None, Some("row"), item.clone()),
+            node(None, Some("middle"), item),
+        ];
+        let scene = Rect {
+            position: Vec2::new(0, 1),
+            size: Vec2::new(40, 10),
+        };
+
+        let placed = Container::layout(&objects, &scene);
+        let positions = placed
+            .iter()
+            .map(|(rect, _)| (rect.position.x, rect.position.y))
+            .collect::<Vec<_>>();
+        // The scene starts a row down. The row's items follow each other with
+        // a gap, whatever else is drawn between them, while a block child
+        // keeps the position it was given.
+        assert_eq!(
+            positions,
+            vec![(2, 2), (2, 2), (8, 2), (0, 1), (14, 2), (0, 1)]
+        );
+        assert_eq!(placed[1].1, Vec2::new(22, 5));
+    }
+
+    #[test]
+    fn a_missing_parent_places_the_box_in_the_scene() {
+        let objects = vec![node(
+            None,
+            Some("nowhere"),
+            DivOptions {
+                position: Vec2::new(3, 4),
+                size: Vec2::new(2, 2),
+                ..Default::default()
+            },
+        )];
+        let scene = Rect {
+            position: Vec2::new(0, 0),
+            size: Vec2::new(10, 10),
+        };
+
+        let placed = Container::layout(&objects, &scene);
+        assert_eq!(placed[0].0.position, Vec2::new(3, 4));
+    }
+}